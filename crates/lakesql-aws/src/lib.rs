@@ -3,12 +3,14 @@
 //! Real AWS Lake Formation implementation for production usage.
 
 use aws_config::{BehaviorVersion, Region};
-use aws_sdk_lakeformation::{Client, Config};
+use aws_sdk_lakeformation::Client;
 use aws_sdk_lakeformation::types::{
-    DataLakeSettings, DataLakePrincipal, Resource as LfResource,
-    Permission as LfPermission, LfTag as AwsLfTag
+    DataLakePrincipal, Resource as LfResource,
+    Permission as LfPermission, LfTagKeyResource, PrincipalResourcePermissions,
+    BatchPermissionsRequestEntry
 };
 use lakesql_core::*;
+use lakesql_parser::DdlStatement;
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use std::collections::HashMap;
@@ -17,6 +19,8 @@ use std::collections::HashMap;
 pub struct AwsBackend {
     client: Client,
     region: String,
+    /// Union new grants with the principal's existing actions on the resource
+    merge_grants: bool,
 }
 
 impl AwsBackend {
@@ -46,14 +50,14 @@ impl AwsBackend {
         let aws_config = loader.load().await;
 
         // Create Lake Formation client
-        let mut lf_config = Config::from(&aws_config);
+        let mut lf_config = aws_sdk_lakeformation::config::Builder::from(&aws_config);
         
         // Set custom endpoint if provided (for LocalStack testing)
         if let Some(endpoint) = endpoint {
             lf_config = lf_config.endpoint_url(endpoint);
         }
 
-        let client = Client::from_conf(lf_config);
+        let client = Client::from_conf(lf_config.build());
         
         let region_name = aws_config
             .region()
@@ -63,41 +67,172 @@ impl AwsBackend {
         Ok(Self {
             client,
             region: region_name,
+            merge_grants: false,
         })
     }
-}
 
-#[async_trait]
-impl LakeFormationBackend for AwsBackend {
-    async fn execute_ddl(&mut self, sql: &str) -> Result<DdlResult> {
-        // Parse the SQL and route to appropriate method
-        let parsed = lakesql_parser::parse_ddl(sql)?;
-        
-        match parsed {
-            DdlStatement::Grant { permission } => {
+    /// Merge each grant with the actions the principal already holds on the
+    /// resource, so the grant call carries the full effective set, matching
+    /// the emulator's one-permission-per-principal/resource model
+    pub fn set_merge_grants(&mut self, merge: bool) {
+        self.merge_grants = merge;
+    }
+
+    /// Actions the principal currently holds directly on the resource
+    async fn existing_actions(&self, principal: &Principal, resource: &Resource) -> Result<Vec<Action>> {
+        let response = self.client
+            .list_permissions()
+            .principal(convert_principal(principal)?)
+            .resource(convert_resource(resource)?)
+            .send()
+            .await?;
+
+        let mut actions = Vec::new();
+        for entry in response.principal_resource_permissions.unwrap_or_default() {
+            if let Some(permission) = convert_aws_entry_to_permission(principal, &entry)? {
+                if permission.resource == *resource {
+                    actions = merge_actions(&actions, &permission.actions);
+                }
+            }
+        }
+        Ok(actions)
+    }
+
+    /// Snapshot the live permissions held by each of the given principals.
+    /// Tagged principals can't be listed directly and are skipped.
+    pub async fn snapshot_permissions(&self, principals: &[Principal]) -> Result<Vec<Permission>> {
+        let mut permissions = Vec::new();
+        for principal in principals {
+            if matches!(principal, Principal::TaggedPrincipal { .. }) {
+                continue;
+            }
+            permissions.extend(self.list_permissions_for_principal(principal).await?);
+        }
+        Ok(permissions)
+    }
+
+    /// Route a parsed statement to the matching Lake Formation call. The match
+    /// lists every statement kind, so a new one won't compile until it is
+    /// routed here or marked unsupported.
+    pub async fn execute_statement(&mut self, statement: DdlStatement) -> Result<DdlResult> {
+        match statement {
+            DdlStatement::Grant { .. } => {
+                let permission = statement.to_permission()?;
                 self.grant_permissions(permission).await
             }
             DdlStatement::Revoke { principal, resource, actions } => {
                 self.revoke_permissions(&principal, &resource, &actions).await
             }
-            DdlStatement::CreateRole { role_name, .. } => {
+            DdlStatement::CreateRole { name } => {
                 // Lake Formation doesn't have explicit role creation
                 // Roles are implicit when first used
                 Ok(DdlResult::Success {
-                    message: format!("Role '{}' will be created implicitly when first used", role_name),
-                    rows_affected: 0,
+                    message: format!("Role '{}' will be created implicitly when first used", name),
+                })
+            }
+            DdlStatement::CreateRoles { names } => {
+                Ok(DdlResult::Success {
+                    message: format!("Roles {} will be created implicitly when first used", names.join(", ")),
                 })
             }
-            DdlStatement::CreateTag { tag } => {
-                self.create_tag(tag).await
+            DdlStatement::CreateTag { name, values } => {
+                self.create_tag(LfTag { key: name, values, description: None }).await
+            }
+            DdlStatement::DropTag { name } => {
+                self.delete_tag(&name).await
+            }
+            DdlStatement::DropRole { name } => {
+                self.drop_role(&name).await
             }
-            DdlStatement::DropTag { tag_key } => {
-                self.delete_tag(&tag_key).await
+            DdlStatement::GrantRole { .. } => Err(unsupported("GRANT ROLE")),
+            DdlStatement::Deny { .. } => Err(unsupported("DENY")),
+            DdlStatement::AlterRole { .. } => Err(unsupported("ALTER ROLE")),
+            DdlStatement::RegisterLocation { .. } => Err(unsupported("REGISTER LOCATION")),
+            DdlStatement::RegisterTable { .. } => Err(unsupported("REGISTER TABLE")),
+            DdlStatement::SetAccessMode { .. } => Err(unsupported("SET ACCESS MODE")),
+            DdlStatement::DefineGroup { .. } => Err(unsupported("DEFINE GROUP")),
+            DdlStatement::DefineTemplate { .. } => Err(unsupported("DEFINE TEMPLATE")),
+            DdlStatement::ApplyTemplate { .. } => Err(unsupported("APPLY TEMPLATE")),
+            DdlStatement::ShowPermissions { .. } => Err(unsupported("SHOW PERMISSIONS")),
+            DdlStatement::ShowRoles => Err(unsupported("SHOW ROLES")),
+            DdlStatement::ShowTags => Err(unsupported("SHOW TAGS")),
+        }
+    }
+
+    /// Drop a role by revoking every grant it holds. Lake Formation has no
+    /// role objects to delete, so this mirrors the emulator's DROP ROLE cascade.
+    pub async fn drop_role(&mut self, role: &str) -> Result<DdlResult> {
+        let principal = Principal::Role(role.to_string());
+        let permissions = self.list_permissions_for_principal(&principal).await?;
+        let entries = revoke_entries(&permissions)?;
+
+        for chunk in entries.chunks(BATCH_PERMISSIONS_LIMIT) {
+            let response = self.client
+                .batch_revoke_permissions()
+                .set_entries(Some(chunk.to_vec()))
+                .send()
+                .await
+                .map_err(|e| anyhow!("Failed to revoke grants for role '{}': {}", role, e))?;
+
+            if let Some(failure) = response.failures.unwrap_or_default().into_iter().next() {
+                let reason = failure.error
+                    .and_then(|e| e.error_message)
+                    .unwrap_or_else(|| "unknown error".to_string());
+                return Err(anyhow!("Failed to revoke grants for role '{}': {}", role, reason));
             }
         }
+
+        tracing::info!(role = %role, revoked = entries.len(), "dropped role in Lake Formation");
+        Ok(DdlResult::Success {
+            message: format!("Dropped role: {} ({} grant(s) revoked)", role, entries.len()),
+        })
+    }
+}
+
+/// Error for a statement the emulator understands but Lake Formation has no
+/// equivalent for
+fn unsupported(statement: &str) -> anyhow::Error {
+    anyhow!("{} is not supported by the AWS backend", statement)
+}
+
+/// Maximum entries accepted by a single BatchGrant/BatchRevokePermissions call
+const BATCH_PERMISSIONS_LIMIT: usize = 20;
+
+/// Batch request entries revoking each permission in full
+fn revoke_entries(permissions: &[Permission]) -> Result<Vec<BatchPermissionsRequestEntry>> {
+    permissions
+        .iter()
+        .enumerate()
+        .map(|(i, permission)| {
+            let entry = BatchPermissionsRequestEntry::builder()
+                .id(i.to_string())
+                .principal(convert_principal(&permission.principal)?)
+                .resource(convert_resource(&permission.resource)?)
+                .set_permissions(Some(convert_actions(&permission.actions)));
+            let entry = if permission.grant_option() {
+                entry.set_permissions_with_grant_option(Some(convert_grantable_actions(permission)))
+            } else {
+                entry
+            };
+            entry.build().map_err(|e| anyhow!("Failed to build revoke entry: {}", e))
+        })
+        .collect()
+}
+
+#[async_trait]
+impl LakeFormationBackend for AwsBackend {
+    async fn execute_ddl(&mut self, sql: &str) -> Result<DdlResult> {
+        // Parse the SQL and route to appropriate method
+        let statement = lakesql_parser::parse_ddl(sql)?;
+        self.execute_statement(statement).await
     }
 
-    async fn grant_permissions(&mut self, permission: Permission) -> Result<DdlResult> {
+    async fn grant_permissions(&mut self, mut permission: Permission) -> Result<DdlResult> {
+        if self.merge_grants {
+            let existing = self.existing_actions(&permission.principal, &permission.resource).await?;
+            permission.actions = merge_actions(&existing, &permission.actions);
+        }
+
         let principal = convert_principal(&permission.principal)?;
         let resource = convert_resource(&permission.resource)?;
         let permissions = convert_actions(&permission.actions);
@@ -109,17 +244,24 @@ impl LakeFormationBackend for AwsBackend {
             .set_permissions(Some(permissions));
 
         // Add grant option if specified
-        let request = if permission.grant_option {
-            request.set_permissions_with_grant_option(Some(convert_actions(&permission.actions)))
+        let request = if permission.grant_option() {
+            request.set_permissions_with_grant_option(Some(convert_grantable_actions(&permission)))
         } else {
             request
         };
 
         match request.send().await {
-            Ok(_) => Ok(DdlResult::Success {
-                message: format!("Granted permissions successfully"),
-                rows_affected: 1,
-            }),
+            Ok(_) => {
+                tracing::info!(
+                    principal = ?permission.principal,
+                    resource = ?permission.resource,
+                    actions = ?permission.actions,
+                    "granted permissions in Lake Formation"
+                );
+                Ok(DdlResult::Success {
+                    message: "Granted permissions successfully".to_string(),
+                })
+            },
             Err(e) => Err(anyhow!("Failed to grant permissions: {}", e)),
         }
     }
@@ -142,10 +284,17 @@ impl LakeFormationBackend for AwsBackend {
             .send()
             .await
         {
-            Ok(_) => Ok(DdlResult::Success {
-                message: format!("Revoked permissions successfully"),
-                rows_affected: 1,
-            }),
+            Ok(_) => {
+                tracing::info!(
+                    principal = ?principal,
+                    resource = ?resource,
+                    actions = ?actions,
+                    "revoked permissions in Lake Formation"
+                );
+                Ok(DdlResult::Success {
+                    message: "Revoked permissions successfully".to_string(),
+                })
+            },
             Err(e) => Err(anyhow!("Failed to revoke permissions: {}", e)),
         }
     }
@@ -157,7 +306,6 @@ impl LakeFormationBackend for AwsBackend {
         action: &Action,
     ) -> Result<bool> {
         let aws_principal = convert_principal(principal)?;
-        let aws_resource = convert_resource(resource)?;
 
         let response = self.client
             .get_effective_permissions_for_path()
@@ -166,7 +314,7 @@ impl LakeFormationBackend for AwsBackend {
             .await?;
 
         // Check if the principal has the required permission
-        if let Some(permissions) = response.permissions_by_principal {
+        if let Some(permissions) = response.permissions {
             for permission_entry in permissions {
                 if is_principal_match(&permission_entry.principal, &aws_principal) {
                     if let Some(perms) = permission_entry.permissions {
@@ -184,12 +332,6 @@ impl LakeFormationBackend for AwsBackend {
     }
 
     async fn create_tag(&mut self, tag: LfTag) -> Result<DdlResult> {
-        let aws_tag = AwsLfTag::builder()
-            .tag_key(&tag.key)
-            .set_tag_values(Some(tag.values))
-            .build()
-            .map_err(|e| anyhow!("Failed to build LF-Tag: {}", e))?;
-
         match self.client
             .create_lf_tag()
             .tag_key(&tag.key)
@@ -199,7 +341,6 @@ impl LakeFormationBackend for AwsBackend {
         {
             Ok(_) => Ok(DdlResult::Success {
                 message: format!("Created LF-Tag '{}' successfully", tag.key),
-                rows_affected: 1,
             }),
             Err(e) => Err(anyhow!("Failed to create LF-Tag: {}", e)),
         }
@@ -214,7 +355,6 @@ impl LakeFormationBackend for AwsBackend {
         {
             Ok(_) => Ok(DdlResult::Success {
                 message: format!("Deleted LF-Tag '{}' successfully", tag_key),
-                rows_affected: 1,
             }),
             Err(e) => Err(anyhow!("Failed to delete LF-Tag: {}", e)),
         }
@@ -236,23 +376,8 @@ impl LakeFormationBackend for AwsBackend {
         
         if let Some(principal_resource_permissions) = response.principal_resource_permissions {
             for perm_entry in principal_resource_permissions {
-                if let Some(resource) = perm_entry.resource {
-                    if let Some(perms) = perm_entry.permissions {
-                        let actions: Vec<Action> = perms
-                            .iter()
-                            .filter_map(|p| convert_aws_permission_to_action(p))
-                            .collect();
-
-                        if !actions.is_empty() {
-                            permissions.push(Permission {
-                                principal: principal.clone(),
-                                resource: convert_aws_resource_to_resource(&resource)?,
-                                actions,
-                                grant_option: perm_entry.permissions_with_grant_option.is_some(),
-                                row_filter: None,
-                            });
-                        }
-                    }
+                if let Some(permission) = convert_aws_entry_to_permission(principal, &perm_entry)? {
+                    permissions.push(permission);
                 }
             }
         }
@@ -271,13 +396,13 @@ impl LakeFormationBackend for AwsBackend {
 
         let mut permissions = Vec::new();
 
-        if let Some(permissions_by_principal) = response.permissions_by_principal {
+        if let Some(permissions_by_principal) = response.permissions {
             for perm_entry in permissions_by_principal {
                 if let Some(principal) = perm_entry.principal {
                     if let Some(perms) = perm_entry.permissions {
                         let actions: Vec<Action> = perms
                             .iter()
-                            .filter_map(|p| convert_aws_permission_to_action(p))
+                            .filter_map(convert_aws_permission_to_action)
                             .collect();
 
                         if !actions.is_empty() {
@@ -285,8 +410,16 @@ impl LakeFormationBackend for AwsBackend {
                                 principal: convert_aws_principal_to_principal(&principal)?,
                                 resource: resource.clone(),
                                 actions,
-                                grant_option: false, // TODO: Check grant options properly
+                                grantable_actions: perm_entry.permissions_with_grant_option
+                                    .iter()
+                                    .flatten()
+                                    .filter_map(convert_aws_permission_to_action)
+                                    .collect(),
                                 row_filter: None,
+                                principal_condition: None,
+                                comment: None,
+                                expires_at: None,
+                                effect: Effect::Allow,
                             });
                         }
                     }
@@ -331,29 +464,63 @@ fn convert_principal(principal: &Principal) -> Result<DataLakePrincipal> {
 
 fn convert_resource(resource: &Resource) -> Result<LfResource> {
     match resource {
-        Resource::Database { name } => {
+        Resource::Database { name, catalog_id } => {
             Ok(LfResource::builder()
                 .database(
                     aws_sdk_lakeformation::types::DatabaseResource::builder()
+                        .set_catalog_id(catalog_id.clone())
                         .name(name)
                         .build()
                         .map_err(|e| anyhow!("Failed to build database resource: {}", e))?
                 )
                 .build())
         }
-        Resource::Table { database, table, columns } => {
-            let table_resource = aws_sdk_lakeformation::types::TableResource::builder()
+        // Column grants are a separate resource type in Lake Formation
+        Resource::Table { database, table, columns: Some(cols), catalog_id } => {
+            let table_resource = aws_sdk_lakeformation::types::TableWithColumnsResource::builder()
+                .set_catalog_id(catalog_id.clone())
                 .database_name(database)
-                .name(table);
+                .name(table)
+                .set_column_names(Some(cols.clone()))
+                .build()
+                .map_err(|e| anyhow!("Failed to build table with columns resource: {}", e))?;
 
-            let table_resource = if let Some(cols) = columns {
-                table_resource.set_column_names(Some(cols.clone()))
-            } else {
-                table_resource
-            };
+            Ok(LfResource::builder()
+                .table_with_columns(table_resource)
+                .build())
+        }
+        Resource::Table { database, table, columns: None, catalog_id } => {
+            let table_resource = aws_sdk_lakeformation::types::TableResource::builder()
+                .set_catalog_id(catalog_id.clone())
+                .database_name(database)
+                .name(table)
+                .build()
+                .map_err(|e| anyhow!("Failed to build table resource: {}", e))?;
 
             Ok(LfResource::builder()
-                .table(table_resource.build().map_err(|e| anyhow!("Failed to build table resource: {}", e))?)
+                .table(table_resource)
+                .build())
+        }
+        Resource::AllTables { database, catalog_id } => {
+            let table_resource = aws_sdk_lakeformation::types::TableResource::builder()
+                .set_catalog_id(catalog_id.clone())
+                .database_name(database)
+                .table_wildcard(aws_sdk_lakeformation::types::TableWildcard::builder().build())
+                .build()
+                .map_err(|e| anyhow!("Failed to build table wildcard resource: {}", e))?;
+            Ok(LfResource::builder()
+                .table(table_resource)
+                .build())
+        }
+        // Lake Formation addresses Glue views as tables
+        Resource::View { database, view } => {
+            let table_resource = aws_sdk_lakeformation::types::TableResource::builder()
+                .database_name(database)
+                .name(view)
+                .build()
+                .map_err(|e| anyhow!("Failed to build view resource: {}", e))?;
+            Ok(LfResource::builder()
+                .table(table_resource)
                 .build())
         }
         Resource::DataLocation { path } => {
@@ -369,18 +536,47 @@ fn convert_resource(resource: &Resource) -> Result<LfResource> {
         Resource::TaggedResource { .. } => {
             Err(anyhow!("Tagged resources not yet supported in AWS backend"))
         }
+        Resource::LfTag { key } => {
+            Ok(LfResource::builder()
+                .lf_tag(
+                    LfTagKeyResource::builder()
+                        .tag_key(key)
+                        // Permissions on the key cover all of its values
+                        .tag_values("*")
+                        .build()
+                        .map_err(|e| anyhow!("Failed to build LF-Tag resource: {}", e))?
+                )
+                .build())
+        }
     }
 }
 
+/// Lake Formation permissions for the actions granted WITH GRANT OPTION,
+/// in the order they appear in the grant
+fn convert_grantable_actions(permission: &Permission) -> Vec<LfPermission> {
+    let grantable: Vec<Action> = permission.actions
+        .iter()
+        .filter(|action| permission.grantable_actions.contains(action))
+        .cloned()
+        .collect();
+    convert_actions(&grantable)
+}
+
 fn convert_actions(actions: &[Action]) -> Vec<LfPermission> {
-    actions.iter().map(|action| match action {
-        Action::Select => LfPermission::Select,
-        Action::Insert => LfPermission::Insert,
-        Action::Update => LfPermission::Insert, // Lake Formation doesn't have UPDATE
-        Action::Delete => LfPermission::Delete,
-        Action::Create => LfPermission::CreateTable,
-        Action::Alter => LfPermission::Alter,
-        Action::Drop => LfPermission::Drop,
+    actions.iter().filter_map(|action| match action {
+        Action::Select => Some(LfPermission::Select),
+        Action::Insert => Some(LfPermission::Insert),
+        Action::Update => Some(LfPermission::Insert), // Lake Formation doesn't have UPDATE
+        Action::Delete => Some(LfPermission::Delete),
+        Action::CreateTable => Some(LfPermission::CreateTable),
+        Action::AlterTable => Some(LfPermission::Alter),
+        Action::DropTable => Some(LfPermission::Drop),
+        Action::Describe => Some(LfPermission::Describe),
+        Action::DataLocationAccess => Some(LfPermission::DataLocationAccess),
+        Action::Associate => Some(LfPermission::Associate),
+        Action::All => Some(LfPermission::All),
+        // Expressed through PermissionsWithGrantOption rather than as a permission
+        Action::GrantWithGrantOption => None,
     }).collect()
 }
 
@@ -407,49 +603,113 @@ fn convert_aws_principal_to_principal(aws_principal: &DataLakePrincipal) -> Resu
 fn convert_aws_resource_to_resource(aws_resource: &LfResource) -> Result<Resource> {
     if let Some(db) = &aws_resource.database {
         Ok(Resource::Database {
-            name: db.name.clone().unwrap_or_default(),
+            name: db.name.clone(),
+            catalog_id: db.catalog_id.clone(),
+        })
+    } else if let Some(table) = aws_resource.table.as_ref().filter(|t| t.table_wildcard.is_some()) {
+        Ok(Resource::AllTables {
+            database: table.database_name.clone(),
+            catalog_id: table.catalog_id.clone(),
         })
     } else if let Some(table) = &aws_resource.table {
         Ok(Resource::Table {
-            database: table.database_name.clone().unwrap_or_default(),
+            database: table.database_name.clone(),
             table: table.name.clone().unwrap_or_default(),
+            columns: None,
+            catalog_id: table.catalog_id.clone(),
+        })
+    } else if let Some(table) = &aws_resource.table_with_columns {
+        Ok(Resource::Table {
+            database: table.database_name.clone(),
+            table: table.name.clone(),
             columns: table.column_names.clone(),
+            catalog_id: table.catalog_id.clone(),
         })
     } else if let Some(data_loc) = &aws_resource.data_location {
         Ok(Resource::DataLocation {
-            path: data_loc.resource_arn.clone().unwrap_or_default(),
+            path: data_loc.resource_arn.clone(),
+        })
+    } else if let Some(lf_tag) = &aws_resource.lf_tag {
+        Ok(Resource::LfTag {
+            key: lf_tag.tag_key.clone(),
         })
     } else {
         Err(anyhow!("Unsupported AWS resource type"))
     }
 }
 
+/// Convert a ListPermissions entry into a Permission for the given principal.
+/// Returns None when the entry carries no actions we can represent.
+fn convert_aws_entry_to_permission(
+    principal: &Principal,
+    entry: &PrincipalResourcePermissions,
+) -> Result<Option<Permission>> {
+    let (Some(resource), Some(perms)) = (&entry.resource, &entry.permissions) else {
+        return Ok(None);
+    };
+
+    let actions: Vec<Action> = perms
+        .iter()
+        .filter_map(convert_aws_permission_to_action)
+        .collect();
+
+    if actions.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(Permission {
+        principal: principal.clone(),
+        resource: convert_aws_resource_to_resource(resource)?,
+        actions,
+        grantable_actions: entry.permissions_with_grant_option
+            .iter()
+            .flatten()
+            .filter_map(convert_aws_permission_to_action)
+            .collect(),
+        row_filter: None,
+        principal_condition: None,
+        comment: None,
+        expires_at: None,
+        effect: Effect::Allow,
+    }))
+}
+
 fn convert_aws_permission_to_action(aws_perm: &LfPermission) -> Option<Action> {
     match aws_perm {
         LfPermission::Select => Some(Action::Select),
         LfPermission::Insert => Some(Action::Insert),
         LfPermission::Delete => Some(Action::Delete),
-        LfPermission::CreateTable => Some(Action::Create),
-        LfPermission::Alter => Some(Action::Alter),
-        LfPermission::Drop => Some(Action::Drop),
+        LfPermission::CreateTable => Some(Action::CreateTable),
+        LfPermission::Alter => Some(Action::AlterTable),
+        LfPermission::Drop => Some(Action::DropTable),
+        LfPermission::Describe => Some(Action::Describe),
+        LfPermission::DataLocationAccess => Some(Action::DataLocationAccess),
+        LfPermission::Associate => Some(Action::Associate),
+        LfPermission::All => Some(Action::All),
         _ => None,
     }
 }
 
 fn get_resource_arn(resource: &Resource, region: &str) -> Result<String> {
     match resource {
-        Resource::Database { name } => {
+        Resource::Database { name, .. } => {
             Ok(format!("arn:aws:lakeformation:{}:*:database/{}", region, name))
         }
-        Resource::Table { database, table, .. } => {
+        Resource::Table { database, table, .. } | Resource::View { database, view: table } => {
             Ok(format!("arn:aws:lakeformation:{}:*:table/{}/{}", region, database, table))
         }
+        Resource::AllTables { database, .. } => {
+            Ok(format!("arn:aws:lakeformation:{}:*:table/{}/*", region, database))
+        }
         Resource::DataLocation { path } => {
             Ok(path.clone())
         }
         Resource::TaggedResource { .. } => {
             Err(anyhow!("Tagged resources not supported for ARN generation"))
         }
+        Resource::LfTag { .. } => {
+            Err(anyhow!("LF-Tag resources have no ARN"))
+        }
     }
 }
 
@@ -464,15 +724,30 @@ fn is_principal_match(
     }
 }
 
+/// Union of two action lists, keeping first-seen order
+fn merge_actions(existing: &[Action], new: &[Action]) -> Vec<Action> {
+    let mut merged = existing.to_vec();
+    for action in new {
+        if !merged.contains(action) {
+            merged.push(action.clone());
+        }
+    }
+    merged
+}
+
 fn is_action_match(aws_permission: &LfPermission, target_action: &Action) -> bool {
     matches!(
         (aws_permission, target_action),
         (LfPermission::Select, Action::Select) |
         (LfPermission::Insert, Action::Insert) |
         (LfPermission::Delete, Action::Delete) |
-        (LfPermission::CreateTable, Action::Create) |
-        (LfPermission::Alter, Action::Alter) |
-        (LfPermission::Drop, Action::Drop)
+        (LfPermission::CreateTable, Action::CreateTable) |
+        (LfPermission::Alter, Action::AlterTable) |
+        (LfPermission::Drop, Action::DropTable) |
+        (LfPermission::Describe, Action::Describe) |
+        (LfPermission::DataLocationAccess, Action::DataLocationAccess) |
+        (LfPermission::Associate, Action::Associate) |
+        (LfPermission::All, _)
     )
 }
 
@@ -483,4 +758,301 @@ pub async fn create_aws_backend(
     endpoint: Option<String>,
 ) -> Result<AwsBackend> {
     AwsBackend::with_config(region, profile, endpoint).await
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_lakeformation::types::DatabaseResource;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_describe_converts_to_aws() {
+        let converted = convert_actions(&[Action::Describe]);
+        assert_eq!(converted, vec![LfPermission::Describe]);
+    }
+
+    #[test]
+    fn test_describe_converts_from_aws() {
+        assert_eq!(
+            convert_aws_permission_to_action(&LfPermission::Describe),
+            Some(Action::Describe)
+        );
+    }
+
+    #[test]
+    fn test_describe_round_trip() {
+        let actions = vec![Action::Select, Action::Describe, Action::AlterTable];
+        let round_tripped: Vec<Action> = convert_actions(&actions)
+            .iter()
+            .filter_map(convert_aws_permission_to_action)
+            .collect();
+        assert_eq!(round_tripped, actions);
+    }
+
+    #[test]
+    fn test_database_describe_grant_survives_import() {
+        let principal = Principal::Role("arn:aws:iam::123456789012:role/analyst".to_string());
+        let entry = PrincipalResourcePermissions::builder()
+            .resource(
+                LfResource::builder()
+                    .database(DatabaseResource::builder().name("sales").build().unwrap())
+                    .build()
+            )
+            .permissions(LfPermission::Describe)
+            .build();
+
+        let permission = convert_aws_entry_to_permission(&principal, &entry)
+            .unwrap()
+            .expect("Describe grant should be imported");
+
+        assert_eq!(permission.principal, principal);
+        assert_eq!(permission.resource, Resource::Database { name: "sales".to_string(), catalog_id: None });
+        assert_eq!(permission.actions, vec![Action::Describe]);
+    }
+
+    #[test]
+    fn test_merge_actions_unions_without_duplicates() {
+        let merged = merge_actions(&[Action::Select, Action::Describe], &[Action::Insert, Action::Select]);
+        assert_eq!(merged, vec![Action::Select, Action::Describe, Action::Insert]);
+    }
+
+    /// Requires LocalStack with Lake Formation; run with
+    /// `LAKESQL_LOCALSTACK_ENDPOINT=http://localhost:4566 cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore]
+    async fn test_merge_grants_coalesces_against_localstack() {
+        let endpoint = std::env::var("LAKESQL_LOCALSTACK_ENDPOINT")
+            .unwrap_or_else(|_| "http://localhost:4566".to_string());
+        let mut backend = AwsBackend::with_config(Some("us-east-1".to_string()), None, Some(endpoint))
+            .await
+            .unwrap();
+        backend.set_merge_grants(true);
+
+        let principal = Principal::Role("arn:aws:iam::000000000000:role/analyst".to_string());
+        let resource = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        for action in [Action::Select, Action::Insert] {
+            backend.grant_permissions(Permission::new(principal.clone(), resource.clone(), vec![action])).await.unwrap();
+        }
+
+        let mut actions = backend.existing_actions(&principal, &resource).await.unwrap();
+        actions.sort_by_key(|a| format!("{:?}", a));
+        assert_eq!(actions, vec![Action::Insert, Action::Select]);
+    }
+
+    #[test]
+    fn test_database_catalog_id_is_set_when_present() {
+        let resource = Resource::Database {
+            name: "sales".to_string(),
+            catalog_id: Some("123456789012".to_string()),
+        };
+        let converted = convert_resource(&resource).unwrap();
+        let database = converted.database.unwrap();
+        assert_eq!(database.catalog_id.as_deref(), Some("123456789012"));
+        assert_eq!(database.name, "sales");
+    }
+
+    #[test]
+    fn test_table_catalog_id_is_set_when_present() {
+        let resource = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: Some("123456789012".to_string()),
+        };
+        let converted = convert_resource(&resource).unwrap();
+        let table = converted.table.unwrap();
+        assert_eq!(table.catalog_id.as_deref(), Some("123456789012"));
+        assert_eq!(table.database_name, "sales");
+    }
+
+    #[test]
+    fn test_catalog_id_is_omitted_when_absent() {
+        let database = convert_resource(&Resource::Database {
+            name: "sales".to_string(),
+            catalog_id: None,
+        }).unwrap();
+        assert_eq!(database.database.unwrap().catalog_id, None);
+
+        let table = convert_resource(&Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        }).unwrap();
+        assert_eq!(table.table.unwrap().catalog_id, None);
+    }
+
+    #[test]
+    fn test_column_grant_round_trips_as_table_with_columns() {
+        let resource = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: Some(vec!["id".to_string(), "region".to_string()]),
+            catalog_id: None,
+        };
+        let converted = convert_resource(&resource).unwrap();
+        assert!(converted.table.is_none());
+        assert_eq!(converted.table_with_columns.as_ref().unwrap().name, "orders");
+        assert_eq!(convert_aws_resource_to_resource(&converted).unwrap(), resource);
+    }
+
+    #[test]
+    fn test_all_tables_round_trips_as_table_wildcard() {
+        let resource = Resource::AllTables { database: "sales".to_string(), catalog_id: None };
+        let converted = convert_resource(&resource).unwrap();
+        assert!(converted.table.as_ref().unwrap().table_wildcard.is_some());
+        assert_eq!(convert_aws_resource_to_resource(&converted).unwrap(), resource);
+    }
+
+    #[tokio::test]
+    async fn test_statements_without_lake_formation_equivalent_are_rejected() {
+        // Rejected before any request is sent, so no endpoint is needed
+        let mut backend = AwsBackend::with_config(
+            Some("us-east-1".to_string()),
+            None,
+            Some("http://localhost:4566".to_string()),
+        ).await.unwrap();
+
+        for sql in [
+            "DEFINE GROUP analysts AS ('alice')",
+            "ALTER ROLE analyst ADD USER 'alice'",
+            "SET ACCESS MODE IAM_ONLY ON DATABASE sales",
+            "SHOW ROLES",
+        ] {
+            let error = backend.execute_ddl(sql).await.unwrap_err().to_string();
+            assert!(error.contains("not supported by the AWS backend"), "{}: {}", sql, error);
+        }
+
+        match backend.execute_ddl("CREATE ROLES analyst, engineer").await.unwrap() {
+            DdlResult::Success { message } => assert!(message.contains("analyst, engineer")),
+            other => panic!("Expected success, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_view_converts_to_table_resource() {
+        let resource = Resource::View { database: "sales".to_string(), view: "monthly".to_string() };
+        let table = convert_resource(&resource).unwrap().table.unwrap();
+        assert_eq!(table.database_name, "sales");
+        assert_eq!(table.name.as_deref(), Some("monthly"));
+        assert_eq!(get_resource_arn(&resource, "us-east-1").unwrap(), "arn:aws:lakeformation:us-east-1:*:table/sales/monthly");
+    }
+
+    #[test]
+    fn test_lf_tag_resource_round_trip() {
+        let resource = Resource::LfTag { key: "classification".to_string() };
+        let converted = convert_resource(&resource).unwrap();
+        let lf_tag = converted.lf_tag.as_ref().unwrap();
+        assert_eq!(lf_tag.tag_key, "classification");
+        assert_eq!(lf_tag.tag_values, vec!["*".to_string()]);
+
+        assert_eq!(convert_aws_resource_to_resource(&converted).unwrap(), resource);
+        assert_eq!(convert_actions(&[Action::Associate]), vec![LfPermission::Associate]);
+        assert_eq!(convert_aws_permission_to_action(&LfPermission::Associate), Some(Action::Associate));
+    }
+
+    #[test]
+    fn test_revoke_entries_cover_each_grant() {
+        let role = Principal::Role("arn:aws:iam::123456789012:role/analyst".to_string());
+        let permissions = vec![
+            Permission::new(
+                role.clone(),
+                Resource::Database { name: "sales".to_string(), catalog_id: None },
+                vec![Action::Describe],
+            ),
+            Permission {
+                grantable_actions: HashSet::from([Action::Select]),
+                ..Permission::new(
+                    role,
+                    Resource::Table {
+                        database: "sales".to_string(),
+                        table: "orders".to_string(),
+                        columns: None,
+                        catalog_id: None,
+                    },
+                    vec![Action::Select, Action::Insert],
+                )
+            },
+        ];
+
+        let entries = revoke_entries(&permissions).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].id, "0");
+        assert_eq!(entries[0].permissions, Some(vec![LfPermission::Describe]));
+        assert_eq!(entries[0].permissions_with_grant_option, None);
+        assert_eq!(entries[1].permissions, Some(vec![LfPermission::Insert, LfPermission::Select]));
+        assert_eq!(entries[1].permissions_with_grant_option, Some(vec![LfPermission::Select]));
+    }
+
+    /// Requires LocalStack with Lake Formation; run with
+    /// `LAKESQL_LOCALSTACK_ENDPOINT=http://localhost:4566 cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore]
+    async fn test_drop_role_revokes_grants_against_localstack() {
+        let endpoint = std::env::var("LAKESQL_LOCALSTACK_ENDPOINT")
+            .unwrap_or_else(|_| "http://localhost:4566".to_string());
+        let mut backend = AwsBackend::with_config(Some("us-east-1".to_string()), None, Some(endpoint))
+            .await
+            .unwrap();
+
+        let role = "arn:aws:iam::000000000000:role/analyst";
+        let principal = Principal::Role(role.to_string());
+        backend.grant_permissions(Permission::new(
+            principal.clone(),
+            Resource::Table {
+                database: "sales".to_string(),
+                table: "orders".to_string(),
+                columns: None,
+                catalog_id: None,
+            },
+            vec![Action::Select],
+        )).await.unwrap();
+        assert!(!backend.list_permissions_for_principal(&principal).await.unwrap().is_empty());
+
+        backend.drop_role(role).await.unwrap();
+        assert!(backend.list_permissions_for_principal(&principal).await.unwrap().is_empty());
+    }
+
+    /// Requires LocalStack with Lake Formation; run with
+    /// `LAKESQL_LOCALSTACK_ENDPOINT=http://localhost:4566 cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore]
+    async fn test_apply_diff_converges_against_localstack() {
+        use lakesql_emulator::EmulatorState;
+
+        let endpoint = std::env::var("LAKESQL_LOCALSTACK_ENDPOINT")
+            .unwrap_or_else(|_| "http://localhost:4566".to_string());
+        let mut backend = AwsBackend::with_config(Some("us-east-1".to_string()), None, Some(endpoint))
+            .await
+            .unwrap();
+
+        let principal = Principal::Role("arn:aws:iam::000000000000:role/reconciler".to_string());
+        let table = |name: &str| Resource::Table {
+            database: "sales".to_string(),
+            table: name.to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        let grant = |resource: Resource, actions: Vec<Action>| Permission::new(principal.clone(), resource, actions);
+
+        // Live: SELECT, INSERT on orders; desired: SELECT on orders and customers
+        backend.grant_permissions(grant(table("orders"), vec![Action::Select, Action::Insert])).await.unwrap();
+        let desired = EmulatorState::from_permissions(vec![
+            grant(table("orders"), vec![Action::Select]),
+            grant(table("customers"), vec![Action::Select]),
+        ]);
+
+        let seeds = [principal.clone()];
+        let live = EmulatorState::from_permissions(backend.snapshot_permissions(&seeds).await.unwrap());
+        live.diff(&desired).apply(&mut backend).await.unwrap();
+
+        let converged = EmulatorState::from_permissions(backend.snapshot_permissions(&seeds).await.unwrap());
+        assert!(converged.diff(&desired).is_empty());
+    }
+}
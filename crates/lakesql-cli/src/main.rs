@@ -1,8 +1,8 @@
 use lakesql_core::*;
-use lakesql_emulator::EmulatorBackend;
+use lakesql_emulator::{AuditLog, EmulatorBackend, GroupBy};
 use clap::{Parser, Subcommand};
 use anyhow::Result;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Parser)]
 #[command(name = "lakesql")]
@@ -14,6 +14,15 @@ struct Cli {
     #[arg(short, long)]
     /// State file for persistence (optional)
     state_file: Option<String>,
+
+    /// Allow statements that remove grants wholesale (DROP ROLE, REVOKE ALL,
+    /// and revokes made by apply)
+    #[arg(long, global = true, alias = "yes")]
+    confirm_destructive: bool,
+
+    /// Append an audit entry for every executed statement to this JSON-lines file
+    #[arg(long, global = true)]
+    audit_log: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -40,12 +49,74 @@ enum Commands {
         #[arg(short, long)]
         action: String,
     },
+    /// Evaluate a row filter expression against a sample row
+    TestFilter {
+        /// Filter expression (e.g. "region = SESSION_CONTEXT('user_region')")
+        #[arg(short, long)]
+        expression: String,
+        /// Row column as key=value; repeat for more columns
+        #[arg(short, long)]
+        row: Vec<String>,
+        /// Session context entry as key=value; repeat for more entries
+        #[arg(short, long)]
+        session: Vec<String>,
+    },
     /// Show current state
-    Status,
+    Status {
+        /// Group permissions by resource, principal or action
+        #[arg(long, default_value = "resource")]
+        group_by: String,
+        /// Print the grouped permissions as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check state for dangling tag references, malformed members and duplicates
+    ValidateState,
+    /// Flag risky grants: PUBLIC, broad database grants, ALL and WITH GRANT OPTION
+    RiskReport,
     /// Export state
     Export {
         #[arg(short, long)]
-        format: Option<String>, // "sql" or "summary"
+        format: Option<String>, // "sql", "csv" or "summary"
+        /// Only export what touches this principal (e.g. "ROLE analyst")
+        #[arg(short, long)]
+        principal: Option<String>,
+    },
+    /// Print the audit log given with --audit-log
+    Audit {
+        #[arg(short, long)]
+        format: Option<String>, // "text" or "jsonl"
+    },
+    /// Compare a local state file against live Lake Formation permissions
+    #[cfg(feature = "aws")]
+    Drift {
+        /// Local state file holding the declared policy
+        #[arg(long)]
+        state: String,
+        /// Extra principals to snapshot (e.g. "ROLE arn:aws:iam::123456789012:role/analyst");
+        /// principals granted in the local state are always included
+        #[arg(short, long)]
+        principal: Vec<String>,
+        /// AWS region
+        #[arg(long)]
+        region: Option<String>,
+    },
+    /// Converge live Lake Formation permissions to a desired-state DDL file
+    #[cfg(feature = "aws")]
+    Apply {
+        /// DDL script declaring the desired grants
+        #[arg(short, long)]
+        file: String,
+        /// Extra principals to converge (e.g. "ROLE arn:aws:iam::123456789012:role/analyst");
+        /// principals granted in the file are always included
+        #[arg(short, long)]
+        principal: Vec<String>,
+        /// AWS region
+        #[arg(long)]
+        region: Option<String>,
+        /// Print the grants and revokes without applying them
+        #[arg(long)]
+        dry_run: bool,
     },
 }
 
@@ -53,7 +124,16 @@ enum Commands {
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    // Library crates log through tracing; print their events to the terminal
+    tracing_subscriber::fmt()
+        .with_max_level(tracing_subscriber::filter::LevelFilter::INFO)
+        .with_target(false)
+        .without_time()
+        .init();
+
     let mut backend = EmulatorBackend::new(cli.state_file).await?;
+    backend.set_allow_destructive(cli.confirm_destructive);
+    backend.set_audit_log(cli.audit_log.clone());
 
     match cli.command {
         Commands::Execute { sql } => {
@@ -77,12 +157,38 @@ async fn main() -> Result<()> {
             check_permission(&backend, &principal, &resource, &action).await?;
         },
         
-        Commands::Status => {
-            show_status(&backend).await?;
+        Commands::TestFilter { expression, row, session } => {
+            test_filter(&backend, &expression, &row, &session)?;
+        },
+
+        Commands::Status { group_by, json } => {
+            show_status(&backend, group_by.parse()?, json).await?;
+        },
+
+        Commands::ValidateState => {
+            validate_state(&backend)?;
+        },
+
+        Commands::RiskReport => {
+            risk_report(&backend);
         },
         
-        Commands::Export { format } => {
-            export_state(&backend, format.as_deref().unwrap_or("summary")).await?;
+        Commands::Export { format, principal } => {
+            export_state(&backend, format.as_deref().unwrap_or("summary"), principal.as_deref()).await?;
+        },
+
+        Commands::Audit { format } => {
+            show_audit_log(cli.audit_log.as_deref(), format.as_deref().unwrap_or("text")).await?;
+        },
+
+        #[cfg(feature = "aws")]
+        Commands::Drift { state, principal, region } => {
+            show_drift(&state, &principal, region).await?;
+        },
+
+        #[cfg(feature = "aws")]
+        Commands::Apply { file, principal, region, dry_run } => {
+            apply_desired_state(&file, &principal, region, dry_run, cli.confirm_destructive).await?;
         },
     }
 
@@ -109,9 +215,10 @@ async fn execute_statement(backend: &mut EmulatorBackend, sql: &str) -> Result<(
                 },
             }
         },
-        Err(e) => {
-            println!("❌ Execution failed: {}", e);
-        }
+        Err(e) => match e.downcast_ref::<lakesql_parser::ParseError>() {
+            Some(parse_error) => println!("❌ Syntax error: {}", parse_error),
+            None => println!("❌ Execution failed: {}", e),
+        },
     }
     
     Ok(())
@@ -169,7 +276,7 @@ async fn run_demo(backend: &mut EmulatorBackend) -> Result<()> {
     }
 
     println!("🎉 Demo complete! Current state:");
-    show_status(backend).await?;
+    show_status(backend, GroupBy::Resource, false).await?;
     
     println!("\n🧪 Testing permission checks:");
     
@@ -214,13 +321,19 @@ async fn run_row_level_security_demo(backend: &mut EmulatorBackend) -> Result<()
             database: "sales".to_string(),
             table: "orders".to_string(),
             columns: None,
+            catalog_id: None,
         },
         actions: vec![Action::Select],
-        grant_option: false,
+        grantable_actions: HashSet::new(),
         row_filter: Some(RowFilter {
             expression: "region = SESSION_CONTEXT('user_region')".to_string(),
             session_context: None,
+            ast: None,
         }),
+        principal_condition: None,
+        comment: None,
+        expires_at: None,
+        effect: Effect::Allow,
     };
 
     let department_permission = Permission {
@@ -229,13 +342,19 @@ async fn run_row_level_security_demo(backend: &mut EmulatorBackend) -> Result<()
             database: "hr".to_string(),
             table: "employees".to_string(),
             columns: None,
+            catalog_id: None,
         },
         actions: vec![Action::Select],
-        grant_option: false,
+        grantable_actions: HashSet::new(),
         row_filter: Some(RowFilter {
             expression: "department = SESSION_CONTEXT('user_department') AND region = SESSION_CONTEXT('user_region')".to_string(),
             session_context: None,
+            ast: None,
         }),
+        principal_condition: None,
+        comment: None,
+        expires_at: None,
+        effect: Effect::Allow,
     };
 
     // Grant permissions directly
@@ -334,8 +453,39 @@ async fn check_permission(backend: &EmulatorBackend, principal_str: &str, resour
     Ok(())
 }
 
-async fn show_status(backend: &EmulatorBackend) -> Result<()> {
+fn test_filter(backend: &EmulatorBackend, expression: &str, row: &[String], session: &[String]) -> Result<()> {
+    let row = parse_key_values(row)?;
+    let session = parse_key_values(session)?;
+
+    match backend.evaluate_filter(expression, row, session) {
+        Ok(true) => println!("✅ Row passes filter: {}", expression),
+        Ok(false) => println!("❌ Row filtered out: {}", expression),
+        Err(e) => println!("⚠️  Cannot evaluate filter: {}", e),
+    }
+
+    Ok(())
+}
+
+/// Parse `key=value` arguments into a map
+fn parse_key_values(pairs: &[String]) -> Result<HashMap<String, String>> {
+    pairs
+        .iter()
+        .map(|pair| {
+            pair.split_once('=')
+                .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+                .ok_or_else(|| anyhow::anyhow!("Expected key=value, got: {}", pair))
+        })
+        .collect()
+}
+
+async fn show_status(backend: &EmulatorBackend, group_by: GroupBy, json: bool) -> Result<()> {
     let state = backend.get_state();
+    let groups = state.group_permissions(group_by);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&groups)?);
+        return Ok(());
+    }
     
     println!("📊 **Lake Formation Emulator Status**");
     println!("====================================");
@@ -359,25 +509,92 @@ async fn show_status(backend: &EmulatorBackend) -> Result<()> {
     }
     
     if !state.permissions.is_empty() {
-        println!("\n🔐 **Permissions:**");
-        for (i, permission) in state.permissions.iter().enumerate() {
-            let filter_info = if permission.row_filter.is_some() { " [ROW-LEVEL]" } else { "" };
-            println!("  {}. {:?} → {:?} → {:?}{}", 
-                i + 1, permission.principal, permission.actions, permission.resource, filter_info);
+        println!("\n🔐 **Permissions by {:?}:**", group_by);
+        for group in &groups {
+            println!("  {} ({})", group.key, group.count);
+            for permission in &group.permissions {
+                let filter_info = if permission.row_filter.is_some() { " [ROW-LEVEL]" } else { "" };
+                println!("    • {:?} → {:?} → {:?}{}",
+                    permission.principal, permission.actions, permission.resource, filter_info);
+            }
         }
     }
     
     Ok(())
 }
 
-async fn export_state(backend: &EmulatorBackend, format: &str) -> Result<()> {
+fn validate_state(backend: &EmulatorBackend) -> Result<()> {
+    let issues = backend.validate_state()?;
+
+    if issues.is_empty() {
+        println!("✅ State is consistent");
+    } else {
+        println!("⚠️  Found {} issue(s):", issues.len());
+        for issue in &issues {
+            println!("  • {}", issue);
+        }
+    }
+
+    Ok(())
+}
+
+fn risk_report(backend: &EmulatorBackend) {
     let state = backend.get_state();
+    let findings = state.risk_report();
+
+    if findings.is_empty() {
+        println!("✅ No risky grants found");
+        return;
+    }
+
+    println!("⚠️  Found {} risky grant(s):", findings.len());
+    for finding in &findings {
+        let permission = &state.permissions[finding.permission_index];
+        println!("  • {}: {:?} on {:?} to {:?}",
+            finding, permission.actions, permission.resource, permission.principal);
+    }
+}
+
+async fn show_audit_log(path: Option<&str>, format: &str) -> Result<()> {
+    let Some(path) = path else {
+        return Err(anyhow::anyhow!("No audit log given; pass --audit-log <file>"));
+    };
+    let log = AuditLog::load(path).await?;
+
+    match format {
+        "jsonl" => print!("{}", log.to_jsonl()),
+        "text" => {
+            println!("📜 {} audit entr{}:", log.entries.len(), if log.entries.len() == 1 { "y" } else { "ies" });
+            for entry in &log.entries {
+                let status = if entry.success { "✅" } else { "❌" };
+                println!("  {} [{}] {} → {}", status, entry.timestamp, entry.sql, entry.result);
+            }
+        },
+        other => return Err(anyhow::anyhow!("Unknown audit format '{}', expected text or jsonl", other)),
+    }
+
+    Ok(())
+}
+
+async fn export_state(backend: &EmulatorBackend, format: &str, principal: Option<&str>) -> Result<()> {
+    let subset;
+    let state = match principal {
+        Some(principal) => {
+            subset = backend.get_state().subset_for_principal(&parse_principal(principal)?);
+            &subset
+        },
+        None => backend.get_state(),
+    };
     
     match format {
         "sql" => {
             let sql = lakesql_emulator::storage::StateExporter::to_sql_ddl(state);
             println!("{}", sql);
         },
+        "csv" => {
+            let csv = lakesql_emulator::storage::StateExporter::to_csv(state);
+            print!("{}", csv);
+        },
         "summary" | _ => {
             let summary = lakesql_emulator::storage::StateExporter::to_summary(state);
             println!("{}", summary);
@@ -387,6 +604,109 @@ async fn export_state(backend: &EmulatorBackend, format: &str) -> Result<()> {
     Ok(())
 }
 
+#[cfg(feature = "aws")]
+async fn show_drift(state_path: &str, principals: &[String], region: Option<String>) -> Result<()> {
+    use lakesql_emulator::{EmulatorState, FileStorage, Storage};
+
+    let local = FileStorage::new(state_path.to_string()).load().await?;
+
+    let mut seeds = principals
+        .iter()
+        .map(|p| parse_principal(p))
+        .collect::<Result<Vec<_>>>()?;
+    for permission in &local.permissions {
+        if !seeds.contains(&permission.principal) {
+            seeds.push(permission.principal.clone());
+        }
+    }
+
+    let aws = lakesql_aws::create_aws_backend(region, None, None).await?;
+    let live = EmulatorState::from_permissions(aws.snapshot_permissions(&seeds).await?);
+    let diff = local.diff(&live);
+
+    if diff.is_empty() {
+        println!("✅ No drift: AWS matches {}", state_path);
+        return Ok(());
+    }
+
+    if !diff.removed.is_empty() {
+        println!("➖ Declared in {} but missing in AWS:", state_path);
+        for permission in &diff.removed {
+            println!("  • {:?} → {:?} → {:?}", permission.principal, permission.actions, permission.resource);
+        }
+    }
+    if !diff.added.is_empty() {
+        println!("➕ Present in AWS but not declared:");
+        for permission in &diff.added {
+            println!("  • {:?} → {:?} → {:?}", permission.principal, permission.actions, permission.resource);
+        }
+    }
+
+    Ok(())
+}
+
+/// Import the desired-state file, snapshot AWS for the principals involved,
+/// and grant/revoke the difference
+#[cfg(feature = "aws")]
+async fn apply_desired_state(
+    file: &str,
+    principals: &[String],
+    region: Option<String>,
+    dry_run: bool,
+    confirm_destructive: bool,
+) -> Result<()> {
+    use lakesql_emulator::EmulatorState;
+
+    let sql = std::fs::read_to_string(file)?;
+    let mut desired = EmulatorBackend::new(None).await?;
+    for statement in lakesql_parser::parse_ddl_script(&sql)? {
+        desired.execute_ddl_direct(statement).await?;
+    }
+    let desired = desired.get_state();
+
+    let mut seeds = principals
+        .iter()
+        .map(|p| parse_principal(p))
+        .collect::<Result<Vec<_>>>()?;
+    for permission in &desired.permissions {
+        if !seeds.contains(&permission.principal) {
+            seeds.push(permission.principal.clone());
+        }
+    }
+
+    let mut aws = lakesql_aws::create_aws_backend(region, None, None).await?;
+    let live = EmulatorState::from_permissions(aws.snapshot_permissions(&seeds).await?);
+    let plan = live.diff(desired);
+
+    if plan.is_empty() {
+        println!("✅ AWS already matches {}", file);
+        return Ok(());
+    }
+
+    println!("📋 Plan to converge AWS to {}:", file);
+    for permission in &plan.added {
+        println!("  ➕ GRANT {:?} → {:?} → {:?}", permission.actions, permission.resource, permission.principal);
+    }
+    for permission in &plan.removed {
+        println!("  ➖ REVOKE {:?} → {:?} → {:?}", permission.actions, permission.resource, permission.principal);
+    }
+
+    if dry_run {
+        println!("💡 Dry run: nothing applied");
+        return Ok(());
+    }
+    if !plan.removed.is_empty() && !confirm_destructive {
+        return Err(anyhow::anyhow!(
+            "Plan revokes {} grant(s); re-run with --confirm-destructive to apply it",
+            plan.removed.len()
+        ));
+    }
+
+    let results = plan.apply(&mut aws).await?;
+    println!("✅ Applied {} change(s)", results.len());
+    Ok(())
+}
+
 // Helper parsing functions
 fn parse_principal(s: &str) -> Result<Principal> {
     let parts: Vec<&str> = s.split_whitespace().collect();
@@ -402,6 +722,19 @@ fn parse_resource(s: &str) -> Result<Resource> {
     if s.starts_with("DATABASE ") {
         Ok(Resource::Database {
             name: s.strip_prefix("DATABASE ").unwrap().to_string(),
+            catalog_id: None,
+        })
+    } else if let Some(view) = s.strip_prefix("VIEW ") {
+        let (database, view) = view
+            .split_once('.')
+            .ok_or_else(|| anyhow::anyhow!("Invalid view format: {}", s))?;
+        Ok(Resource::View {
+            database: database.to_string(),
+            view: view.to_string(),
+        })
+    } else if let Some(key) = s.strip_prefix("LF-TAG ") {
+        Ok(Resource::LfTag {
+            key: key.to_string(),
         })
     } else if s.contains('.') {
         let parts: Vec<&str> = s.split('.').collect();
@@ -409,6 +742,7 @@ fn parse_resource(s: &str) -> Result<Resource> {
             database: parts[0].to_string(),
             table: parts[1].to_string(),
             columns: None,
+            catalog_id: None,
         })
     } else {
         Err(anyhow::anyhow!("Invalid resource format: {}", s))
@@ -416,17 +750,18 @@ fn parse_resource(s: &str) -> Result<Resource> {
 }
 
 fn parse_action(s: &str) -> Result<Action> {
-    match s.to_uppercase().as_str() {
-        "SELECT" => Ok(Action::Select),
-        "INSERT" => Ok(Action::Insert),
-        "UPDATE" => Ok(Action::Update),
-        "DELETE" => Ok(Action::Delete),
-        "CREATE_TABLE" => Ok(Action::CreateTable),
-        "DROP_TABLE" => Ok(Action::DropTable),
-        "ALTER_TABLE" => Ok(Action::AlterTable),
-        "DESCRIBE" => Ok(Action::Describe),
-        _ => Err(anyhow::anyhow!("Invalid action: {}", s)),
-    }
+    // Accept `create table` and `CREATE_TABLE` alike
+    let name = s.split(|c: char| c == '_' || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+        .to_uppercase();
+    Action::CONCRETE
+        .iter()
+        .chain([&Action::All])
+        .find(|action| action.to_ddl_keyword() == name)
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("Invalid action: {}", s))
 }
 
 fn create_session_context(data: Vec<(&str, &str)>) -> HashMap<String, String> {
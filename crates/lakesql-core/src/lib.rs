@@ -13,6 +13,7 @@ pub use backend::*;
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashSet;
 
     #[test] 
     fn test_basic_permission() {
@@ -22,13 +23,213 @@ mod tests {
                 database: "sales".to_string(),
                 table: "orders".to_string(),
                 columns: None,
+                catalog_id: None,
             },
             actions: vec![Action::Select],
-            grant_option: false,
+            grantable_actions: HashSet::new(),
             row_filter: None,
+            principal_condition: None,
+            comment: None,
+            expires_at: None,
+            effect: Effect::Allow,
         };
         
         assert_eq!(perm.actions.len(), 1);
         assert_eq!(perm.actions[0], Action::Select);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_effective_actions_include_implied() {
+        let mut perm = Permission {
+            principal: Principal::Role("analyst".to_string()),
+            resource: Resource::Database { name: "sales".to_string(), catalog_id: None },
+            actions: vec![Action::Select],
+            grantable_actions: HashSet::new(),
+            row_filter: None,
+            principal_condition: None,
+            comment: None,
+            expires_at: None,
+            effect: Effect::Allow,
+        };
+        assert_eq!(perm.effective_actions(), HashSet::from([Action::Select, Action::Describe]));
+
+        perm.actions = vec![Action::Associate];
+        assert_eq!(perm.effective_actions(), HashSet::from([Action::Associate]));
+
+        perm.actions = vec![Action::All];
+        let all = perm.effective_actions();
+        assert!(all.contains(&Action::All));
+        assert!(Action::CONCRETE.iter().all(|a| all.contains(a)));
+    }
+
+    #[test]
+    fn test_coverage_reason() {
+        let table = |db: &str, name: &str| Resource::Table {
+            database: db.to_string(),
+            table: name.to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        let database = |name: &str| Resource::Database { name: name.to_string(), catalog_id: None };
+        let location = |path: &str| Resource::DataLocation { path: path.to_string() };
+
+        assert_eq!(table("sales", "orders").coverage_reason(&table("sales", "orders")), CoverageResult::ExactMatch);
+        assert_eq!(database("sales").coverage_reason(&database("sales")), CoverageResult::ExactMatch);
+        assert_eq!(location("s3://lake/sales").coverage_reason(&location("s3://lake/sales")), CoverageResult::ExactMatch);
+        assert_eq!(table("sales", "orders").coverage_reason(&database("sales")), CoverageResult::DatabaseCovers);
+        assert_eq!(location("s3://lake/sales/2024").coverage_reason(&location("s3://lake/sales")), CoverageResult::LocationPrefix);
+
+        assert_eq!(table("sales", "orders").coverage_reason(&database("hr")), CoverageResult::NotCovered);
+        assert_eq!(database("sales").coverage_reason(&table("sales", "orders")), CoverageResult::NotCovered);
+        assert_eq!(location("s3://lake/hr").coverage_reason(&location("s3://lake/sales")), CoverageResult::NotCovered);
+        assert!(!table("sales", "orders").is_covered_by(&table("sales", "refunds")));
+    }
+
+    #[test]
+    fn test_column_coverage() {
+        let orders = |columns: Option<&[&str]>| Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: columns.map(|cols| cols.iter().map(|c| c.to_string()).collect()),
+            catalog_id: None,
+        };
+
+        // A column grant covers requests for a subset of its columns
+        let granted = orders(Some(&["order_id", "amount"]));
+        assert_eq!(orders(Some(&["order_id"])).coverage_reason(&granted), CoverageResult::ExactMatch);
+        assert!(orders(Some(&["amount", "order_id"])).is_covered_by(&granted));
+        assert!(!orders(Some(&["order_id", "ssn"])).is_covered_by(&granted));
+        // ...but not the whole table
+        assert!(!orders(None).is_covered_by(&granted));
+        // A whole-table grant covers any column subset
+        assert!(orders(Some(&["ssn"])).is_covered_by(&orders(None)));
+        assert!(orders(None).is_covered_by(&orders(None)));
+    }
+
+    #[test]
+    fn test_all_tables_coverage() {
+        let orders = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: Some(vec!["id".to_string()]),
+            catalog_id: None,
+        };
+        let all_tables = |db: &str| Resource::AllTables { database: db.to_string(), catalog_id: None };
+
+        assert_eq!(orders.coverage_reason(&all_tables("sales")), CoverageResult::TableWildcard);
+        assert!(!orders.is_covered_by(&all_tables("hr")));
+        assert!(all_tables("sales").is_covered_by(&Resource::Database { name: "sales".to_string(), catalog_id: None }));
+        assert!(!Resource::Database { name: "sales".to_string(), catalog_id: None }.is_covered_by(&all_tables("sales")));
+        assert_eq!(all_tables("sales").kind(), ResourceKind::Table);
+    }
+
+    #[test]
+    fn test_equivalent_locations_are_covered() {
+        let location = |path: &str| Resource::DataLocation { path: path.to_string() };
+
+        assert_eq!(location("S3://bucket/p/").coverage_reason(&location("s3://bucket/p")), CoverageResult::ExactMatch);
+        assert_eq!(location("s3://bucket//p/2024/").coverage_reason(&location("s3://bucket/p/")), CoverageResult::LocationPrefix);
+        assert_eq!(canonical_location("S3://bucket//p/"), "s3://bucket/p");
+        assert_eq!(location("S3://bucket/p/").normalize(), location("s3://bucket/p"));
+        assert!(!location("s3://bucket/q").is_covered_by(&location("S3://bucket/p")));
+        assert!(!location("s3://lake/sales2").is_covered_by(&location("s3://lake/sales/")));
+        assert!(!location("s3://lake/sales2/2024").is_covered_by(&location("s3://lake/sales")));
+    }
+
+    #[test]
+    fn test_view_coverage() {
+        let view = |db: &str, name: &str| Resource::View { database: db.to_string(), view: name.to_string() };
+        let database = |name: &str| Resource::Database { name: name.to_string(), catalog_id: None };
+        let table = Resource::Table {
+            database: "sales".to_string(),
+            table: "monthly".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+
+        assert_eq!(view("sales", "monthly").coverage_reason(&view("sales", "monthly")), CoverageResult::ExactMatch);
+        assert_eq!(view("sales", "monthly").coverage_reason(&database("sales")), CoverageResult::DatabaseCovers);
+        assert!(!view("sales", "monthly").is_covered_by(&database("hr")));
+        assert!(!view("sales", "monthly").is_covered_by(&view("sales", "weekly")));
+
+        // Views and tables are distinct objects even when the names agree
+        assert!(!view("sales", "monthly").is_covered_by(&table));
+        assert!(!table.is_covered_by(&view("sales", "monthly")));
+    }
+
+    #[test]
+    fn test_legacy_grant_option_migrates_per_action() {
+        let legacy = r#"{
+            "principal": {"Role": "analyst"},
+            "resource": {"Database": {"name": "sales"}},
+            "actions": ["Select", "Describe"],
+            "grant_option": true,
+            "row_filter": null
+        }"#;
+        let permission: Permission = serde_json::from_str(legacy).unwrap();
+        assert_eq!(permission.grantable_actions, HashSet::from([Action::Select, Action::Describe]));
+        assert!(permission.grant_option());
+
+        // Round-trips through the per-action form
+        let json = serde_json::to_string(&permission).unwrap();
+        assert!(!json.contains("\"grant_option\""));
+        assert_eq!(serde_json::from_str::<Permission>(&json).unwrap(), permission);
+
+        let mut mixed = permission.clone();
+        mixed.grantable_actions = HashSet::from([Action::Select]);
+        assert!(mixed.is_grantable(&Action::Select));
+        assert!(!mixed.is_grantable(&Action::Describe));
+        assert!(!mixed.is_grantable(&Action::Insert));
+    }
+
+    #[test]
+    fn test_actions_are_normalized() {
+        let role = Principal::Role("analyst".to_string());
+        let sales = Resource::Database { name: "sales".to_string(), catalog_id: None };
+
+        let permission = Permission::new(role.clone(), sales.clone(), vec![Action::Select, Action::Select, Action::Insert]);
+        assert_eq!(permission.actions, vec![Action::Insert, Action::Select]);
+        assert_eq!(permission, Permission::new(role, sales, vec![Action::Insert, Action::Select]));
+
+        // Stored state is normalized on load
+        let stored = r#"{
+            "principal": {"Role": "analyst"},
+            "resource": {"Database": {"name": "sales"}},
+            "actions": ["Select", "CreateTable", "Select"],
+            "row_filter": null
+        }"#;
+        let loaded: Permission = serde_json::from_str(stored).unwrap();
+        assert_eq!(loaded.actions, vec![Action::CreateTable, Action::Select]);
+    }
+
+    #[test]
+    fn test_revoke_only_named_actions() {
+        let role = Principal::Role("analyst".to_string());
+        let orders = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+
+        let mut engine = PermissionEngine::new();
+        engine.grant_permission(Permission::new(role.clone(), orders.clone(), vec![Action::Select, Action::Insert])).unwrap();
+        engine.revoke_permission(&role, &orders, &[Action::Insert]).unwrap();
+        assert!(engine.check_permission(&role, &orders, &Action::Select));
+        assert!(!engine.check_permission(&role, &orders, &Action::Insert));
+
+        engine.revoke_permission(&role, &orders, &[Action::Select]).unwrap();
+        assert!(!engine.check_permission(&role, &orders, &Action::Select));
+
+        // Revoking one action from ALL keeps the rest
+        let mut all = Permission::new(role.clone(), orders.clone(), vec![Action::All]);
+        all.grantable_actions.insert(Action::All);
+        all.revoke_actions(&[Action::Delete]);
+        assert!(!all.grants_action(&Action::Delete));
+        assert!(all.is_grantable(&Action::Select));
+        assert_eq!(all.actions.len(), Action::CONCRETE.len() - 1);
+
+        all.revoke_actions(&[Action::All]);
+        assert!(all.actions.is_empty());
+    }
+}
@@ -24,32 +24,42 @@ impl PermissionEngine {
         }
     }
 
-    /// Grant a permission
+    /// Grant a permission, merging it into an existing one where possible
     pub fn grant_permission(&mut self, permission: Permission) -> Result<()> {
-        // Remove any existing conflicting permissions for same principal/resource
-        self.permissions.retain(|p| {
-            !(p.principal == permission.principal && p.resource == permission.resource)
-        });
-        
-        self.permissions.push(permission);
+        match self.permissions.iter_mut().find(|p| p.merges_with(&permission)) {
+            Some(existing) => existing.merge(&permission),
+            None => self.permissions.push(permission),
+        }
         Ok(())
     }
 
-    /// Revoke a permission  
+    /// Revoke actions, dropping a permission only once it has none left
     pub fn revoke_permission(&mut self, principal: &Principal, resource: &Resource, actions: &[Action]) -> Result<()> {
-        self.permissions.retain(|p| {
-            !(p.principal == *principal && 
-              p.resource == *resource &&
-              actions.iter().any(|a| p.actions.contains(a)))
+        self.permissions.retain_mut(|p| {
+            if p.principal == *principal && p.resource == *resource {
+                p.revoke_actions(actions);
+            }
+            !p.actions.is_empty()
         });
         Ok(())
     }
 
-    /// Check if a principal has specific permissions on a resource
+    /// Check if a principal has specific permissions on a resource. A
+    /// matching deny overrides any grant.
     pub fn check_permission(&self, principal: &Principal, resource: &Resource, action: &Action) -> bool {
-        for permission in &self.permissions {
+        let denied = self.permissions.iter().any(|permission| {
+            permission.effect == Effect::Deny
+                && permission.principal.matches(principal)
+                && permission.grants_action(action)
+                && resource.is_covered_by(&permission.resource)
+        });
+        if denied {
+            return false;
+        }
+
+        for permission in self.permissions.iter().filter(|p| p.effect.is_allow()) {
             if permission.principal.matches(principal) &&
-               permission.actions.contains(action) &&
+               permission.grants_action(action) &&
                resource.is_covered_by(&permission.resource) {
                 
                 // Check row-level filters if present
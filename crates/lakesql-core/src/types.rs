@@ -1,7 +1,7 @@
 //! Core data types for Lake Formation DDL
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Represents a principal (user, role, group) that can have permissions
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -27,12 +27,31 @@ pub enum Resource {
     /// Entire database
     Database {
         name: String,
+        /// Glue catalog (account) id; None targets the caller's catalog
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        catalog_id: Option<String>,
     },
     /// Specific table, optionally with column restrictions
     Table {
         database: String,
         table: String,
         columns: Option<Vec<String>>,
+        /// Glue catalog (account) id; None targets the caller's catalog
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        catalog_id: Option<String>,
+    },
+    /// Every table in a database (`ALL TABLES IN DATABASE`), Lake
+    /// Formation's table wildcard
+    AllTables {
+        database: String,
+        /// Glue catalog (account) id; None targets the caller's catalog
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        catalog_id: Option<String>,
+    },
+    /// A Glue view, granted on separately from tables
+    View {
+        database: String,
+        view: String,
     },
     /// Data location (S3 path)
     DataLocation {
@@ -42,21 +61,27 @@ pub enum Resource {
     TaggedResource {
         tag_conditions: Vec<(String, Vec<String>)>,
     },
+    /// An LF-Tag itself, for permissions to describe or associate the tag
+    LfTag {
+        key: String,
+    },
 }
 
 // Manual Hash implementation for Resource
 impl std::hash::Hash for Resource {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         match self {
-            Resource::Database { name } => {
+            Resource::Database { name, catalog_id } => {
                 0.hash(state);
                 name.hash(state);
+                catalog_id.hash(state);
             },
-            Resource::Table { database, table, columns } => {
+            Resource::Table { database, table, columns, catalog_id } => {
                 1.hash(state);
                 database.hash(state);
                 table.hash(state);
                 columns.hash(state);
+                catalog_id.hash(state);
             },
             Resource::DataLocation { path } => {
                 2.hash(state);
@@ -69,6 +94,20 @@ impl std::hash::Hash for Resource {
                 sorted_conditions.sort();
                 sorted_conditions.hash(state);
             },
+            Resource::LfTag { key } => {
+                4.hash(state);
+                key.hash(state);
+            },
+            Resource::View { database, view } => {
+                5.hash(state);
+                database.hash(state);
+                view.hash(state);
+            },
+            Resource::AllTables { database, catalog_id } => {
+                6.hash(state);
+                database.hash(state);
+                catalog_id.hash(state);
+            },
         }
     }
 }
@@ -93,23 +132,191 @@ pub enum Action {
     
     // Administrative permissions
     GrantWithGrantOption,
+
+    // LF-Tag permissions (assigning a tag to resources)
+    Associate,
+
+    // Every action on the resource (GRANT ALL)
+    All,
+}
+
+/// Whether Lake Formation or IAM alone governs access to a resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AccessMode {
+    /// Lake Formation permissions are enforced
+    #[default]
+    LakeFormation,
+    /// IAM_ALLOWED_PRINCIPALS hybrid mode: IAM policies decide and Lake
+    /// Formation grants are not checked
+    IamOnly,
 }
 
 /// Row-level security filter expression
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RowFilter {
+    /// Source text, kept for display and export
     pub expression: String,
     pub session_context: Option<HashMap<String, String>>,
+    /// Parsed form of `expression`, set when the filter came from the parser
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ast: Option<FilterExpr>,
+}
+
+/// Parsed row filter or grant condition. NOT binds tighter than AND, and
+/// AND tighter than OR; parenthesized groups are represented by nesting.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FilterExpr {
+    Comparison {
+        left: FilterOperand,
+        op: ComparisonOp,
+        right: FilterOperand,
+    },
+    /// `left IN (a, b, ...)`: true when `left` equals any entry
+    In {
+        left: FilterOperand,
+        list: Vec<FilterOperand>,
+    },
+    And(Box<FilterExpr>, Box<FilterExpr>),
+    Or(Box<FilterExpr>, Box<FilterExpr>),
+    Not(Box<FilterExpr>),
+    /// `operand IS NULL`, or `IS NOT NULL` when `negated`. A column missing
+    /// from the row or an unset session key counts as NULL.
+    IsNull {
+        operand: FilterOperand,
+        negated: bool,
+    },
+    /// A bare operand, such as a custom function call, that must evaluate
+    /// to TRUE or FALSE
+    Value(FilterOperand),
+}
+
+/// One side of a comparison in a `FilterExpr`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FilterOperand {
+    /// Column of the row being filtered (`region`, `t.region`)
+    Column(String),
+    /// String literal, unquoted
+    Literal(String),
+    /// Numeric literal as written
+    Number(String),
+    Null,
+    /// `SESSION_CONTEXT('key')`, with an optional default for a missing key
+    SessionContext {
+        key: String,
+        default: Option<Box<FilterOperand>>,
+    },
+    /// `CURRENT_USER`, the requesting principal
+    CurrentUser,
+    /// `CURRENT_TIMESTAMP`, as a UTC `YYYY-MM-DD HH:MM:SS` string
+    CurrentTimestamp,
+    /// `CURRENT_DATE`, as a UTC `YYYY-MM-DD` string
+    CurrentDate,
+    /// Call to a custom function registered with the evaluator
+    Function {
+        name: String,
+        args: Vec<FilterOperand>,
+    },
+}
+
+/// Comparison operator in a `FilterExpr`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ComparisonOp {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    Like,
+    NotLike,
 }
 
 /// A complete permission grant/revoke
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)] 
+#[serde(from = "PermissionRecord")]
 pub struct Permission {
     pub principal: Principal,
     pub resource: Resource,
     pub actions: Vec<Action>,
-    pub grant_option: bool,
+    /// Actions granted WITH GRANT OPTION, which the grantee may pass on
+    #[serde(default)]
+    pub grantable_actions: HashSet<Action>,
     pub row_filter: Option<RowFilter>,
+    /// Session condition (e.g. `SESSION_CONTEXT('mfa') = 'true'`) that must hold
+    /// for the grant to apply; evaluated without row data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub principal_condition: Option<RowFilter>,
+    /// Free-form annotation carried from a trailing `-- comment` in DDL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// When the grant lapses, in seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+    /// Whether this grants or (from `DENY`) explicitly denies the actions
+    #[serde(default, skip_serializing_if = "Effect::is_allow")]
+    pub effect: Effect,
+}
+
+/// Whether a permission allows or denies its actions. A matching deny
+/// overrides any allow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Effect {
+    #[default]
+    Allow,
+    Deny,
+}
+
+impl Effect {
+    pub fn is_allow(&self) -> bool {
+        *self == Effect::Allow
+    }
+}
+
+/// Stored form of a [`Permission`], also accepting state written before
+/// grant options were tracked per action
+#[derive(Deserialize)]
+struct PermissionRecord {
+    principal: Principal,
+    resource: Resource,
+    actions: Vec<Action>,
+    #[serde(default)]
+    grantable_actions: HashSet<Action>,
+    /// Legacy whole-permission flag, superseded by `grantable_actions`
+    #[serde(default)]
+    grant_option: bool,
+    row_filter: Option<RowFilter>,
+    #[serde(default)]
+    principal_condition: Option<RowFilter>,
+    #[serde(default)]
+    comment: Option<String>,
+    #[serde(default)]
+    expires_at: Option<u64>,
+    #[serde(default)]
+    effect: Effect,
+}
+
+impl From<PermissionRecord> for Permission {
+    fn from(record: PermissionRecord) -> Self {
+        let grantable_actions = if record.grant_option && record.grantable_actions.is_empty() {
+            record.actions.iter().cloned().collect()
+        } else {
+            record.grantable_actions
+        };
+
+        let mut permission = Permission {
+            principal: record.principal,
+            resource: record.resource,
+            actions: record.actions,
+            grantable_actions,
+            row_filter: record.row_filter,
+            principal_condition: record.principal_condition,
+            comment: record.comment,
+            expires_at: record.expires_at,
+            effect: record.effect,
+        };
+        permission.normalize_actions();
+        permission
+    }
 }
 
 /// Lake Formation Tag definition
@@ -135,45 +342,396 @@ impl Principal {
     /// Check if this principal matches another (for permission resolution)
     pub fn matches(&self, other: &Principal) -> bool {
         match (self, other) {
-            (Principal::User(a), Principal::User(b)) => a == b,
-            (Principal::Role(a), Principal::Role(b)) => a == b,
+            (Principal::User(a), Principal::User(b)) => same_iam_name(a, b),
+            (Principal::Role(a), Principal::Role(b)) => same_iam_name(a, b),
             (Principal::SamlGroup(a), Principal::SamlGroup(b)) => a == b,
             (Principal::ExternalAccount(a), Principal::ExternalAccount(b)) => a == b,
             // Tagged principals require more complex matching logic
             _ => false,
         }
     }
+
+    /// Canonical form of the principal: IAM user and role ARNs are reduced
+    /// to their name, so `arn:aws:iam::123456789012:user/alice` and `alice`
+    /// compare equal
+    pub fn normalize(&self) -> Principal {
+        match self {
+            Principal::User(name) => Principal::User(canonical_iam_name(name).to_string()),
+            Principal::Role(name) => Principal::Role(canonical_iam_name(name).to_string()),
+            other => other.clone(),
+        }
+    }
+}
+
+/// The name at the end of an IAM `user/...` or `role/...` ARN (dropping any
+/// path), or the input unchanged if it isn't one
+pub fn canonical_iam_name(name: &str) -> &str {
+    if !name.starts_with("arn:") {
+        return name;
+    }
+    match name.splitn(6, ':').nth(5).and_then(|resource| resource.split_once('/')) {
+        Some(("user" | "role", path)) => path.rsplit('/').next().unwrap_or(path),
+        _ => name,
+    }
+}
+
+/// Whether two IAM user or role names refer to the same principal. Two ARNs
+/// must be identical, account and path included, so `alice` in one account
+/// never stands for `alice` in another; a bare name matches any ARN with
+/// that name.
+pub fn same_iam_name(a: &str, b: &str) -> bool {
+    if a.starts_with("arn:") && b.starts_with("arn:") {
+        a == b
+    } else {
+        canonical_iam_name(a) == canonical_iam_name(b)
+    }
+}
+
+/// Canonical form of a data location: the scheme is lowercased, repeated
+/// slashes collapsed and any trailing slash dropped, so `S3://bucket//sales/`
+/// and `s3://bucket/sales` compare equal
+pub fn canonical_location(path: &str) -> String {
+    let (scheme, rest) = match path.split_once("://") {
+        Some((scheme, rest)) => (Some(scheme.to_ascii_lowercase()), rest),
+        None => (None, path),
+    };
+    let segments = rest
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    match scheme {
+        Some(scheme) => format!("{}://{}", scheme, segments),
+        None if rest.starts_with('/') => format!("/{}", segments),
+        None => segments,
+    }
+}
+
+impl Action {
+    /// Every action other than ALL
+    pub const CONCRETE: [Action; 11] = [
+        Action::Select,
+        Action::Insert,
+        Action::Update,
+        Action::Delete,
+        Action::CreateTable,
+        Action::DropTable,
+        Action::AlterTable,
+        Action::Describe,
+        Action::DataLocationAccess,
+        Action::GrantWithGrantOption,
+        Action::Associate,
+    ];
+
+    /// Expand ALL into the concrete actions it stands for
+    pub fn expand(&self) -> Vec<Action> {
+        match self {
+            Action::All => Action::CONCRETE.to_vec(),
+            other => vec![other.clone()],
+        }
+    }
+
+    /// Actions that come with this one without being granted explicitly.
+    /// Lake Formation lets anyone who can read or change a table describe it,
+    /// and ALL stands for every concrete action.
+    pub fn implied_actions(&self) -> &'static [Action] {
+        match self {
+            Action::All => &Action::CONCRETE,
+            Action::Select
+            | Action::Insert
+            | Action::Update
+            | Action::Delete
+            | Action::AlterTable
+            | Action::DropTable => &[Action::Describe],
+            _ => &[],
+        }
+    }
+
+    /// Canonical DDL token for the action, as the parser accepts it, e.g.
+    /// `CREATE_TABLE`. Use this rather than the Debug name when printing.
+    pub fn to_ddl_keyword(&self) -> &'static str {
+        match self {
+            Action::Select => "SELECT",
+            Action::Insert => "INSERT",
+            Action::Update => "UPDATE",
+            Action::Delete => "DELETE",
+            Action::CreateTable => "CREATE_TABLE",
+            Action::DropTable => "DROP_TABLE",
+            Action::AlterTable => "ALTER_TABLE",
+            Action::Describe => "DESCRIBE",
+            Action::DataLocationAccess => "DATA_LOCATION_ACCESS",
+            Action::GrantWithGrantOption => "GRANT_WITH_GRANT_OPTION",
+            Action::Associate => "ASSOCIATE",
+            Action::All => "ALL",
+        }
+    }
+}
+
+/// Actions order by DDL keyword, which keeps normalized action lists stable
+/// and readable
+impl Ord for Action {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.to_ddl_keyword().cmp(other.to_ddl_keyword())
+    }
+}
+
+impl PartialOrd for Action {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Permission {
+    /// A plain grant of actions on a resource, with the actions normalized
+    pub fn new(principal: Principal, resource: Resource, actions: Vec<Action>) -> Self {
+        let mut permission = Permission {
+            principal,
+            resource,
+            actions,
+            grantable_actions: HashSet::new(),
+            row_filter: None,
+            principal_condition: None,
+            comment: None,
+            expires_at: None,
+            effect: Effect::Allow,
+        };
+        permission.normalize_actions();
+        permission
+    }
+
+    /// Sort and deduplicate the actions so equal grants compare equal
+    /// regardless of how their actions were listed
+    pub fn normalize_actions(&mut self) {
+        self.actions.sort();
+        self.actions.dedup();
+    }
+
+    /// Whether a new grant folds into this one instead of standing beside
+    /// it: same principal, resource and effect, the same row filter, session
+    /// condition and expiry, and both with or both without a grant option
+    pub fn merges_with(&self, other: &Permission) -> bool {
+        self.principal == other.principal
+            && self.resource == other.resource
+            && self.effect == other.effect
+            && self.row_filter == other.row_filter
+            && self.principal_condition == other.principal_condition
+            && self.expires_at == other.expires_at
+            && self.grant_option() == other.grant_option()
+    }
+
+    /// Union another grant's actions and grant options into this one. A
+    /// comment on the new grant replaces this one's.
+    pub fn merge(&mut self, other: &Permission) {
+        self.actions.extend(other.actions.iter().cloned());
+        self.grantable_actions.extend(other.grantable_actions.iter().cloned());
+        if other.comment.is_some() {
+            self.comment = other.comment.clone();
+        }
+        self.normalize_actions();
+    }
+
+    /// Take actions away from this permission, expanding ALL into the
+    /// actions it stands for so only the revoked ones go. Revoking ALL
+    /// clears it. The caller drops the permission once no actions remain.
+    pub fn revoke_actions(&mut self, actions: &[Action]) {
+        if actions.contains(&Action::All) {
+            self.actions.clear();
+            self.grantable_actions.clear();
+            return;
+        }
+        if self.actions.contains(&Action::All) {
+            self.actions = Action::All.expand();
+        }
+        if self.grantable_actions.contains(&Action::All) {
+            self.grantable_actions = Action::All.expand().into_iter().collect();
+        }
+        self.actions.retain(|a| !actions.contains(a));
+        self.grantable_actions.retain(|a| !actions.contains(a));
+        self.normalize_actions();
+    }
+
+    /// Check if this permission grants an action, either directly or via ALL
+    pub fn grants_action(&self, action: &Action) -> bool {
+        self.actions.contains(&Action::All) || self.actions.contains(action)
+    }
+
+    /// Every action this permission grants: the listed actions plus
+    /// everything they imply, transitively
+    pub fn effective_actions(&self) -> HashSet<Action> {
+        let mut effective: HashSet<Action> = HashSet::new();
+        let mut pending = self.actions.clone();
+        while let Some(action) = pending.pop() {
+            if effective.insert(action.clone()) {
+                pending.extend(action.implied_actions().iter().cloned());
+            }
+        }
+        effective
+    }
+
+    /// Whether any action was granted WITH GRANT OPTION
+    pub fn grant_option(&self) -> bool {
+        !self.grantable_actions.is_empty()
+    }
+
+    /// Check if the grantee may grant an action on, either directly or via
+    /// ALL WITH GRANT OPTION
+    pub fn is_grantable(&self, action: &Action) -> bool {
+        self.grants_action(action)
+            && (self.grantable_actions.contains(&Action::All) || self.grantable_actions.contains(action))
+    }
+
+    /// Check if the grant has lapsed at the given time (Unix seconds)
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+/// Broad category of a resource, used to group and summarize grants
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ResourceKind {
+    Database,
+    Table,
+    View,
+    DataLocation,
+    TaggedResource,
+    LfTag,
+}
+
+impl ResourceKind {
+    /// Upper-case label, matching the resource types used in CSV export
+    pub fn label(&self) -> &'static str {
+        match self {
+            ResourceKind::Database => "DATABASE",
+            ResourceKind::Table => "TABLE",
+            ResourceKind::View => "VIEW",
+            ResourceKind::DataLocation => "DATA_LOCATION",
+            ResourceKind::TaggedResource => "TAGGED_RESOURCE",
+            ResourceKind::LfTag => "LF_TAG",
+        }
+    }
+}
+
+/// Why one resource is (or isn't) covered by another
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoverageResult {
+    /// Same database, table, view, data location or LF-Tag. For a table
+    /// with a column list, every requested column is granted.
+    ExactMatch,
+    /// A table or view covered by a grant on its database
+    DatabaseCovers,
+    /// A table covered by an ALL TABLES grant on its database
+    TableWildcard,
+    /// A data location under a granted location prefix
+    LocationPrefix,
+    NotCovered,
 }
 
 impl Resource {
+    /// Which category of resource this is
+    pub fn kind(&self) -> ResourceKind {
+        match self {
+            Resource::Database { .. } => ResourceKind::Database,
+            Resource::Table { .. } | Resource::AllTables { .. } => ResourceKind::Table,
+            Resource::View { .. } => ResourceKind::View,
+            Resource::DataLocation { .. } => ResourceKind::DataLocation,
+            Resource::TaggedResource { .. } => ResourceKind::TaggedResource,
+            Resource::LfTag { .. } => ResourceKind::LfTag,
+        }
+    }
+
+    /// Canonical form of the resource: data location paths go through
+    /// `canonical_location`, everything else is unchanged
+    pub fn normalize(&self) -> Resource {
+        match self {
+            Resource::DataLocation { path } => Resource::DataLocation { path: canonical_location(path) },
+            other => other.clone(),
+        }
+    }
+
     /// Check if this resource is contained within or matches another resource
     pub fn is_covered_by(&self, other: &Resource) -> bool {
+        self.coverage_reason(other) != CoverageResult::NotCovered
+    }
+
+    /// Explain how this resource is covered by another
+    pub fn coverage_reason(&self, other: &Resource) -> CoverageResult {
+        let covered = |covered: bool, reason: CoverageResult| {
+            if covered { reason } else { CoverageResult::NotCovered }
+        };
+
         match (self, other) {
-            // Exact table match
-            (Resource::Table { database: db1, table: t1, .. }, 
-             Resource::Table { database: db2, table: t2, .. }) => {
-                db1 == db2 && t1 == t2
+            // Exact table match. A column-restricted grant only covers
+            // requests for a subset of its columns, never the whole table.
+            (Resource::Table { database: db1, table: t1, columns: cols1, catalog_id: c1 },
+             Resource::Table { database: db2, table: t2, columns: cols2, catalog_id: c2 }) => {
+                let columns_covered = match (cols1, cols2) {
+                    (_, None) => true,
+                    (None, Some(_)) => false,
+                    (Some(requested), Some(granted)) => requested.iter().all(|c| granted.contains(c)),
+                };
+                covered(c1 == c2 && db1 == db2 && t1 == t2 && columns_covered, CoverageResult::ExactMatch)
             },
             
             // Table is covered by database permission
-            (Resource::Table { database: db1, .. }, 
-             Resource::Database { name: db2 }) => {
-                db1 == db2
+            (Resource::Table { database: db1, catalog_id: c1, .. }, 
+             Resource::Database { name: db2, catalog_id: c2 }) => {
+                covered(c1 == c2 && db1 == db2, CoverageResult::DatabaseCovers)
             },
             
+            // Table is covered by a table wildcard on its database
+            (Resource::Table { database: db1, catalog_id: c1, .. },
+             Resource::AllTables { database: db2, catalog_id: c2 }) => {
+                covered(c1 == c2 && db1 == db2, CoverageResult::TableWildcard)
+            },
+
+            (Resource::AllTables { database: db1, catalog_id: c1 },
+             Resource::AllTables { database: db2, catalog_id: c2 }) => {
+                covered(c1 == c2 && db1 == db2, CoverageResult::ExactMatch)
+            },
+
+            (Resource::AllTables { database: db1, catalog_id: c1 },
+             Resource::Database { name: db2, catalog_id: c2 }) => {
+                covered(c1 == c2 && db1 == db2, CoverageResult::DatabaseCovers)
+            },
+
+            // Exact view match
+            (Resource::View { database: db1, view: v1 },
+             Resource::View { database: db2, view: v2 }) => {
+                covered(db1 == db2 && v1 == v2, CoverageResult::ExactMatch)
+            },
+
+            // View is covered by database permission (in the caller's catalog)
+            (Resource::View { database: db1, .. },
+             Resource::Database { name: db2, catalog_id: None }) => {
+                covered(db1 == db2, CoverageResult::DatabaseCovers)
+            },
+
             // Exact database match
-            (Resource::Database { name: db1 }, 
-             Resource::Database { name: db2 }) => {
-                db1 == db2
+            (Resource::Database { name: db1, catalog_id: c1 }, 
+             Resource::Database { name: db2, catalog_id: c2 }) => {
+                covered(c1 == c2 && db1 == db2, CoverageResult::ExactMatch)
+            },
+            
+            // LF-Tag permissions apply to the tag key only
+            (Resource::LfTag { key: k1 },
+             Resource::LfTag { key: k2 }) => {
+                covered(k1 == k2, CoverageResult::ExactMatch)
             },
             
             // Data location prefix matching
             (Resource::DataLocation { path: p1 },
              Resource::DataLocation { path: p2 }) => {
-                p1.starts_with(p2) || p1 == p2
+                let (p1, p2) = (canonical_location(p1), canonical_location(p2));
+                if p1 == p2 {
+                    CoverageResult::ExactMatch
+                } else {
+                    // Whole path segments only: s3://lake/sales doesn't cover s3://lake/sales2
+                    covered(p1.starts_with(&format!("{}/", p2)), CoverageResult::LocationPrefix)
+                }
             },
             
-            _ => false,
+            _ => CoverageResult::NotCovered,
         }
     }
-}
\ No newline at end of file
+}
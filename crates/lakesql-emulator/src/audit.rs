@@ -0,0 +1,145 @@
+//! Audit trail of executed DDL, written as JSON lines for SIEM ingestion
+
+use anyhow::Result;
+use lakesql_core::*;
+use lakesql_parser::DdlStatement;
+use serde::{Deserialize, Serialize};
+
+/// One executed statement and its outcome
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// When the statement ran, in seconds since the Unix epoch
+    pub timestamp: u64,
+    pub sql: String,
+    /// Result message on success, error text on failure
+    pub result: String,
+    pub success: bool,
+    /// Principal the statement acted on, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub principal: Option<Principal>,
+    /// Resource the statement acted on, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource: Option<Resource>,
+}
+
+impl AuditEntry {
+    /// Build an entry for a statement, pulling the affected principal and
+    /// resource out of it when it parsed
+    pub fn new(timestamp: u64, sql: &str, statement: Option<&DdlStatement>, result: &Result<DdlResult>) -> Self {
+        let (principal, resource) = statement.map(affected).unwrap_or((None, None));
+        let (success, result) = match result {
+            Ok(DdlResult::Success { message }) => (true, message.clone()),
+            Ok(DdlResult::Error { error }) => (false, error.clone()),
+            Ok(DdlResult::PermissionCheck { allowed, .. }) => (true, format!("allowed: {}", allowed)),
+            Err(e) => (false, e.to_string()),
+        };
+
+        Self {
+            timestamp,
+            sql: sql.to_string(),
+            result,
+            success,
+            principal,
+            resource,
+        }
+    }
+}
+
+/// Ordered list of audit entries
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuditLog {
+    pub entries: Vec<AuditEntry>,
+}
+
+impl AuditLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, entry: AuditEntry) {
+        self.entries.push(entry);
+    }
+
+    /// Read a log written by `EmulatorBackend::set_audit_log`, skipping blank lines
+    pub async fn load(path: &str) -> Result<Self> {
+        let content = tokio::fs::read_to_string(path).await?;
+        Self::from_jsonl(&content)
+    }
+
+    /// Parse JSON lines, one entry per line
+    pub fn from_jsonl(content: &str) -> Result<Self> {
+        let entries = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(Into::into))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { entries })
+    }
+
+    /// One JSON object per entry, each on its own line
+    pub fn to_jsonl(&self) -> String {
+        self.entries
+            .iter()
+            .filter_map(|entry| serde_json::to_string(entry).ok())
+            .map(|line| line + "\n")
+            .collect()
+    }
+}
+
+/// Principal and resource a statement acts on
+fn affected(statement: &DdlStatement) -> (Option<Principal>, Option<Resource>) {
+    match statement {
+        DdlStatement::Grant { principal, resource, .. }
+        | DdlStatement::Revoke { principal, resource, .. }
+        | DdlStatement::Deny { principal, resource, .. }
+        | DdlStatement::ApplyTemplate { principal, resource, .. } => {
+            (Some(principal.clone()), Some(resource.clone()))
+        },
+        DdlStatement::CreateRole { name }
+        | DdlStatement::DropRole { name }
+        | DdlStatement::GrantRole { role: name, .. }
+        | DdlStatement::AlterRole { name, .. } => {
+            (Some(Principal::Role(name.clone())), None)
+        },
+        DdlStatement::DropTag { name } => (None, Some(Resource::LfTag { key: name.clone() })),
+        DdlStatement::CreateTag { name, .. } => (None, Some(Resource::LfTag { key: name.clone() })),
+        DdlStatement::RegisterLocation { path } => (None, Some(Resource::DataLocation { path: path.clone() })),
+        DdlStatement::SetAccessMode { resource, .. } => (None, Some(resource.clone())),
+        DdlStatement::DefineGroup { name, .. } => (Some(Principal::SamlGroup(name.clone())), None),
+        DdlStatement::ShowPermissions { principal } => (principal.clone(), None),
+        _ => (None, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lakesql_parser::parse_ddl;
+
+    #[test]
+    fn test_jsonl_lines_parse_independently() {
+        let mut log = AuditLog::new();
+        let sql = "GRANT SELECT ON sales.orders TO ROLE analyst";
+        let statement = parse_ddl(sql).unwrap();
+        log.push(AuditEntry::new(
+            1_000,
+            sql,
+            Some(&statement),
+            &Ok(DdlResult::Success { message: "Granted".to_string() }),
+        ));
+        log.push(AuditEntry::new(1_001, "GRANT BOGUS", None, &Err(anyhow::anyhow!("parse error\nat line 1"))));
+
+        let jsonl = log.to_jsonl();
+        assert_eq!(jsonl.lines().count(), 2);
+        let entries: Vec<AuditEntry> = jsonl.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(entries, log.entries);
+
+        assert!(entries[0].success);
+        assert_eq!(entries[0].principal, Some(Principal::Role("analyst".to_string())));
+        assert!(matches!(entries[0].resource, Some(Resource::Table { .. })));
+        assert!(!entries[1].success);
+        assert_eq!(entries[1].principal, None);
+
+        assert_eq!(AuditLog::from_jsonl(&jsonl).unwrap(), log);
+    }
+}
@@ -0,0 +1,198 @@
+//! Permission-level differences between emulator states
+
+use crate::EmulatorState;
+use anyhow::{anyhow, Result};
+use lakesql_core::*;
+use serde::{Deserialize, Serialize};
+
+/// Grants that differ between a base state and a target state
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StateDiff {
+    /// Actions granted in the target state but not in the base
+    pub added: Vec<Permission>,
+    /// Actions granted in the base state but not in the target
+    pub removed: Vec<Permission>,
+}
+
+impl StateDiff {
+    /// Whether the two states grant exactly the same actions
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// Converge a backend holding the base state towards the target: grant
+    /// the added actions, then revoke the removed ones. Grants go first so a
+    /// principal keeps access it is meant to keep while the change is applied.
+    ///
+    /// The backend trait can only grant and revoke, so a diff that adds or
+    /// removes a DENY is refused before anything is changed: granting a deny
+    /// would hand out the very actions it forbids, and revoking one would
+    /// take away matching grants.
+    pub async fn apply<B: LakeFormationBackend + ?Sized>(&self, backend: &mut B) -> Result<Vec<DdlResult>> {
+        if let Some(deny) = self.added.iter().chain(&self.removed).find(|p| p.effect == Effect::Deny) {
+            return Err(anyhow!(
+                "Cannot apply a DENY through grant and revoke: {:?} on {:?} for {:?}",
+                deny.actions, deny.resource, deny.principal
+            ));
+        }
+
+        let mut results = Vec::new();
+        for permission in &self.added {
+            results.push(backend.grant_permissions(permission.clone()).await?);
+        }
+        for permission in &self.removed {
+            results.push(
+                backend
+                    .revoke_permissions(&permission.principal, &permission.resource, &permission.actions)
+                    .await?,
+            );
+        }
+        Ok(results)
+    }
+}
+
+impl EmulatorState {
+    /// Build a state holding only the given permissions (e.g. a snapshot of
+    /// live AWS grants)
+    pub fn from_permissions(permissions: Vec<Permission>) -> Self {
+        Self {
+            permissions,
+            ..Self::new()
+        }
+    }
+
+    /// Compare the grants in this state against a target state.
+    ///
+    /// Grants are compared per principal, resource and action, so a grant of
+    /// `SELECT, INSERT` against a grant of `SELECT` shows up as `INSERT` only.
+    pub fn diff(&self, target: &EmulatorState) -> StateDiff {
+        StateDiff {
+            added: missing_grants(&target.permissions, &self.permissions),
+            removed: missing_grants(&self.permissions, &target.permissions),
+        }
+    }
+}
+
+/// Permissions (narrowed to their missing actions) present in `from` but
+/// not granted by anything in `against`
+fn missing_grants(from: &[Permission], against: &[Permission]) -> Vec<Permission> {
+    let mut missing: Vec<Permission> = Vec::new();
+
+    for permission in from {
+        let actions: Vec<Action> = permission.actions
+            .iter()
+            .filter(|action| {
+                !against.iter().any(|p| {
+                    p.principal == permission.principal
+                        && p.resource == permission.resource
+                        && p.effect == permission.effect
+                        && p.actions.contains(action)
+                })
+            })
+            .cloned()
+            .collect();
+
+        if actions.is_empty() {
+            continue;
+        }
+
+        let existing = missing.iter_mut().find(|p| {
+            p.principal == permission.principal && p.resource == permission.resource && p.effect == permission.effect
+        });
+        match existing {
+            Some(existing) => {
+                for action in actions {
+                    if !existing.actions.contains(&action) {
+                        existing.actions.push(action);
+                    }
+                }
+            },
+            None => missing.push(Permission {
+                actions,
+                ..permission.clone()
+            }),
+        }
+    }
+
+    missing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grant(principal: &str, table: &str, actions: Vec<Action>) -> Permission {
+        Permission::new(
+            Principal::Role(principal.to_string()),
+            Resource::Table {
+                database: "sales".to_string(),
+                table: table.to_string(),
+                columns: None,
+                catalog_id: None,
+            },
+            actions,
+        )
+    }
+
+    #[test]
+    fn test_identical_states_have_no_diff() {
+        let state = EmulatorState::from_permissions(vec![
+            grant("analyst", "orders", vec![Action::Select]),
+        ]);
+        assert!(state.diff(&state.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_drift_against_mocked_aws_snapshot() {
+        let local = EmulatorState::from_permissions(vec![
+            grant("analyst", "orders", vec![Action::Select, Action::Insert]),
+            grant("analyst", "customers", vec![Action::Select]),
+        ]);
+
+        // What list_permissions would return from AWS for the same principals
+        let live = EmulatorState::from_permissions(vec![
+            grant("analyst", "orders", vec![Action::Select]),
+            grant("analyst", "refunds", vec![Action::Delete]),
+            grant("analyst", "customers", vec![Action::Select]),
+        ]);
+
+        let diff = local.diff(&live);
+        assert_eq!(diff.added, vec![grant("analyst", "refunds", vec![Action::Delete])]);
+        assert_eq!(diff.removed, vec![grant("analyst", "orders", vec![Action::Insert])]);
+    }
+
+    #[tokio::test]
+    async fn test_apply_converges_backend_to_target() {
+        let mut backend = crate::EmulatorBackend::new(None).await.unwrap();
+        backend.execute_ddl("GRANT SELECT ON sales.orders TO ROLE analyst").await.unwrap();
+        backend.execute_ddl("GRANT DELETE ON sales.refunds TO ROLE analyst").await.unwrap();
+
+        let desired = EmulatorState::from_permissions(vec![
+            grant("analyst", "orders", vec![Action::Select]),
+            grant("analyst", "customers", vec![Action::Select]),
+        ]);
+
+        let diff = backend.get_state().diff(&desired);
+        let results = diff.apply(&mut backend).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(backend.get_state().diff(&desired).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_deny_is_never_applied_as_a_grant() {
+        let mut deny = grant("intern", "orders", vec![Action::Select]);
+        deny.effect = Effect::Deny;
+        let allowed = EmulatorState::from_permissions(vec![grant("intern", "orders", vec![Action::Select])]);
+        let denied = EmulatorState::from_permissions(vec![deny.clone()]);
+
+        // Allow and deny of the same action differ
+        let diff = allowed.diff(&denied);
+        assert_eq!(diff.added, vec![deny]);
+        assert_eq!(diff.removed, vec![grant("intern", "orders", vec![Action::Select])]);
+
+        let mut backend = crate::EmulatorBackend::new(None).await.unwrap();
+        let err = EmulatorState::new().diff(&denied).apply(&mut backend).await.unwrap_err();
+        assert!(err.to_string().contains("DENY"));
+        assert!(backend.get_state().permissions.is_empty());
+    }
+}
@@ -2,37 +2,323 @@
 
 use lakesql_core::*;
 use crate::{EmulatorState, expression::ExpressionEvaluator};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use anyhow::Result;
 
 /// Engine that evaluates permissions based on current state
 #[derive(Debug)]
 pub struct EmulatorEngine {
     /// Cached state for fast lookups
     state: EmulatorState,
+    /// Positions in `state.permissions` by the resource they're granted on
+    index: PermissionIndex,
+}
+
+/// Permissions grouped by the database and table their resource names, so a
+/// check only scans grants that could cover the requested resource. Holds
+/// positions into the permission list, in ascending order within each bucket.
+#[derive(Debug, Default, PartialEq)]
+struct PermissionIndex {
+    databases: HashMap<String, DatabaseIndex>,
+    /// Grants on data locations and LF-Tags
+    other: Vec<usize>,
+    /// `RESOURCES TAGGED` grants, which can cover any resource
+    tagged: Vec<usize>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct DatabaseIndex {
+    /// Grants on the database itself or on all of its tables
+    database: Vec<usize>,
+    /// Grants on one table or view (with or without columns), by name
+    tables: HashMap<String, Vec<usize>>,
+}
+
+impl PermissionIndex {
+    fn build(permissions: &[Permission]) -> Self {
+        let mut index = Self::default();
+        for (i, permission) in permissions.iter().enumerate() {
+            index.bucket_mut(&permission.resource).push(i);
+        }
+        index
+    }
+
+    /// Positions of the permissions granted on exactly this resource's
+    /// bucket; any permission on an equal resource is among them
+    fn bucket(&self, resource: &Resource) -> &[usize] {
+        match resource {
+            Resource::Database { name: database, .. } | Resource::AllTables { database, .. } => {
+                self.databases.get(database).map_or(&[], |db| &db.database)
+            },
+            Resource::Table { database, table: name, .. } | Resource::View { database, view: name } => {
+                self.databases
+                    .get(database)
+                    .and_then(|db| db.tables.get(name))
+                    .map_or(&[], Vec::as_slice)
+            },
+            Resource::TaggedResource { .. } => &self.tagged,
+            Resource::DataLocation { .. } | Resource::LfTag { .. } => &self.other,
+        }
+    }
+
+    fn bucket_mut(&mut self, resource: &Resource) -> &mut Vec<usize> {
+        match resource {
+            Resource::Database { name: database, .. } | Resource::AllTables { database, .. } => {
+                &mut self.databases.entry(database.clone()).or_default().database
+            },
+            Resource::Table { database, table: name, .. } | Resource::View { database, view: name } => {
+                self.databases
+                    .entry(database.clone())
+                    .or_default()
+                    .tables
+                    .entry(name.clone())
+                    .or_default()
+            },
+            Resource::TaggedResource { .. } => &mut self.tagged,
+            Resource::DataLocation { .. } | Resource::LfTag { .. } => &mut self.other,
+        }
+    }
+
+    /// Follow a `retain` on the permission list: `kept[i]` says whether the
+    /// permission at position i survived. Removals shift every later
+    /// position down, so each bucket is renumbered in place.
+    fn retain(&mut self, kept: &[bool]) {
+        let mut positions = Vec::with_capacity(kept.len());
+        let mut next = 0;
+        for &keep in kept {
+            positions.push(next);
+            next += usize::from(keep);
+        }
+        let renumber = |bucket: &mut Vec<usize>| {
+            bucket.retain(|&i| kept[i]);
+            for i in bucket.iter_mut() {
+                *i = positions[*i];
+            }
+        };
+
+        renumber(&mut self.other);
+        renumber(&mut self.tagged);
+        for db in self.databases.values_mut() {
+            renumber(&mut db.database);
+            for table in db.tables.values_mut() {
+                renumber(table);
+            }
+            db.tables.retain(|_, table| !table.is_empty());
+        }
+        self.databases.retain(|_, db| !db.database.is_empty() || !db.tables.is_empty());
+    }
+
+    /// Positions of the permissions that could cover a resource, in order
+    fn candidates(&self, resource: &Resource) -> Vec<usize> {
+        let mut candidates = self.tagged.clone();
+        match resource {
+            Resource::Database { name: database, .. } | Resource::AllTables { database, .. } => {
+                if let Some(db) = self.databases.get(database) {
+                    candidates.extend(&db.database);
+                }
+            },
+            Resource::Table { database, table: name, .. } | Resource::View { database, view: name } => {
+                if let Some(db) = self.databases.get(database) {
+                    candidates.extend(&db.database);
+                    candidates.extend(db.tables.get(name).into_iter().flatten());
+                }
+            },
+            Resource::TaggedResource { .. } | Resource::DataLocation { .. } | Resource::LfTag { .. } => {
+                candidates.extend(&self.other);
+            },
+        }
+        candidates.sort_unstable();
+        candidates
+    }
 }
 
 impl EmulatorEngine {
     pub fn new() -> Self {
         Self {
             state: EmulatorState::new(),
+            index: PermissionIndex::default(),
         }
     }
 
-    /// Update the engine with new state
+    /// Update the engine with new state. This copies the whole state; the
+    /// `apply_*` methods below update it in place for single changes.
     pub fn update_state(&mut self, state: &EmulatorState) {
         self.state = state.clone();
+        self.index = PermissionIndex::build(&self.state.permissions);
+    }
+
+    /// Apply one grant or deny, merging it into a matching permission (see
+    /// `Permission::merges_with`) as the backend does
+    pub fn apply_grant(&mut self, permission: &Permission) {
+        // A merge needs an equal resource, so only its bucket can hold one
+        let existing = self.index
+            .bucket(&permission.resource)
+            .iter()
+            .copied()
+            .find(|&i| self.state.permissions[i].merges_with(permission));
+        match existing {
+            Some(i) => self.state.permissions[i].merge(permission),
+            None => {
+                self.index.bucket_mut(&permission.resource).push(self.state.permissions.len());
+                self.state.permissions.push(permission.clone());
+            },
+        }
+    }
+
+    /// Apply a revoke: `revoke` narrows each grant in place and returns
+    /// whether it should be kept
+    pub fn apply_revoke(&mut self, revoke: impl FnMut(&mut Permission) -> bool) {
+        self.retain_permissions(revoke);
     }
 
-    /// Check if a principal has permission to perform an action on a resource
+    /// Remove permissions from the list, keeping the index in step
+    fn retain_permissions(&mut self, mut keep: impl FnMut(&mut Permission) -> bool) {
+        let mut kept = Vec::with_capacity(self.state.permissions.len());
+        self.state.permissions.retain_mut(|p| {
+            let keep = keep(p);
+            kept.push(keep);
+            keep
+        });
+        // Narrowing in place leaves every position where it was
+        if kept.contains(&false) {
+            self.index.retain(&kept);
+        }
+    }
+
+    /// Create a role, or reset an existing one, with the given members
+    pub fn apply_role(&mut self, role: &str, members: HashSet<String>) {
+        self.state.roles.insert(role.to_string(), members);
+    }
+
+    /// Record that `member` inherits `role`, as GRANT ROLE does
+    pub fn apply_role_grant(&mut self, role: &str, member: &str) {
+        self.state.role_parents.entry(member.to_string()).or_default().insert(role.to_string());
+    }
+
+    /// Drop a role along with the grants made to it
+    pub fn apply_drop_role(&mut self, role: &str) {
+        self.state.roles.remove(role);
+        self.state.remove_role_parents(role);
+        self.retain_permissions(|p| !matches!(&p.principal, Principal::Role(name) if name == role));
+    }
+
+    /// Replace the session context used for row filters and WHEN conditions
+    pub fn apply_session_context(&mut self, context: HashMap<String, String>) {
+        self.state.session_context = context;
+    }
+
+    /// Check if a principal has permission to perform an action on a resource.
+    /// A matching DENY wins over any matching grant, and over IAM-only mode.
     pub fn check_permission(&self, principal: &Principal, resource: &Resource, action: &Action) -> bool {
-        // Check direct permissions
-        for permission in &self.state.permissions {
-            if self.matches_permission(principal, resource, action, permission) {
-                return true;
+        if self.matching_deny(principal, resource, action).is_some() {
+            return false;
+        }
+
+        // Otherwise IAM governs resources in IAM-only mode
+        if self.state.access_mode(resource) == AccessMode::IamOnly {
+            return true;
+        }
+
+        // Check the permissions that could cover the resource
+        self.candidates(resource).any(|(_, permission)| {
+            permission.effect.is_allow() && self.matches_permission(principal, resource, action, permission)
+        })
+    }
+
+    /// The permissions that could cover a resource, with their positions,
+    /// in order. Anything else can't match, so checks needn't scan it.
+    fn candidates<'a>(&'a self, resource: &Resource) -> impl Iterator<Item = (usize, &'a Permission)> + 'a {
+        self.index
+            .candidates(resource)
+            .into_iter()
+            .map(|i| (i, &self.state.permissions[i]))
+    }
+
+    /// Check if a principal may grant an action on a resource to others,
+    /// i.e. holds it WITH GRANT OPTION and isn't denied it
+    pub fn can_grant(&self, principal: &Principal, resource: &Resource, action: &Action) -> bool {
+        if self.matching_deny(principal, resource, action).is_some() {
+            return false;
+        }
+        self.candidates(resource).any(|(_, permission)| {
+            permission.effect.is_allow()
+                && permission.is_grantable(action)
+                && self.matches_permission(principal, resource, action, permission)
+        })
+    }
+
+    /// Check a request that reads or writes specific columns of a table.
+    /// Column grants are unioned, so grants on `(id)` and on `(region)`
+    /// together allow `(id, region)`. An empty `columns` asks for the whole
+    /// table, which needs a grant on the table without a column list (or on
+    /// its database). A deny on any requested column refuses the request.
+    pub fn check_column_permission(
+        &self,
+        principal: &Principal,
+        database: &str,
+        table: &str,
+        columns: &[String],
+        action: &Action,
+    ) -> bool {
+        let whole_table = Resource::Table {
+            database: database.to_string(),
+            table: table.to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        let iam_only = self.state.access_mode(&whole_table) == AccessMode::IamOnly;
+
+        let mut allowed = HashSet::new();
+        let mut whole_table_granted = false;
+        for (_, permission) in self.candidates(&whole_table) {
+            let granted = match self.granted_columns(principal, &whole_table, action, permission) {
+                Some(granted) => granted,
+                None => continue,
+            };
+            match (permission.effect, granted) {
+                (Effect::Deny, None) => return false,
+                (Effect::Deny, Some(denied)) => {
+                    if columns.is_empty() || columns.iter().any(|c| denied.contains(c)) {
+                        return false;
+                    }
+                },
+                (Effect::Allow, None) => whole_table_granted = true,
+                (Effect::Allow, Some(granted)) => allowed.extend(granted.iter().map(String::as_str)),
             }
         }
 
-        false
+        iam_only || whole_table_granted || (!columns.is_empty() && columns.iter().all(|c| allowed.contains(c.as_str())))
+    }
+
+    /// How much of a table a permission applies to for this request: None
+    /// if it doesn't apply, `Some(None)` for the whole table and
+    /// `Some(Some(columns))` for a column grant on the table itself
+    fn granted_columns<'a>(
+        &self,
+        principal: &Principal,
+        whole_table: &Resource,
+        action: &Action,
+        permission: &'a Permission,
+    ) -> Option<Option<&'a [String]>> {
+        if let (
+            Resource::Table { database, table, columns: Some(columns), catalog_id },
+            Resource::Table { database: db, table: t, catalog_id: c, .. },
+        ) = (&permission.resource, whole_table) {
+            if database == db && table == t && catalog_id == c {
+                return self
+                    .matches_permission(principal, &permission.resource, action, permission)
+                    .then_some(Some(columns.as_slice()));
+            }
+        }
+        self.matches_permission(principal, whole_table, action, permission).then_some(None)
+    }
+
+    /// The first DENY that applies to the request, with its index
+    fn matching_deny(&self, principal: &Principal, resource: &Resource, action: &Action) -> Option<(usize, &Permission)> {
+        self.candidates(resource)
+            .find(|(_, permission)| {
+                permission.effect == Effect::Deny && self.matches_permission(principal, resource, action, permission)
+            })
     }
 
     /// Check if a permission matches the request
@@ -48,19 +334,26 @@ impl EmulatorEngine {
             return false;
         }
 
-        // Check if action is allowed
-        if !permission.actions.contains(action) {
+        // Check if action is allowed (ALL covers any action)
+        if !permission.grants_action(action) {
             return false;
         }
 
         // Check if resource is covered
-        if !resource.is_covered_by(&permission.resource) {
+        if !self.resource_covered(resource, &permission.resource) {
             return false;
         }
 
         // Check row-level filters if present
         if let Some(ref row_filter) = permission.row_filter {
-            if !self.evaluate_row_filter(row_filter, resource) {
+            if !passes(self.evaluate_row_filter(row_filter, resource), row_filter) {
+                return false;
+            }
+        }
+
+        // Check the grant-time session condition if present
+        if let Some(ref condition) = permission.principal_condition {
+            if !passes(self.evaluate_principal_condition(condition), condition) {
                 return false;
             }
         }
@@ -68,41 +361,114 @@ impl EmulatorEngine {
         true
     }
 
+    /// Check if a granted resource covers the requested one. Grants on
+    /// `RESOURCES TAGGED` cover a resource whose tags (see
+    /// `EmulatorState::resource_tags_for`) satisfy every condition.
+    fn resource_covered(&self, resource: &Resource, granted: &Resource) -> bool {
+        match granted {
+            Resource::TaggedResource { tag_conditions } => {
+                let tags = self.state.resource_tags_for(resource);
+                !tag_conditions.is_empty() && tag_conditions.iter().all(|(key, values)| {
+                    tags.get(key).is_some_and(|value| values.contains(value))
+                })
+            },
+            _ => resource.is_covered_by(granted),
+        }
+    }
+
     /// Check if a principal matches (including role membership, tags, etc.)
     fn principal_matches(&self, request_principal: &Principal, permission_principal: &Principal) -> bool {
         match (request_principal, permission_principal) {
-            // Exact matches
-            (Principal::User(u1), Principal::User(u2)) => u1 == u2,
-            (Principal::Role(r1), Principal::Role(r2)) => r1 == r2,
-            (Principal::SamlGroup(g1), Principal::SamlGroup(g2)) => g1 == g2,
+            // Exact matches; a short name matches an ARN, two ARNs must be equal
+            (Principal::User(u1), Principal::User(u2)) => same_iam_name(u1, u2),
+            // A role also matches the roles it is nested in
+            (Principal::Role(_), Principal::Role(role)) => self.membership_path(request_principal, role).is_some(),
+            // Grants to a group pattern like `org:finance:*` cover its subgroups
+            (Principal::SamlGroup(g1), Principal::SamlGroup(g2)) => glob_matches(g2, g1),
             (Principal::ExternalAccount(a1), Principal::ExternalAccount(a2)) => a1 == a2,
 
-            // User can match role if they're a member
-            (Principal::User(user), Principal::Role(role)) => {
-                if let Some(members) = self.state.roles.get(role) {
-                    members.contains(user)
-                } else {
-                    false
-                }
-            },
+            // User can match role if they're a member, directly or through nested roles
+            (Principal::User(_), Principal::Role(role)) => self.membership_path(request_principal, role).is_some(),
 
-            // TODO: Implement tag-based matching
-            (Principal::TaggedPrincipal { .. }, _) => {
-                // For now, tagged principals don't match
-                false
+            // User can match a local group from DEFINE GROUP if listed in it
+            (Principal::User(user), Principal::SamlGroup(group)) => {
+                self.state.local_groups
+                    .get(group)
+                    .is_some_and(|members| members.iter().any(|member| same_iam_name(member, user)))
             },
-            (_, Principal::TaggedPrincipal { .. }) => {
-                // For now, tagged principals don't match
-                false
+
+            // A user or role matches a tag grant when its assigned value for
+            // the key is one of the granted values
+            (Principal::User(name) | Principal::Role(name), Principal::TaggedPrincipal { tag_key, tag_values }) => {
+                self.state.principal_tags
+                    .get(canonical_iam_name(name))
+                    .and_then(|tags| tags.get(tag_key))
+                    .is_some_and(|value| tag_values.contains(value))
             },
+            // A tag expression isn't itself a requester
+            (Principal::TaggedPrincipal { .. }, _) => false,
 
             // Different types don't match
             _ => false,
         }
     }
 
-    /// Evaluate row-level security filters
-    fn evaluate_row_filter(&self, row_filter: &RowFilter, _resource: &Resource) -> bool {
+    /// Shortest membership chain from a principal to a role, e.g.
+    /// user -> analyst -> senior_analyst, or None if it isn't a member
+    fn membership_path(&self, principal: &Principal, role: &str) -> Option<Vec<Principal>> {
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::from([vec![principal.clone()]]);
+
+        while let Some(path) = queue.pop_front() {
+            let last = path.last()?;
+            if matches!(last, Principal::Role(name) if same_iam_name(name, role)) {
+                return Some(path);
+            }
+            for parent in self.state.parent_roles(last) {
+                // Membership cycles are tolerated; each role is visited once
+                if visited.insert(parent) {
+                    let mut next = path.clone();
+                    next.push(Principal::Role(parent.to_string()));
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// Whether `role` inherits the grants of `ancestor`, directly or through
+    /// other roles. A role counts as inheriting from itself.
+    pub fn role_inherits(&self, role: &str, ancestor: &str) -> bool {
+        self.membership_path(&Principal::Role(role.to_string()), ancestor).is_some()
+    }
+
+    /// The chain of principals through which a request is authorized: just
+    /// the principal for a direct grant, or user -> role [-> nested role] for
+    /// access inherited through roles. The shortest chain wins; None if denied.
+    pub fn authorizing_path(&self, principal: &Principal, resource: &Resource, action: &Action) -> Option<Vec<Principal>> {
+        if self.matching_deny(principal, resource, action).is_some() {
+            return None;
+        }
+        if self.state.access_mode(resource) == AccessMode::IamOnly {
+            return Some(vec![principal.clone()]);
+        }
+
+        self.candidates(resource)
+            .map(|(_, permission)| permission)
+            .filter(|permission| permission.effect.is_allow())
+            .filter(|permission| self.matches_permission(principal, resource, action, permission))
+            .filter_map(|permission| match &permission.principal {
+                Principal::Role(role) => self.membership_path(principal, role),
+                granted if granted == principal => Some(vec![principal.clone()]),
+                // Wildcard groups and local groups
+                granted => Some(vec![principal.clone(), granted.clone()]),
+            })
+            .min_by_key(|path| path.len())
+    }
+
+    /// Evaluate row-level security filters. An error (see `EvalError`)
+    /// denies access; it's returned so callers can say why.
+    fn evaluate_row_filter(&self, row_filter: &RowFilter, _resource: &Resource) -> Result<bool> {
         // Create expression evaluator
         let mut evaluator = ExpressionEvaluator::new();
         
@@ -115,13 +481,14 @@ impl EmulatorEngine {
         evaluator.set_row_data(sample_row);
         
         // Evaluate the filter
-        match evaluator.evaluate_filter(row_filter) {
-            Ok(result) => result,
-            Err(_) => {
-                // If evaluation fails, deny access for security
-                false
-            }
-        }
+        evaluator.evaluate_filter(row_filter)
+    }
+
+    /// Evaluate a `WHEN` condition against the session context only
+    fn evaluate_principal_condition(&self, condition: &RowFilter) -> Result<bool> {
+        let mut evaluator = ExpressionEvaluator::new();
+        evaluator.set_session_context(self.state.session_context.clone());
+        evaluator.evaluate_filter(condition)
     }
 
     /// Create sample row data for testing row-level security
@@ -158,7 +525,7 @@ impl EmulatorEngine {
                     }
                 }
             },
-            Resource::Database { name } => {
+            Resource::Database { name, .. } => {
                 // Database-level filters might check metadata
                 row_data.insert("database_owner".to_string(), "admin".to_string());
                 row_data.insert("classification".to_string(), "internal".to_string());
@@ -197,7 +564,10 @@ impl EmulatorEngine {
     /// Add a user to a role
     pub fn add_user_to_role(&mut self, user: String, role: String) -> Result<(), String> {
         if let Some(members) = self.state.roles.get_mut(&role) {
-            members.insert(user);
+            // An ARN and its short name are one member; the first form added is kept
+            if !members.iter().any(|member| same_iam_name(member, &user)) {
+                members.insert(user);
+            }
             Ok(())
         } else {
             Err(format!("Role '{}' does not exist", role))
@@ -207,7 +577,7 @@ impl EmulatorEngine {
     /// Remove a user from a role
     pub fn remove_user_from_role(&mut self, user: &str, role: &str) -> Result<(), String> {
         if let Some(members) = self.state.roles.get_mut(role) {
-            members.remove(user);
+            members.retain(|member| !same_iam_name(member, user));
             Ok(())
         } else {
             Err(format!("Role '{}' does not exist", role))
@@ -226,36 +596,124 @@ impl EmulatorEngine {
         resource: &Resource, 
         action: &Action
     ) -> (bool, String) {
+        if let Some((i, permission)) = self.matching_deny(principal, resource, action) {
+            let denied = permission.actions
+                .iter()
+                .map(|a| a.to_ddl_keyword().to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            return (false, format!(
+                "DENIED: explicit deny by permission {}: DENY [{}] on {:?} to {:?}",
+                i, denied, permission.resource, permission.principal
+            ));
+        }
+
+        if self.state.access_mode(resource) == AccessMode::IamOnly {
+            return (true, "Resource is in IAM_ONLY access mode; Lake Formation checks bypassed".to_string());
+        }
+
         let mut reasons = Vec::new();
+        let mut closest_match = None;
 
-        // Check each permission
+        // Check each grant; denies were handled above
         for (i, permission) in self.state.permissions.iter().enumerate() {
+            if !permission.effect.is_allow() {
+                continue;
+            }
             let principal_match = self.principal_matches(principal, &permission.principal);
-            let action_match = permission.actions.contains(action);
-            let resource_match = resource.is_covered_by(&permission.resource);
-            let row_filter_match = permission.row_filter.as_ref()
-                .map(|f| self.evaluate_row_filter(f, resource))
-                .unwrap_or(true);
+            let action_match = permission.grants_action(action);
+            let resource_match = self.resource_covered(resource, &permission.resource);
+            let (row_filter_match, row_filter_outcome) = describe_outcome(
+                permission.row_filter.as_ref().map(|f| self.evaluate_row_filter(f, resource)),
+            );
+            let (condition_match, condition_outcome) = describe_outcome(
+                permission.principal_condition.as_ref().map(|c| self.evaluate_principal_condition(c)),
+            );
 
             reasons.push(format!(
-                "Permission {}: principal={} action={} resource={} row_filter={} => {}",
+                "Permission {}: principal={} action={} resource={} row_filter={} condition={} => {}",
                 i,
                 principal_match,
                 action_match,
                 resource_match,
-                row_filter_match,
-                principal_match && action_match && resource_match && row_filter_match
+                row_filter_outcome,
+                condition_outcome,
+                principal_match && action_match && resource_match && row_filter_match && condition_match
             ));
 
-            if principal_match && action_match && resource_match && row_filter_match {
+            if principal_match && action_match && resource_match && row_filter_match && condition_match {
                 return (true, reasons.join("\n"));
             }
+
+            if principal_match && resource_match && !action_match && closest_match.is_none() {
+                closest_match = Some((i, permission));
+            }
+        }
+
+        match closest_match {
+            Some((i, permission)) => {
+                let granted = permission.actions
+                    .iter()
+                    .map(|a| a.to_ddl_keyword().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                (false, format!(
+                    "DENIED: closest match: permission {} grants [{}] but requested {}\n{}",
+                    i,
+                    granted,
+                    action.to_ddl_keyword(),
+                    reasons.join("\n")
+                ))
+            },
+            None => (false, format!("DENIED:\n{}", reasons.join("\n"))),
         }
+    }
+}
 
-        (false, format!("DENIED:\n{}", reasons.join("\n")))
+/// Whether a row filter or condition let the request through. Evaluation
+/// errors deny access, and are logged since they usually point at a typo
+/// in the filter or a missing session key rather than a real mismatch.
+fn passes(outcome: Result<bool>, filter: &RowFilter) -> bool {
+    outcome.unwrap_or_else(|err| {
+        tracing::warn!(expression = %filter.expression, error = %err, "filter could not be evaluated; denying access");
+        false
+    })
+}
+
+/// Whether an optional filter or condition passed, and how to show its
+/// outcome in `check_permission_with_reason`
+fn describe_outcome(outcome: Option<Result<bool>>) -> (bool, String) {
+    match outcome {
+        None => (true, "true".to_string()),
+        Some(Ok(matched)) => (matched, matched.to_string()),
+        Some(Err(err)) => (false, format!("error ({})", err)),
     }
 }
 
+/// Match a name against a pattern where `*` stands for any run of
+/// characters (including none); without `*` this is plain equality
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // No `*` in the pattern
+        return rest.is_empty();
+    };
+
+    for part in parts {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
 impl Default for EmulatorEngine {
     fn default() -> Self {
         Self::new()
@@ -272,17 +730,16 @@ mod tests {
         let mut engine = EmulatorEngine::new();
         
         // Create a permission
-        let permission = Permission {
-            principal: Principal::Role("analyst".to_string()),
-            resource: Resource::Table {
+        let permission = Permission::new(
+            Principal::Role("analyst".to_string()),
+            Resource::Table {
                 database: "sales".to_string(),
                 table: "orders".to_string(),
                 columns: None,
+                catalog_id: None,
             },
-            actions: vec![Action::Select, Action::Insert],
-            grant_option: false,
-            row_filter: None,
-        };
+            vec![Action::Select, Action::Insert],
+        );
 
         let mut state = EmulatorState::new();
         state.permissions.push(permission);
@@ -295,6 +752,7 @@ mod tests {
                 database: "sales".to_string(),
                 table: "orders".to_string(),
                 columns: None,
+                catalog_id: None,
             },
             &Action::Select
         );
@@ -307,6 +765,7 @@ mod tests {
                 database: "sales".to_string(),
                 table: "orders".to_string(),
                 columns: None,
+                catalog_id: None,
             },
             &Action::Delete
         );
@@ -324,15 +783,14 @@ mod tests {
         state.roles.insert("analyst".to_string(), members);
         
         // Create permission for role
-        let permission = Permission {
-            principal: Principal::Role("analyst".to_string()),
-            resource: Resource::Database {
+        let permission = Permission::new(
+            Principal::Role("analyst".to_string()),
+            Resource::Database {
                 name: "sales".to_string(),
+                catalog_id: None,
             },
-            actions: vec![Action::Select],
-            grant_option: false,
-            row_filter: None,
-        };
+            vec![Action::Select],
+        );
         state.permissions.push(permission);
         
         engine.update_state(&state);
@@ -342,6 +800,7 @@ mod tests {
             &Principal::User("john@company.com".to_string()),
             &Resource::Database {
                 name: "sales".to_string(),
+                catalog_id: None,
             },
             &Action::Select
         );
@@ -352,6 +811,7 @@ mod tests {
             &Principal::User("jane@company.com".to_string()),
             &Resource::Database {
                 name: "sales".to_string(),
+                catalog_id: None,
             },
             &Action::Select
         );
@@ -363,17 +823,16 @@ mod tests {
         let mut engine = EmulatorEngine::new();
         let mut state = EmulatorState::new();
 
-        let permission = Permission {
-            principal: Principal::Role("analyst".to_string()),
-            resource: Resource::Table {
+        let permission = Permission::new(
+            Principal::Role("analyst".to_string()),
+            Resource::Table {
                 database: "sales".to_string(),
                 table: "orders".to_string(),
                 columns: None,
+                catalog_id: None,
             },
-            actions: vec![Action::Select],
-            grant_option: false,
-            row_filter: None,
-        };
+            vec![Action::Select],
+        );
         state.permissions.push(permission);
         engine.update_state(&state);
 
@@ -383,6 +842,7 @@ mod tests {
                 database: "sales".to_string(),
                 table: "orders".to_string(),
                 columns: None,
+                catalog_id: None,
             },
             &Action::Select
         );
@@ -391,4 +851,683 @@ mod tests {
         assert!(reason.contains("DENIED"));
         assert!(reason.contains("principal=false"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_reason_names_filter_errors() {
+        let mut engine = EmulatorEngine::new();
+        let mut state = EmulatorState::new();
+        let orders = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        let filtered = |expression: &str| Some(RowFilter {
+            expression: expression.to_string(),
+            session_context: None,
+            ast: None,
+        });
+
+        state.permissions.push(Permission {
+            row_filter: filtered("regoin = 'west'"),
+            ..Permission::new(Principal::Role("analyst".to_string()), orders.clone(), vec![Action::Select])
+        });
+        state.permissions.push(Permission {
+            principal_condition: filtered("SESSION_CONTEXT('team') = 'data'"),
+            ..Permission::new(Principal::Role("analyst".to_string()), orders.clone(), vec![Action::Describe])
+        });
+        engine.update_state(&state);
+
+        let analyst = Principal::Role("analyst".to_string());
+        let (allowed, reason) = engine.check_permission_with_reason(&analyst, &orders, &Action::Select);
+        assert!(!allowed);
+        assert!(reason.contains("row_filter=error (Unknown column 'regoin')"));
+
+        let (allowed, reason) = engine.check_permission_with_reason(&analyst, &orders, &Action::Describe);
+        assert!(!allowed);
+        assert!(reason.contains("condition=error (Session context key 'team' not found)"));
+        assert!(!engine.check_permission(&analyst, &orders, &Action::Describe));
+    }
+
+    #[test]
+    fn test_all_grant_authorizes_every_action() {
+        let mut engine = EmulatorEngine::new();
+        let mut state = EmulatorState::new();
+
+        state.permissions.push(Permission::new(
+            Principal::Role("admin".to_string()),
+            Resource::Database {
+                name: "sales".to_string(),
+                catalog_id: None,
+            },
+            vec![Action::All],
+        ));
+        engine.update_state(&state);
+
+        let table = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        let actions = [
+            Action::Select,
+            Action::Insert,
+            Action::Update,
+            Action::Delete,
+            Action::CreateTable,
+            Action::DropTable,
+            Action::AlterTable,
+            Action::Describe,
+            Action::DataLocationAccess,
+            Action::GrantWithGrantOption,
+            Action::All,
+        ];
+        for action in &actions {
+            assert!(
+                engine.check_permission(&Principal::Role("admin".to_string()), &table, action),
+                "ALL should authorize {:?}", action
+            );
+        }
+
+        // ALL is still limited to the granted resource and principal
+        let other_table = Resource::Table {
+            database: "hr".to_string(),
+            table: "employees".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        assert!(!engine.check_permission(&Principal::Role("admin".to_string()), &other_table, &Action::Select));
+        assert!(!engine.check_permission(&Principal::Role("analyst".to_string()), &table, &Action::Select));
+    }
+
+    #[test]
+    fn test_lf_tag_permissions() {
+        let mut engine = EmulatorEngine::new();
+        let mut state = EmulatorState::new();
+
+        state.permissions.push(Permission::new(
+            Principal::Role("data_steward".to_string()),
+            Resource::LfTag {
+                key: "classification".to_string(),
+            },
+            vec![Action::Associate, Action::Describe],
+        ));
+        engine.update_state(&state);
+
+        let steward = Principal::Role("data_steward".to_string());
+        let classification = Resource::LfTag { key: "classification".to_string() };
+        assert!(engine.check_permission(&steward, &classification, &Action::Associate));
+        assert!(engine.check_permission(&steward, &classification, &Action::Describe));
+        assert!(!engine.check_permission(&steward, &classification, &Action::Select));
+        assert!(!engine.check_permission(&steward, &Resource::LfTag { key: "region".to_string() }, &Action::Associate));
+
+        // Tag permissions don't grant access to data
+        assert!(!engine.check_permission(&steward, &Resource::Database {
+            name: "sales".to_string(),
+            catalog_id: None,
+        }, &Action::Describe));
+    }
+
+    #[test]
+    fn test_tagged_principal_grants() {
+        let mut engine = EmulatorEngine::new();
+        let mut state = EmulatorState::new();
+        let sales = Resource::Database { name: "sales".to_string(), catalog_id: None };
+
+        for value in ["finance", "hr"] {
+            state.permissions.push(Permission::new(
+                Principal::TaggedPrincipal {
+                    tag_key: "department".to_string(),
+                    tag_values: vec![value.to_string()],
+                },
+                sales.clone(),
+                vec![if value == "finance" { Action::Describe } else { Action::CreateTable }],
+            ));
+        }
+        state.principal_tags.insert(
+            "finance_team".to_string(),
+            HashMap::from([("department".to_string(), "finance".to_string())]),
+        );
+        engine.update_state(&state);
+
+        let finance = Principal::Role("finance_team".to_string());
+        assert!(engine.check_permission(&finance, &sales, &Action::Describe));
+        assert!(!engine.check_permission(&finance, &sales, &Action::CreateTable));
+        // IAM ARNs match the tags of their short name
+        let arn = Principal::Role("arn:aws:iam::123456789012:role/finance_team".to_string());
+        assert!(engine.check_permission(&arn, &sales, &Action::Describe));
+        // Untagged principals and other keys don't match
+        assert!(!engine.check_permission(&Principal::Role("analyst".to_string()), &sales, &Action::Describe));
+        state.principal_tags.insert(
+            "ana".to_string(),
+            HashMap::from([("region".to_string(), "finance".to_string())]),
+        );
+        engine.update_state(&state);
+        assert!(!engine.check_permission(&Principal::User("ana".to_string()), &sales, &Action::Describe));
+    }
+
+    #[test]
+    fn test_tagged_resource_grants() {
+        let mut engine = EmulatorEngine::new();
+        let mut state = EmulatorState::new();
+        let table = |name: &str| Resource::Table {
+            database: "sales".to_string(),
+            table: name.to_string(),
+            columns: Some(vec!["id".to_string()]),
+            catalog_id: None,
+        };
+
+        state.permissions.push(Permission::new(
+            Principal::Role("auditor".to_string()),
+            Resource::TaggedResource {
+                tag_conditions: vec![
+                    ("classification".to_string(), vec!["confidential".to_string(), "internal".to_string()]),
+                    ("department".to_string(), vec!["finance".to_string()]),
+                ],
+            },
+            vec![Action::Select],
+        ));
+        let tags = |pairs: &[(&str, &str)]| pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<HashMap<_, _>>();
+        state.resource_tags.insert("sales".to_string(), tags(&[("department", "finance")]));
+        state.resource_tags.insert("sales.orders".to_string(), tags(&[("classification", "confidential")]));
+        state.resource_tags.insert("sales.budgets".to_string(), tags(&[("classification", "internal")]));
+        state.resource_tags.insert("sales.promos".to_string(), tags(&[("classification", "public")]));
+        engine.update_state(&state);
+
+        let auditor = Principal::Role("auditor".to_string());
+        // Every condition must hold; any listed value satisfies one
+        assert!(engine.check_permission(&auditor, &table("orders"), &Action::Select));
+        assert!(engine.check_permission(&auditor, &table("budgets"), &Action::Select));
+        assert!(!engine.check_permission(&auditor, &table("promos"), &Action::Select));
+        assert!(!engine.check_permission(&auditor, &table("untagged"), &Action::Select));
+        assert!(!engine.check_permission(&auditor, &table("orders"), &Action::Insert));
+
+        let (allowed, reason) = engine.check_permission_with_reason(&auditor, &table("orders"), &Action::Select);
+        assert!(allowed);
+        assert!(reason.contains("resource=true"));
+    }
+
+    #[test]
+    fn test_denial_reports_closest_action_match() {
+        let mut engine = EmulatorEngine::new();
+        let mut state = EmulatorState::new();
+
+        let orders = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        for (principal, actions) in [
+            ("engineer", vec![Action::Delete]),
+            ("analyst", vec![Action::Describe]),
+            ("analyst", vec![Action::Select, Action::Insert]),
+        ] {
+            state.permissions.push(Permission::new(
+                Principal::Role(principal.to_string()),
+                orders.clone(),
+                actions,
+            ));
+        }
+        engine.update_state(&state);
+
+        let (allowed, reason) = engine.check_permission_with_reason(
+            &Principal::Role("analyst".to_string()),
+            &orders,
+            &Action::Delete,
+        );
+        assert!(!allowed);
+        assert!(reason.starts_with("DENIED: closest match: permission 1 grants [DESCRIBE] but requested DELETE\n"));
+
+        // No hint when nothing matches on principal and resource
+        let (_, reason) = engine.check_permission_with_reason(
+            &Principal::Role("auditor".to_string()),
+            &orders,
+            &Action::Delete,
+        );
+        assert!(!reason.contains("closest match"));
+    }
+
+    #[test]
+    fn test_role_members_are_canonical() {
+        let mut engine = EmulatorEngine::new();
+        let mut state = EmulatorState::new();
+        state.roles.insert("analyst".to_string(), std::collections::HashSet::new());
+        state.permissions.push(Permission::new(
+            Principal::Role("analyst".to_string()),
+            Resource::Database { name: "sales".to_string(), catalog_id: None },
+            vec![Action::Select],
+        ));
+        state.permissions.push(Permission::new(
+            Principal::User("arn:aws:iam::123456789012:user/engineering/bob".to_string()),
+            Resource::Database { name: "hr".to_string(), catalog_id: None },
+            vec![Action::Describe],
+        ));
+        engine.update_state(&state);
+
+        engine.add_user_to_role("arn:aws:iam::123456789012:user/alice".to_string(), "analyst".to_string()).unwrap();
+        engine.add_user_to_role("alice".to_string(), "analyst".to_string()).unwrap();
+        assert_eq!(engine.get_role_members("analyst").unwrap().len(), 1);
+
+        // Membership holds whichever form the check uses
+        let sales = Resource::Database { name: "sales".to_string(), catalog_id: None };
+        assert!(engine.check_permission(&Principal::User("alice".to_string()), &sales, &Action::Select));
+        assert!(engine.check_permission(
+            &Principal::User("arn:aws:iam::123456789012:user/alice".to_string()),
+            &sales,
+            &Action::Select,
+        ));
+
+        // A short-name check matches a grant made to the ARN
+        let hr = Resource::Database { name: "hr".to_string(), catalog_id: None };
+        assert!(engine.check_permission(&Principal::User("bob".to_string()), &hr, &Action::Describe));
+        assert!(!engine.check_permission(&Principal::User("carol".to_string()), &hr, &Action::Describe));
+
+        engine.remove_user_from_role("arn:aws:iam::123456789012:user/alice", "analyst").unwrap();
+        assert!(engine.get_role_members("analyst").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_session_condition_on_grant() {
+        let mut engine = EmulatorEngine::new();
+        let mut state = EmulatorState::new();
+
+        let orders = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        state.permissions.push(Permission {
+            principal_condition: Some(RowFilter {
+                expression: "SESSION_CONTEXT('mfa')='true'".to_string(),
+                session_context: None,
+                ast: None,
+            }),
+            ..Permission::new(Principal::Role("analyst".to_string()), orders.clone(), vec![Action::Select])
+        });
+        let analyst = Principal::Role("analyst".to_string());
+
+        // Missing context key leaves the condition unsatisfied
+        engine.update_state(&state);
+        assert!(!engine.check_permission(&analyst, &orders, &Action::Select));
+
+        state.session_context.insert("mfa".to_string(), "false".to_string());
+        engine.update_state(&state);
+        assert!(!engine.check_permission(&analyst, &orders, &Action::Select));
+        let (_, reason) = engine.check_permission_with_reason(&analyst, &orders, &Action::Select);
+        assert!(reason.contains("condition=false"));
+
+        state.session_context.insert("mfa".to_string(), "true".to_string());
+        engine.update_state(&state);
+        assert!(engine.check_permission(&analyst, &orders, &Action::Select));
+    }
+
+    #[test]
+    fn test_can_grant_per_action() {
+        let mut engine = EmulatorEngine::new();
+        let mut state = EmulatorState::new();
+
+        let orders = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        state.permissions.push(Permission {
+            grantable_actions: HashSet::from([Action::Select]),
+            ..Permission::new(
+                Principal::Role("analyst".to_string()),
+                orders.clone(),
+                vec![Action::Select, Action::Insert],
+            )
+        });
+        engine.update_state(&state);
+        let analyst = Principal::Role("analyst".to_string());
+
+        // Both actions are usable, only SELECT can be passed on
+        assert!(engine.check_permission(&analyst, &orders, &Action::Insert));
+        assert!(engine.can_grant(&analyst, &orders, &Action::Select));
+        assert!(!engine.can_grant(&analyst, &orders, &Action::Insert));
+        assert!(!engine.can_grant(&analyst, &orders, &Action::Delete));
+    }
+
+    #[test]
+    fn test_explicit_deny_wins() {
+        let mut engine = EmulatorEngine::new();
+        let mut state = EmulatorState::new();
+
+        let orders = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        let intern = Principal::Role("intern".to_string());
+        state.permissions.push(Permission {
+            grantable_actions: HashSet::from([Action::Select, Action::Insert]),
+            ..Permission::new(intern.clone(), orders.clone(), vec![Action::Select, Action::Insert])
+        });
+        state.permissions.push(Permission {
+            effect: Effect::Deny,
+            ..Permission::new(
+                intern.clone(),
+                Resource::Database { name: "sales".to_string(), catalog_id: None },
+                vec![Action::Select],
+            )
+        });
+        engine.update_state(&state);
+
+        assert!(!engine.check_permission(&intern, &orders, &Action::Select));
+        assert!(!engine.can_grant(&intern, &orders, &Action::Select));
+        assert!(engine.authorizing_path(&intern, &orders, &Action::Select).is_none());
+        assert!(engine.check_permission(&intern, &orders, &Action::Insert));
+        assert!(engine.can_grant(&intern, &orders, &Action::Insert));
+
+        let (allowed, reason) = engine.check_permission_with_reason(&intern, &orders, &Action::Select);
+        assert!(!allowed);
+        assert!(reason.starts_with("DENIED: explicit deny by permission 1: DENY [SELECT]"), "{}", reason);
+    }
+
+    #[test]
+    fn test_column_permission_unions_grants() {
+        let mut engine = EmulatorEngine::new();
+        let mut state = EmulatorState::new();
+        let grant = |resource: Resource, effect: Effect| Permission {
+            effect,
+            ..Permission::new(Principal::Role("analyst".to_string()), resource, vec![Action::Select])
+        };
+        let orders = |columns: &[&str]| Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: Some(columns.iter().map(|c| c.to_string()).collect()),
+            catalog_id: None,
+        };
+        state.permissions.push(grant(orders(&["id", "region"]), Effect::Allow));
+        state.permissions.push(grant(orders(&["amount"]), Effect::Allow));
+        engine.update_state(&state);
+
+        let analyst = Principal::Role("analyst".to_string());
+        let check = |engine: &EmulatorEngine, table: &str, columns: &[&str]| {
+            let columns: Vec<String> = columns.iter().map(|c| c.to_string()).collect();
+            engine.check_column_permission(&analyst, "sales", table, &columns, &Action::Select)
+        };
+
+        // Neither grant covers (id, amount) alone; together they do
+        assert!(check(&engine, "orders", &["id", "amount"]));
+        assert!(check(&engine, "orders", &["region"]));
+        assert!(!check(&engine, "orders", &["id", "customer"]));
+        assert!(!check(&engine, "orders", &[]));
+        assert!(!check(&engine, "refunds", &["id"]));
+        assert!(!engine.check_column_permission(&analyst, "sales", "orders", &["id".to_string()], &Action::Insert));
+
+        // A database grant covers the whole table; a column deny still wins
+        state.permissions.push(grant(Resource::Database { name: "sales".to_string(), catalog_id: None }, Effect::Allow));
+        state.permissions.push(grant(orders(&["amount"]), Effect::Deny));
+        engine.update_state(&state);
+        assert!(check(&engine, "refunds", &[]));
+        assert!(check(&engine, "orders", &["id", "customer"]));
+        assert!(!check(&engine, "orders", &["id", "amount"]));
+        assert!(!check(&engine, "orders", &[]));
+    }
+
+    #[test]
+    fn test_indexed_checks_match_linear_scan() {
+        // Deterministic pseudo-random numbers, so failures reproduce
+        let mut seed: u64 = 0x5eed;
+        let mut next = move |n: usize| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((seed >> 33) as usize) % n
+        };
+        let actions = [Action::Select, Action::Insert, Action::Delete, Action::Describe, Action::All];
+        let resource = |next: &mut dyn FnMut(usize) -> usize| {
+            let database = format!("db{}", next(20));
+            let table = format!("t{}", next(50));
+            match next(10) {
+                0 => Resource::Database { name: database, catalog_id: None },
+                1 => Resource::AllTables { database, catalog_id: None },
+                2 => Resource::View { database, view: table },
+                3 => Resource::Table {
+                    database,
+                    table,
+                    columns: Some(vec![format!("c{}", next(3))]),
+                    catalog_id: None,
+                },
+                4 => Resource::DataLocation { path: format!("s3://lake/db{}", next(20)) },
+                5 => Resource::TaggedResource {
+                    tag_conditions: vec![("tier".to_string(), vec![format!("{}", next(4))])],
+                },
+                _ => Resource::Table { database, table, columns: None, catalog_id: None },
+            }
+        };
+
+        let mut state = EmulatorState::new();
+        for role in 0..10 {
+            let members = (0..3).map(|_| format!("user{}", next(50))).collect();
+            state.roles.insert(format!("role{}", role), members);
+        }
+        for database in 0..20 {
+            state.resource_tags.insert(
+                format!("db{}.t{}", database, next(50)),
+                HashMap::from([("tier".to_string(), format!("{}", next(4)))]),
+            );
+        }
+        for _ in 0..10_000 {
+            let principal = if next(4) == 0 {
+                Principal::User(format!("user{}", next(50)))
+            } else {
+                Principal::Role(format!("role{}", next(10)))
+            };
+            state.permissions.push(Permission {
+                effect: if next(20) == 0 { Effect::Deny } else { Effect::Allow },
+                ..Permission::new(principal, resource(&mut next), vec![actions[next(actions.len())].clone()])
+            });
+        }
+
+        let mut engine = EmulatorEngine::new();
+        engine.update_state(&state);
+        let brute_force = |principal: &Principal, resource: &Resource, action: &Action| {
+            let matching = |effect: Effect| state.permissions.iter().any(|permission| {
+                permission.effect == effect && engine.matches_permission(principal, resource, action, permission)
+            });
+            !matching(Effect::Deny) && matching(Effect::Allow)
+        };
+
+        let mut allowed = 0;
+        for _ in 0..100 {
+            let principal = Principal::User(format!("user{}", next(50)));
+            let resource = match resource(&mut next) {
+                // Requests are for concrete resources
+                Resource::TaggedResource { .. } => continue,
+                resource => resource,
+            };
+            let action = &actions[next(4)];
+            let expected = brute_force(&principal, &resource, action);
+            assert_eq!(engine.check_permission(&principal, &resource, action), expected, "{:?} {:?} {:?}", principal, resource, action);
+            allowed += expected as usize;
+        }
+        // Both outcomes were exercised
+        assert!(allowed > 0 && allowed < 100);
+    }
+
+    #[test]
+    fn test_same_name_in_another_account_is_denied() {
+        let mut engine = EmulatorEngine::new();
+        let mut state = EmulatorState::new();
+        let sales = Resource::Database { name: "sales".to_string(), catalog_id: None };
+        state.roles.insert(
+            "analyst".to_string(),
+            HashSet::from(["arn:aws:iam::111111111111:user/carol".to_string()]),
+        );
+        state.permissions.push(Permission::new(
+            Principal::User("arn:aws:iam::111111111111:user/alice".to_string()),
+            sales.clone(),
+            vec![Action::Select],
+        ));
+        state.permissions.push(Permission::new(Principal::Role("analyst".to_string()), sales.clone(), vec![Action::Describe]));
+        engine.update_state(&state);
+
+        let user = |arn: &str| Principal::User(arn.to_string());
+        assert!(engine.check_permission(&user("arn:aws:iam::111111111111:user/alice"), &sales, &Action::Select));
+        assert!(engine.check_permission(&user("alice"), &sales, &Action::Select));
+        assert!(!engine.check_permission(&user("arn:aws:iam::222222222222:user/alice"), &sales, &Action::Select));
+
+        // Role membership compares the account too
+        assert!(engine.check_permission(&user("carol"), &sales, &Action::Describe));
+        assert!(!engine.check_permission(&user("arn:aws:iam::222222222222:user/carol"), &sales, &Action::Describe));
+
+        // Distinct accounts stay distinct members
+        engine.add_user_to_role("arn:aws:iam::222222222222:user/carol".to_string(), "analyst".to_string()).unwrap();
+        assert_eq!(engine.get_role_members("analyst").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_saml_group_wildcards() {
+        let mut engine = EmulatorEngine::new();
+        let mut state = EmulatorState::new();
+
+        let orders = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        for group in ["org:finance:*", "org:*:auditors", "org:hr"] {
+            state.permissions.push(Permission::new(
+                Principal::SamlGroup(group.to_string()),
+                orders.clone(),
+                vec![Action::Select],
+            ));
+        }
+        engine.update_state(&state);
+
+        let allowed = |group: &str| {
+            engine.check_permission(&Principal::SamlGroup(group.to_string()), &orders, &Action::Select)
+        };
+        assert!(allowed("org:finance:analysts"));
+        assert!(allowed("org:finance:"));
+        assert!(allowed("org:legal:auditors"));
+        assert!(allowed("org:hr"));
+
+        assert!(!allowed("org:financeteam"));
+        assert!(!allowed("org:legal:auditors:extra"));
+        assert!(!allowed("org:hr:payroll"));
+        assert!(!allowed("org:*"));
+
+        assert!(glob_matches("*", "anything"));
+        assert!(glob_matches("a*b*c", "aXXbYYc"));
+        assert!(!glob_matches("a*b*c", "aXXcYYb"));
+    }
+
+    #[test]
+    fn test_authorizing_path() {
+        let mut engine = EmulatorEngine::new();
+        let mut state = EmulatorState::new();
+        let orders = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        let grant = |principal: Principal, table: &str| Permission::new(
+            principal,
+            Resource::Table {
+                database: "sales".to_string(),
+                table: table.to_string(),
+                columns: None,
+                catalog_id: None,
+            },
+            vec![Action::Select],
+        );
+
+        // alice -> analyst -> senior_analyst, which is granted orders
+        state.roles.insert("analyst".to_string(), HashSet::from(["alice".to_string()]));
+        state.roles.insert("senior_analyst".to_string(), HashSet::new());
+        state.role_parents.insert("analyst".to_string(), HashSet::from(["senior_analyst".to_string()]));
+        state.permissions.push(grant(Principal::Role("senior_analyst".to_string()), "orders"));
+        state.permissions.push(grant(Principal::Role("analyst".to_string()), "refunds"));
+        state.permissions.push(grant(Principal::User("bob".to_string()), "orders"));
+        engine.update_state(&state);
+
+        let alice = Principal::User("arn:aws:iam::123456789012:user/alice".to_string());
+        let role = |name: &str| Principal::Role(name.to_string());
+
+        // Direct grant
+        let bob = Principal::User("bob".to_string());
+        assert_eq!(engine.authorizing_path(&bob, &orders, &Action::Select), Some(vec![bob.clone()]));
+
+        // Single role
+        let refunds = grant(role("analyst"), "refunds").resource;
+        assert_eq!(
+            engine.authorizing_path(&alice, &refunds, &Action::Select),
+            Some(vec![alice.clone(), role("analyst")]),
+        );
+
+        // Nested role
+        assert!(engine.check_permission(&alice, &orders, &Action::Select));
+        assert_eq!(
+            engine.authorizing_path(&alice, &orders, &Action::Select),
+            Some(vec![alice.clone(), role("analyst"), role("senior_analyst")]),
+        );
+        assert_eq!(
+            engine.authorizing_path(&role("analyst"), &orders, &Action::Select),
+            Some(vec![role("analyst"), role("senior_analyst")]),
+        );
+
+        // Denied requests have no path, and membership cycles terminate
+        assert_eq!(engine.authorizing_path(&alice, &orders, &Action::Delete), None);
+        state.role_parents.insert("senior_analyst".to_string(), HashSet::from(["analyst".to_string()]));
+        engine.update_state(&state);
+        let carol = Principal::User("carol".to_string());
+        assert_eq!(engine.authorizing_path(&carol, &orders, &Action::Select), None);
+    }
+
+    #[test]
+    fn test_incremental_index_matches_rebuild() {
+        let table = |database: &str, table: &str| Resource::Table {
+            database: database.to_string(),
+            table: table.to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        let grant = |role: &str, resource: Resource, action: Action| {
+            Permission::new(Principal::Role(role.to_string()), resource, vec![action])
+        };
+        let assert_in_step = |engine: &EmulatorEngine| {
+            assert_eq!(engine.index, PermissionIndex::build(&engine.state.permissions));
+        };
+
+        let mut engine = EmulatorEngine::new();
+        engine.apply_role("analyst", HashSet::new());
+        for permission in [
+            grant("analyst", table("sales", "orders"), Action::Select),
+            grant("engineer", table("sales", "orders"), Action::Insert),
+            grant("analyst", Resource::Database { name: "sales".to_string(), catalog_id: None }, Action::Describe),
+            grant("engineer", table("hr", "staff"), Action::Select),
+            grant("analyst", Resource::DataLocation { path: "s3://lake/sales".to_string() }, Action::DataLocationAccess),
+            // Merges into the first grant without moving anything
+            grant("analyst", table("sales", "orders"), Action::Delete),
+        ] {
+            engine.apply_grant(&permission);
+            assert_in_step(&engine);
+        }
+        assert_eq!(engine.state.permissions.len(), 5);
+
+        // Narrowing in place, then removing from the middle of the list
+        engine.apply_revoke(|p| {
+            p.revoke_actions(&[Action::Delete]);
+            !p.actions.is_empty()
+        });
+        assert_in_step(&engine);
+        engine.apply_revoke(|p| p.principal != Principal::Role("engineer".to_string()) || p.resource != table("sales", "orders"));
+        assert_in_step(&engine);
+        engine.apply_drop_role("analyst");
+        assert_in_step(&engine);
+        assert_eq!(engine.state.permissions.len(), 1);
+        assert!(engine.check_permission(&Principal::Role("engineer".to_string()), &table("hr", "staff"), &Action::Select));
+    }
+}
@@ -1,16 +1,60 @@
 //! Expression evaluation engine for row-level security filters
 
+use crate::clock::{self, Clock, SystemClock};
 use lakesql_core::*;
 use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::Arc;
 use anyhow::{Result, anyhow};
 
+/// A custom scalar function callable from filter expressions; receives the
+/// resolved argument values
+pub type ScalarFunction = Box<dyn Fn(&[String]) -> Result<String> + Send + Sync>;
+
+/// Shared form of a registered function so the evaluator stays `Clone`
+type SharedFunction = Arc<dyn Fn(&[String]) -> Result<String> + Send + Sync>;
+
+/// Session context key that `CURRENT_USER` resolves to
+pub const CURRENT_USER_KEY: &str = "principal_id";
+
+/// Why a filter could not be evaluated. Returned inside `anyhow::Error`;
+/// use `downcast_ref::<EvalError>()` to tell the cases apart.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum EvalError {
+    /// The filter names a column the row doesn't have, often a typo
+    #[error("Unknown column '{0}'")]
+    UnknownColumn(String),
+    /// `SESSION_CONTEXT('key')` without a default, and the key isn't set
+    #[error("Session context key '{0}' not found")]
+    MissingSessionKey(String),
+    /// The filter text isn't valid filter syntax
+    #[error("Cannot parse filter expression: {0}")]
+    UnparsableExpression(String),
+}
+
 /// Simple expression evaluator for row-level security
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct ExpressionEvaluator {
     /// Available session context
     session_context: HashMap<String, String>,
     /// Sample row data for evaluation
     row_data: HashMap<String, String>,
+    /// Custom scalar functions, keyed by lowercase name
+    functions: HashMap<String, SharedFunction>,
+    /// Source of CURRENT_TIMESTAMP and CURRENT_DATE
+    clock: Arc<dyn Clock>,
+}
+
+impl fmt::Debug for ExpressionEvaluator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExpressionEvaluator")
+            .field("session_context", &self.session_context)
+            .field("row_data", &self.row_data)
+            .field("functions", &self.functions.keys().collect::<Vec<_>>())
+            .field("clock", &self.clock)
+            .finish()
+    }
 }
 
 impl ExpressionEvaluator {
@@ -18,9 +62,17 @@ impl ExpressionEvaluator {
         Self {
             session_context: HashMap::new(),
             row_data: HashMap::new(),
+            functions: HashMap::new(),
+            clock: Arc::new(SystemClock),
         }
     }
 
+    /// Register a custom scalar function (e.g. `in_region_group('emea')`).
+    /// Names are case-insensitive; built-ins like SESSION_CONTEXT take precedence.
+    pub fn register_function(&mut self, name: &str, function: ScalarFunction) {
+        self.functions.insert(name.to_lowercase(), Arc::from(function));
+    }
+
     /// Set session context (like current user's region, department, etc.)
     pub fn set_session_context(&mut self, context: HashMap<String, String>) {
         self.session_context = context;
@@ -31,105 +83,122 @@ impl ExpressionEvaluator {
         self.row_data = row;
     }
 
-    /// Evaluate a row filter expression
-    pub fn evaluate_filter(&self, filter: &RowFilter) -> Result<bool> {
-        // For now, do simple string-based evaluation
-        // In a real implementation, you'd parse this into an AST
-        self.evaluate_expression(&filter.expression)
+    /// Replace the clock used for CURRENT_TIMESTAMP and CURRENT_DATE, e.g.
+    /// with a `FixedClock` in tests
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
     }
 
-    /// Evaluate a simple expression (basic implementation)
-    fn evaluate_expression(&self, expr: &str) -> Result<bool> {
-        let expr = expr.trim();
-        
-        // Handle WHERE keyword
-        let expr = if expr.to_uppercase().starts_with("WHERE ") {
-            &expr[6..]
-        } else {
-            expr
-        };
-
-        // Handle simple comparisons: column = value
-        if let Some((left, right)) = self.split_comparison(expr, "=") {
-            return self.evaluate_equals(left.trim(), right.trim());
-        }
-
-        // Handle inequalities  
-        if let Some((left, right)) = self.split_comparison(expr, "!=") {
-            let equals = self.evaluate_equals(left.trim(), right.trim())?;
-            return Ok(!equals);
-        }
-
-        // Handle SESSION_CONTEXT calls
-        if expr.contains("SESSION_CONTEXT") {
-            return self.evaluate_session_context_expression(expr);
-        }
-
-        // Handle logical operators (AND, OR)
-        if expr.contains(" AND ") {
-            return self.evaluate_logical_and(expr);
-        }
-        
-        if expr.contains(" OR ") {
-            return self.evaluate_logical_or(expr);
+    /// Evaluate a row filter expression
+    pub fn evaluate_filter(&self, filter: &RowFilter) -> Result<bool> {
+        if let Some(ast) = &filter.ast {
+            return self.evaluate_ast(ast);
         }
 
-        // Default: try to evaluate as boolean literal
-        match expr.to_uppercase().as_str() {
-            "TRUE" => Ok(true),
-            "FALSE" => Ok(false),
-            _ => Err(anyhow!("Cannot evaluate expression: {}", expr)),
-        }
+        // Filters built by hand or loaded from older state only carry the text
+        let ast = lakesql_parser::parse_filter_expression(&filter.expression)
+            .map_err(|err| EvalError::UnparsableExpression(err.to_string()))?;
+        self.evaluate_ast(&ast)
     }
 
-    /// Split expression on comparison operator
-    fn split_comparison<'a>(&self, expr: &'a str, op: &str) -> Option<(&'a str, &'a str)> {
-        if let Some(pos) = expr.find(op) {
-            let left = &expr[..pos];
-            let right = &expr[pos + op.len()..];
-            Some((left, right))
-        } else {
-            None
+    /// Evaluate a parsed filter expression
+    pub fn evaluate_ast(&self, expr: &FilterExpr) -> Result<bool> {
+        match expr {
+            FilterExpr::And(left, right) => Ok(self.evaluate_ast(left)? && self.evaluate_ast(right)?),
+            FilterExpr::Or(left, right) => Ok(self.evaluate_ast(left)? || self.evaluate_ast(right)?),
+            FilterExpr::Not(inner) => Ok(!self.evaluate_ast(inner)?),
+            FilterExpr::Comparison { left, op, right } => {
+                let (Some(left_value), Some(right_value)) = (self.resolve_operand(left)?, self.resolve_operand(right)?) else {
+                    // As in SQL, comparing with NULL is never true
+                    return Ok(false);
+                };
+
+                // Equality with a session value also matches any member of
+                // a comma-separated list, e.g. `allowed_regions=west,central`
+                let membership = match (left, right) {
+                    (FilterOperand::SessionContext { .. }, FilterOperand::SessionContext { .. }) => None,
+                    (FilterOperand::SessionContext { .. }, _) => Some(members_match(&right_value, &left_value)),
+                    (_, FilterOperand::SessionContext { .. }) => Some(members_match(&left_value, &right_value)),
+                    _ => None,
+                };
+                match (op, membership) {
+                    (ComparisonOp::Eq, Some(matched)) => Ok(matched),
+                    (ComparisonOp::NotEq, Some(matched)) => Ok(!matched),
+                    _ => Ok(compare(&left_value, *op, &right_value)),
+                }
+            },
+            FilterExpr::In { left, list } => {
+                let Some(left) = self.resolve_operand(left)? else {
+                    return Ok(false);
+                };
+                for item in list {
+                    let matched = match (item, self.resolve_operand(item)?) {
+                        (_, None) => false,
+                        (FilterOperand::SessionContext { .. }, Some(set)) => members_match(&left, &set),
+                        (_, Some(value)) => compare(&left, ComparisonOp::Eq, &value),
+                    };
+                    if matched {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            },
+            FilterExpr::IsNull { operand, negated } => {
+                let is_null = self.resolve_nullable(operand)?.is_none();
+                Ok(is_null != *negated)
+            },
+            FilterExpr::Value(operand) => match self.resolve_operand(operand)? {
+                Some(value) if value.eq_ignore_ascii_case("TRUE") => Ok(true),
+                Some(value) if value.eq_ignore_ascii_case("FALSE") => Ok(false),
+                None => Ok(false),
+                Some(value) => Err(anyhow!("Expected TRUE or FALSE, got '{}'", value)),
+            },
         }
     }
 
-    /// Evaluate equality comparison
-    fn evaluate_equals(&self, left: &str, right: &str) -> Result<bool> {
-        let left_value = self.resolve_value(left)?;
-        let right_value = self.resolve_value(right)?;
-        
-        Ok(left_value == right_value)
-    }
-
-    /// Resolve a value (column reference, literal, or function call)
-    fn resolve_value(&self, value: &str) -> Result<String> {
-        let value = value.trim();
-
-        // String literal
-        if (value.starts_with('\'') && value.ends_with('\'')) ||
-           (value.starts_with('"') && value.ends_with('"')) {
-            return Ok(value[1..value.len()-1].to_string());
-        }
-
-        // SESSION_CONTEXT function
-        if value.starts_with("SESSION_CONTEXT(") && value.ends_with(")") {
-            let key = &value[16..value.len()-1]; // Remove "SESSION_CONTEXT(" and ")"
-            let key = key.trim_matches('\'').trim_matches('"'); // Remove quotes
-            return self.get_session_context(key);
-        }
-
-        // Column reference - check row data
-        if let Some(row_value) = self.row_data.get(value) {
-            return Ok(row_value.clone());
+    /// Resolve an operand to its value; `None` stands for NULL
+    fn resolve_operand(&self, operand: &FilterOperand) -> Result<Option<String>> {
+        match operand {
+            FilterOperand::Column(name) => self.row_data
+                .get(name)
+                .cloned()
+                .map(Some)
+                .ok_or_else(|| EvalError::UnknownColumn(name.clone()).into()),
+            FilterOperand::Literal(value) | FilterOperand::Number(value) => Ok(Some(value.clone())),
+            FilterOperand::Null => Ok(None),
+            FilterOperand::SessionContext { key, default } => match (self.session_context.get(key), default) {
+                (Some(value), _) => Ok(Some(value.clone())),
+                (None, Some(default)) => self.resolve_operand(default),
+                (None, None) => self.get_session_context(key).map(Some),
+            },
+            FilterOperand::CurrentUser => self.get_session_context(CURRENT_USER_KEY).map(Some),
+            FilterOperand::CurrentTimestamp => Ok(Some(clock::format_utc(self.clock.now()))),
+            FilterOperand::CurrentDate => Ok(Some(clock::format_utc(self.clock.now())[..10].to_string())),
+            FilterOperand::Function { name, args } => {
+                let function = self.functions
+                    .get(&name.to_lowercase())
+                    .ok_or_else(|| anyhow!("Unknown function '{}'", name))?;
+                let mut values = Vec::with_capacity(args.len());
+                for arg in args {
+                    match self.resolve_operand(arg)? {
+                        Some(value) => values.push(value),
+                        // A NULL argument makes the call NULL
+                        None => return Ok(None),
+                    }
+                }
+                function(&values).map(Some)
+            },
         }
+    }
 
-        // Numeric literal
-        if value.parse::<f64>().is_ok() {
-            return Ok(value.to_string());
+    /// Resolve an operand for IS [NOT] NULL, where a column absent from the
+    /// row or an unset session key is NULL rather than an error
+    fn resolve_nullable(&self, operand: &FilterOperand) -> Result<Option<String>> {
+        match operand {
+            FilterOperand::Column(name) => Ok(self.row_data.get(name).cloned()),
+            FilterOperand::SessionContext { key, default: None } => Ok(self.session_context.get(key).cloned()),
+            other => self.resolve_operand(other),
         }
-
-        // Unknown - return as is
-        Ok(value.to_string())
     }
 
     /// Get session context value
@@ -137,43 +206,7 @@ impl ExpressionEvaluator {
         self.session_context
             .get(key)
             .cloned()
-            .ok_or_else(|| anyhow!("Session context key '{}' not found", key))
-    }
-
-    /// Evaluate SESSION_CONTEXT expression
-    fn evaluate_session_context_expression(&self, expr: &str) -> Result<bool> {
-        // This handles expressions like: region = SESSION_CONTEXT('user_region')
-        if let Some((left, right)) = self.split_comparison(expr, "=") {
-            return self.evaluate_equals(left.trim(), right.trim());
-        }
-        
-        Err(anyhow!("Cannot evaluate SESSION_CONTEXT expression: {}", expr))
-    }
-
-    /// Evaluate logical AND
-    fn evaluate_logical_and(&self, expr: &str) -> Result<bool> {
-        let parts: Vec<&str> = expr.split(" AND ").collect();
-        
-        for part in parts {
-            if !self.evaluate_expression(part.trim())? {
-                return Ok(false);
-            }
-        }
-        
-        Ok(true)
-    }
-
-    /// Evaluate logical OR  
-    fn evaluate_logical_or(&self, expr: &str) -> Result<bool> {
-        let parts: Vec<&str> = expr.split(" OR ").collect();
-        
-        for part in parts {
-            if self.evaluate_expression(part.trim())? {
-                return Ok(true);
-            }
-        }
-        
-        Ok(false)
+            .ok_or_else(|| EvalError::MissingSessionKey(key.to_string()).into())
     }
 }
 
@@ -197,10 +230,74 @@ pub fn create_session_context(data: Vec<(&str, &str)>) -> HashMap<String, String
         .collect()
 }
 
+/// Compare two values, numerically when both are finite numbers, so
+/// `100 = 100.0`. Anything else, including `NaN`, compares as text.
+fn compare(left: &str, op: ComparisonOp, right: &str) -> bool {
+    match op {
+        ComparisonOp::Like => return like_matches(right, left),
+        ComparisonOp::NotLike => return !like_matches(right, left),
+        _ => {},
+    }
+
+    let number = |value: &str| value.trim().parse::<f64>().ok().filter(|n| n.is_finite());
+    let ordering = match (number(left), number(right)) {
+        // Finite numbers always have an ordering
+        (Some(l), Some(r)) => l.partial_cmp(&r).unwrap_or(Ordering::Equal),
+        _ => left.cmp(right),
+    };
+
+    match op {
+        ComparisonOp::Eq => ordering.is_eq(),
+        ComparisonOp::NotEq => ordering.is_ne(),
+        ComparisonOp::Lt => ordering.is_lt(),
+        ComparisonOp::LtEq => ordering.is_le(),
+        ComparisonOp::Gt => ordering.is_gt(),
+        ComparisonOp::GtEq => ordering.is_ge(),
+        ComparisonOp::Like | ComparisonOp::NotLike => unreachable!(),
+    }
+}
+
+/// Whether `value` equals `set` as a whole or one of its comma-separated
+/// members
+fn members_match(value: &str, set: &str) -> bool {
+    compare(value, ComparisonOp::Eq, set)
+        || set.split(',').any(|member| compare(value, ComparisonOp::Eq, member.trim()))
+}
+
+/// SQL LIKE: `%` matches any run of characters, `_` exactly one
+fn like_matches(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+
+    // matches[j]: pattern prefix seen so far matches value[..j]
+    let mut matches = vec![false; value.len() + 1];
+    matches[0] = true;
+    for p in &pattern {
+        let mut next = vec![false; value.len() + 1];
+        for j in 0..=value.len() {
+            next[j] = match p {
+                '%' => matches[j] || (j > 0 && next[j - 1]),
+                '_' => j > 0 && matches[j - 1],
+                c => j > 0 && matches[j - 1] && value[j - 1] == *c,
+            };
+        }
+        matches = next;
+    }
+    matches[value.len()]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn eval(evaluator: &ExpressionEvaluator, expression: &str) -> Result<bool> {
+        evaluator.evaluate_filter(&RowFilter {
+            expression: expression.to_string(),
+            session_context: None,
+            ast: None,
+        })
+    }
+
     #[test]
     fn test_simple_equality() {
         let mut evaluator = ExpressionEvaluator::new();
@@ -214,6 +311,7 @@ mod tests {
         let filter = RowFilter {
             expression: "region = 'west'".to_string(),
             session_context: None,
+            ast: None,
         };
 
         let result = evaluator.evaluate_filter(&filter).unwrap();
@@ -239,6 +337,7 @@ mod tests {
         let filter = RowFilter {
             expression: "region = SESSION_CONTEXT('user_region')".to_string(),
             session_context: None,
+            ast: None,
         };
 
         let result = evaluator.evaluate_filter(&filter).unwrap();
@@ -262,6 +361,7 @@ mod tests {
         let filter = RowFilter {
             expression: "region = SESSION_CONTEXT('user_region') AND department = SESSION_CONTEXT('user_department')".to_string(),
             session_context: None,
+            ast: None,
         };
 
         let result = evaluator.evaluate_filter(&filter).unwrap();
@@ -283,6 +383,7 @@ mod tests {
         let filter = RowFilter {
             expression: "region = SESSION_CONTEXT('user_region')".to_string(),
             session_context: None,
+            ast: None,
         };
 
         let result = evaluator.evaluate_filter(&filter).unwrap();
@@ -300,9 +401,372 @@ mod tests {
         let filter = RowFilter {
             expression: "status != 'inactive'".to_string(),
             session_context: None,
+            ast: None,
         };
 
         let result = evaluator.evaluate_filter(&filter).unwrap();
         assert!(result);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_custom_function() {
+        let mut evaluator = ExpressionEvaluator::new();
+        evaluator.register_function("in_region_group", Box::new(|args: &[String]| {
+            let group = args.first().ok_or_else(|| anyhow!("in_region_group takes a group"))?;
+            Ok((group == "emea").to_string())
+        }));
+        evaluator.register_function("upper", Box::new(|args: &[String]| Ok(args.concat().to_uppercase())));
+
+        evaluator.set_row_data(create_sample_row(vec![
+            ("region", "WEST"),
+            ("group", "emea"),
+        ]));
+
+        assert!(eval(&evaluator, "in_region_group('emea')").unwrap());
+        assert!(eval(&evaluator, "IN_REGION_GROUP(group)").unwrap());
+        assert!(!eval(&evaluator, "in_region_group('apac')").unwrap());
+        assert!(eval(&evaluator, "region = upper('west')").unwrap());
+        assert!(eval(&evaluator, "in_region_group()").is_err());
+        assert!(eval(&evaluator, "unknown_fn('x')").is_err());
+    }
+
+    #[test]
+    fn test_session_context_default() {
+        let mut evaluator = ExpressionEvaluator::new();
+        evaluator.set_row_data(create_sample_row(vec![("region", "west")]));
+
+        // Present key wins over the default
+        evaluator.set_session_context(create_session_context(vec![("user_region", "east")]));
+        let filter = RowFilter {
+            expression: "region = SESSION_CONTEXT('user_region', 'west')".to_string(),
+            session_context: None,
+            ast: None,
+        };
+        assert!(!evaluator.evaluate_filter(&filter).unwrap());
+
+        // Missing key falls back to the default, which may contain a comma
+        evaluator.set_session_context(HashMap::new());
+        assert!(evaluator.evaluate_filter(&filter).unwrap());
+        evaluator.set_row_data(create_sample_row(vec![("region", "west, north")]));
+        let filter = RowFilter {
+            expression: "region = SESSION_CONTEXT('user_region', 'west, north')".to_string(),
+            session_context: None,
+            ast: None,
+        };
+        assert!(evaluator.evaluate_filter(&filter).unwrap());
+
+        // Without a default a missing key is still an error
+        let filter = RowFilter {
+            expression: "region = SESSION_CONTEXT('user_region')".to_string(),
+            session_context: None,
+            ast: None,
+        };
+        assert!(evaluator.evaluate_filter(&filter).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_parsed_filter() {
+        let mut evaluator = ExpressionEvaluator::new();
+        evaluator.set_session_context(create_session_context(vec![("user_region", "west")]));
+        evaluator.set_row_data(create_sample_row(vec![
+            ("region", "west"),
+            ("amount", "1000.00"),
+            ("name", "Acme Corp"),
+        ]));
+
+        let eval = |expression: &str| {
+            let ast = lakesql_parser::parse_filter_expression(expression).unwrap();
+            evaluator.evaluate_ast(&ast)
+        };
+
+        assert!(eval("region = SESSION_CONTEXT('user_region') AND amount > 100").unwrap());
+        assert!(!eval("region = SESSION_CONTEXT('user_region') AND amount > 5000").unwrap());
+        // Numbers compare numerically, not as text
+        assert!(eval("amount >= 1000").unwrap());
+        assert!(eval("amount < 2000.5").unwrap());
+        // AND binds tighter than OR
+        assert!(eval("region = 'east' AND amount > 5000 OR name LIKE 'Acme%'").unwrap());
+        assert!(!eval("region = 'east' AND (amount > 5000 OR name LIKE 'Acme%')").unwrap());
+        assert!(eval("name LIKE '_cme C%'").unwrap());
+        assert!(!eval("name LIKE 'Acme'").unwrap());
+        assert!(!eval("region = NULL").unwrap());
+        assert!(eval("missing_column = 'x'").is_err());
+
+        // Filters without an AST are parsed on the fly
+        let filter = RowFilter {
+            expression: "WHERE region <> 'east'".to_string(),
+            session_context: None,
+            ast: None,
+        };
+        assert!(evaluator.evaluate_filter(&filter).unwrap());
+    }
+
+    #[test]
+    fn test_comparison_operators() {
+        let mut evaluator = ExpressionEvaluator::new();
+        evaluator.register_function("double", Box::new(|args: &[String]| {
+            let value: f64 = args.concat().parse()?;
+            Ok((value * 2.0).to_string())
+        }));
+        evaluator.set_row_data(create_sample_row(vec![
+            ("amount", "250"),
+            ("level", "3"),
+            ("region", "west"),
+            ("label", "a<=b"),
+        ]));
+
+        assert!(eval(&evaluator, "amount > 100").unwrap());
+        assert!(!eval(&evaluator, "amount < 100").unwrap());
+        assert!(eval(&evaluator, "level <= 3").unwrap());
+        assert!(eval(&evaluator, "level >= 3.0").unwrap());
+        assert!(!eval(&evaluator, "level > 3").unwrap());
+        // Numbers compare numerically, not as text ("250" < "9" as strings)
+        assert!(eval(&evaluator, "amount > 9").unwrap());
+        assert!(eval(&evaluator, "double(amount) >= 500").unwrap());
+        assert!(eval(&evaluator, "double(level) < amount").unwrap());
+        // Non-numeric values fall back to string ordering
+        assert!(eval(&evaluator, "region > 'east'").unwrap());
+        assert!(!eval(&evaluator, "region < 100").unwrap());
+        // Two-character operators aren't read as `=`, `<` or `>`
+        assert!(eval(&evaluator, "region != 'east'").unwrap());
+        assert!(!eval(&evaluator, "region <> 'west'").unwrap());
+        // Operators inside quoted literals are ignored
+        assert!(eval(&evaluator, "label = 'a<=b'").unwrap());
+    }
+
+    #[test]
+    fn test_in_list() {
+        let mut evaluator = ExpressionEvaluator::new();
+        evaluator.set_session_context(create_session_context(vec![("dept", "sales")]));
+        evaluator.set_row_data(create_sample_row(vec![
+            ("region", "west"),
+            ("department", "sales"),
+            ("level", "2"),
+        ]));
+
+        assert!(eval(&evaluator, "region IN ('west','central')").unwrap());
+        assert!(!eval(&evaluator, "region IN ('east', 'central')").unwrap());
+        assert!(eval(&evaluator, "region IN ( 'west' )").unwrap());
+        assert!(eval(&evaluator, "department IN (SESSION_CONTEXT('dept'))").unwrap());
+        assert!(eval(&evaluator, "level IN (1, 2.0)").unwrap());
+        assert!(!eval(&evaluator, "region IN ('east', NULL)").unwrap());
+        assert!(!eval(&evaluator, "region in ('east', 'central')").unwrap());
+        assert!(eval(&evaluator, "region IN ('a, west', 'west')").unwrap());
+        assert!(eval(&evaluator, "region IN 'west'").is_err());
+        assert!(eval(&evaluator, "region IN ('west',)").is_err());
+    }
+
+    #[test]
+    fn test_like() {
+        let mut evaluator = ExpressionEvaluator::new();
+        evaluator.set_row_data(create_sample_row(vec![
+            ("email", "ana@company.com"),
+            ("region", "west-2"),
+        ]));
+
+        assert!(eval(&evaluator, "email LIKE '%@company.com'").unwrap());
+        assert!(eval(&evaluator, "region LIKE 'west%'").unwrap());
+        assert!(!eval(&evaluator, "region LIKE 'east%'").unwrap());
+        assert!(!eval(&evaluator, "email NOT LIKE '%@company.com'").unwrap());
+        assert!(eval(&evaluator, "email NOT LIKE '%@contractor.com'").unwrap());
+        assert!(eval(&evaluator, "region like 'west-_'").unwrap());
+    }
+
+    #[test]
+    fn test_precedence_and_not() {
+        let mut evaluator = ExpressionEvaluator::new();
+        evaluator.set_row_data(create_sample_row(vec![
+            ("a", "1"),
+            ("b", "0"),
+            ("c", "3"),
+            ("name", "A AND B"),
+        ]));
+
+        assert!(eval(&evaluator, "(a = '1' OR b = '2') AND c = '3'").unwrap());
+        assert!(!eval(&evaluator, "(a = '0' OR b = '2') AND c = '3'").unwrap());
+        // Without parentheses AND binds first: a = '1' OR (b = '2' AND c = '0')
+        assert!(eval(&evaluator, "a = '1' OR b = '2' AND c = '0'").unwrap());
+        assert!(!eval(&evaluator, "(a = '1' OR b = '2') AND c = '0'").unwrap());
+        assert!(eval(&evaluator, "NOT a = '2'").unwrap());
+        assert!(!eval(&evaluator, "NOT (a = '1' OR b = '2')").unwrap());
+        assert!(eval(&evaluator, "NOT a = '1' OR c = '3'").unwrap());
+        // Delimiters inside literals are part of the value
+        assert!(eval(&evaluator, "name = 'A AND B'").unwrap());
+        assert!(eval(&evaluator, "name != 'A OR B' AND a = 1").unwrap());
+        // Text the grammar can't read is an error rather than a guess
+        assert!(eval(&evaluator, "a = = 1").is_err());
+        assert!(eval(&evaluator, "a = 1 AND").is_err());
+    }
+
+    #[test]
+    fn test_parenthesized_groups() {
+        let mut evaluator = ExpressionEvaluator::new();
+        evaluator.set_row_data(create_sample_row(vec![
+            ("region", "west"),
+            ("department", "hr"),
+            ("level", "2"),
+        ]));
+
+        assert!(eval(&evaluator, "region = 'west' AND (department = 'eng' OR department = 'hr')").unwrap());
+        assert!(!eval(&evaluator, "region = 'east' AND (department = 'eng' OR department = 'hr')").unwrap());
+        assert!(eval(&evaluator, "(region = 'west')").unwrap());
+        assert!(eval(&evaluator, "((region = 'west'))").unwrap());
+        assert!(eval(&evaluator, "region = 'west' AND ((department = 'eng' OR level > 1) AND (level < 5))").unwrap());
+        assert!(!eval(&evaluator, "region = 'west' AND ((department = 'eng' OR level > 2) AND level < 5)").unwrap());
+
+        // The group flips the result: ungrouped, AND binds first
+        assert!(eval(&evaluator, "department = 'hr' OR region = 'east' AND level = 9").unwrap());
+        assert!(!eval(&evaluator, "(department = 'hr' OR region = 'east') AND level = 9").unwrap());
+
+        assert!(eval(&evaluator, "(region = 'west'").is_err());
+        assert!(eval(&evaluator, "region = 'west')").is_err());
+    }
+
+    #[test]
+    fn test_not_and_is_null() {
+        let mut evaluator = ExpressionEvaluator::new();
+        evaluator.set_session_context(create_session_context(vec![("mfa", "true")]));
+        evaluator.set_row_data(create_sample_row(vec![
+            ("region", "west"),
+            ("owner", "ana"),
+        ]));
+
+        assert!(eval(&evaluator, "NOT (region = 'east')").unwrap());
+        assert!(!eval(&evaluator, "NOT region = 'west'").unwrap());
+        assert!(eval(&evaluator, "NOT NOT region = 'west'").unwrap());
+
+        // A column missing from the row is NULL
+        assert!(eval(&evaluator, "manager IS NULL").unwrap());
+        assert!(!eval(&evaluator, "manager IS NOT NULL").unwrap());
+        assert!(eval(&evaluator, "owner IS NOT NULL").unwrap());
+        assert!(!eval(&evaluator, "owner IS NULL").unwrap());
+        assert!(eval(&evaluator, "region = 'west' AND NOT manager IS NOT NULL").unwrap());
+        assert!(eval(&evaluator, "SESSION_CONTEXT('mfa') IS NOT NULL").unwrap());
+        assert!(eval(&evaluator, "SESSION_CONTEXT('team') IS NULL").unwrap());
+        // Outside IS NULL a missing column is still an error
+        assert!(eval(&evaluator, "manager = 'ana'").is_err());
+    }
+
+    #[test]
+    fn test_eval_error_kinds() {
+        let mut evaluator = ExpressionEvaluator::new();
+        evaluator.set_session_context(create_session_context(vec![("user_region", "west")]));
+        evaluator.set_row_data(create_sample_row(vec![("region", "west")]));
+
+        let error = |expression: &str| {
+            let err = eval(&evaluator, expression).unwrap_err();
+            err.downcast_ref::<EvalError>().cloned()
+        };
+
+        assert_eq!(error("regoin = 'west'"), Some(EvalError::UnknownColumn("regoin".to_string())));
+        assert_eq!(
+            error("region = SESSION_CONTEXT('user_regoin')"),
+            Some(EvalError::MissingSessionKey("user_regoin".to_string())),
+        );
+        assert!(matches!(error("region = = 'west'"), Some(EvalError::UnparsableExpression(_))));
+        // Failures inside custom functions aren't evaluation errors of their own
+        assert_eq!(error("unknown_fn(region)"), None);
+    }
+
+    #[test]
+    fn test_numeric_equality() {
+        let mut evaluator = ExpressionEvaluator::new();
+        evaluator.set_row_data(create_sample_row(vec![
+            ("amount", "100.0"),
+            ("code", "abc"),
+            ("ratio", "NaN"),
+        ]));
+
+        assert!(eval(&evaluator, "amount = 100").unwrap());
+        assert!(eval(&evaluator, "amount = '100'").unwrap());
+        assert!(eval(&evaluator, "amount IN (99, 100)").unwrap());
+        assert!(!eval(&evaluator, "amount != 100.00").unwrap());
+        assert!(eval(&evaluator, "code = 'abc'").unwrap());
+        assert!(!eval(&evaluator, "code = 'ABC'").unwrap());
+
+        // Literal comparisons follow the same rules
+        assert!(compare("100", ComparisonOp::Eq, "100.0"));
+        assert!(compare("-0", ComparisonOp::Eq, "0"));
+        assert!(compare("1e2", ComparisonOp::Eq, "100"));
+        // NaN isn't a number to compare with, so it's only equal to itself as text
+        assert!(eval(&evaluator, "ratio = 'NaN'").unwrap());
+        assert!(!eval(&evaluator, "ratio = 'nan'").unwrap());
+        assert!(!compare("NaN", ComparisonOp::Eq, "nan"));
+        assert!(compare("inf", ComparisonOp::Gt, "abc"));
+    }
+
+    #[test]
+    fn test_session_context_lists() {
+        let mut evaluator = ExpressionEvaluator::new();
+        evaluator.set_row_data(create_sample_row(vec![
+            ("region", "central"),
+            ("team", "data, platform"),
+        ]));
+
+        // Multi-valued context
+        evaluator.set_session_context(create_session_context(vec![("allowed_regions", "west, central")]));
+        assert!(eval(&evaluator, "region IN SESSION_CONTEXT('allowed_regions')").unwrap());
+        assert!(eval(&evaluator, "region IN (SESSION_CONTEXT('allowed_regions'), 'east')").unwrap());
+        assert!(eval(&evaluator, "SESSION_CONTEXT('allowed_regions') = 'west'").unwrap());
+        assert!(eval(&evaluator, "region = SESSION_CONTEXT('allowed_regions')").unwrap());
+        assert!(!eval(&evaluator, "SESSION_CONTEXT('allowed_regions') = 'east'").unwrap());
+        assert!(eval(&evaluator, "SESSION_CONTEXT('allowed_regions') != 'east'").unwrap());
+        assert!(!eval(&evaluator, "SESSION_CONTEXT('allowed_regions') != 'west'").unwrap());
+        // Lists in row values are not split
+        assert!(!eval(&evaluator, "team = 'data'").unwrap());
+
+        // Single-valued context
+        evaluator.set_session_context(create_session_context(vec![("allowed_regions", "central")]));
+        assert!(eval(&evaluator, "region IN SESSION_CONTEXT('allowed_regions')").unwrap());
+        assert!(!eval(&evaluator, "SESSION_CONTEXT('allowed_regions') = 'west'").unwrap());
+
+        // The whole value still matches, commas included
+        evaluator.set_session_context(create_session_context(vec![("teams", "data, platform")]));
+        assert!(eval(&evaluator, "team = SESSION_CONTEXT('teams')").unwrap());
+    }
+
+    #[test]
+    fn test_current_user_and_time() {
+        let mut evaluator = ExpressionEvaluator::new();
+        evaluator.set_clock(Arc::new(crate::FixedClock(1_700_000_000)));
+        evaluator.set_row_data(create_sample_row(vec![
+            ("owner", "ana"),
+            ("created_at", "2023-11-14 09:30:00"),
+            ("due", "2023-11-15"),
+        ]));
+
+        evaluator.set_session_context(create_session_context(vec![(CURRENT_USER_KEY, "ana")]));
+        assert!(eval(&evaluator, "owner = CURRENT_USER").unwrap());
+        evaluator.set_session_context(create_session_context(vec![(CURRENT_USER_KEY, "ben")]));
+        assert!(!eval(&evaluator, "owner = CURRENT_USER").unwrap());
+        evaluator.set_session_context(HashMap::new());
+        let err = eval(&evaluator, "owner = CURRENT_USER").unwrap_err();
+        assert_eq!(err.downcast_ref::<EvalError>(), Some(&EvalError::MissingSessionKey(CURRENT_USER_KEY.to_string())));
+
+        // 1_700_000_000 is 2023-11-14 22:13:20 UTC
+        assert!(eval(&evaluator, "created_at <= CURRENT_TIMESTAMP").unwrap());
+        assert!(eval(&evaluator, "CURRENT_TIMESTAMP = '2023-11-14 22:13:20'").unwrap());
+        assert!(eval(&evaluator, "due > CURRENT_DATE").unwrap());
+        assert!(eval(&evaluator, "CURRENT_DATE = '2023-11-14'").unwrap());
+
+        evaluator.set_clock(Arc::new(crate::FixedClock(951_782_400)));
+        assert!(eval(&evaluator, "CURRENT_TIMESTAMP = '2000-02-29 00:00:00'").unwrap());
+        assert!(eval(&evaluator, "created_at > CURRENT_TIMESTAMP AND due > CURRENT_DATE").unwrap());
+    }
+
+    #[test]
+    fn test_quoted_delimiters() {
+        let mut evaluator = ExpressionEvaluator::new();
+        evaluator.set_row_data(create_sample_row(vec![
+            ("note", "pending AND review OR escalate"),
+            ("formula", "a >= b = c"),
+            ("owner", "o'brien"),
+        ]));
+
+        assert!(eval(&evaluator, "note = 'pending AND review OR escalate'").unwrap());
+        assert!(!eval(&evaluator, "note = 'pending AND review'").unwrap());
+        assert!(eval(&evaluator, "note != 'x OR y' AND formula = 'a >= b = c'").unwrap());
+        assert!(eval(&evaluator, "note LIKE '% AND %' OR note = 'AND'").unwrap());
+        assert!(eval(&evaluator, "owner IN ('a, b AND c', 'o''brien')").unwrap());
+    }
+}
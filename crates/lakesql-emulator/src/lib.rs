@@ -6,15 +6,30 @@
 use lakesql_core::*;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::path::Path;
-use anyhow::Result;
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use tokio::io::AsyncWriteExt;
 
+pub mod audit;
 pub mod storage;
+pub mod clock;
 pub mod engine;
+pub mod diff;
 pub mod expression;
+pub mod validation;
+pub mod risk;
+pub mod stats;
 
+pub use audit::{AuditEntry, AuditLog};
+pub use clock::{Clock, FixedClock, SystemClock};
 pub use engine::EmulatorEngine;
+pub use expression::EvalError;
+pub use storage::{FileStorage, MemoryStorage, Storage};
+pub use diff::StateDiff;
+pub use validation::ValidationIssue;
+pub use risk::{RiskFinding, RiskKind, RiskSeverity};
+pub use stats::{GroupBy, PermissionGroup};
 
 /// Complete state of the Lake Formation emulator
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +42,35 @@ pub struct EmulatorState {
     pub tags: HashMap<String, LfTag>,
     /// Session context for row-level security
     pub session_context: HashMap<String, String>,
+    /// Data locations registered with REGISTER LOCATION
+    #[serde(default)]
+    pub registered_locations: HashSet<String>,
+    /// Resources switched to IAM-only access with SET ACCESS MODE
+    #[serde(default)]
+    pub iam_only_resources: HashSet<Resource>,
+    /// S3 locations of tables from REGISTER TABLE, keyed by `database.table`
+    #[serde(default)]
+    pub table_locations: HashMap<String, String>,
+    /// Grant templates from DEFINE TEMPLATE (name -> body)
+    #[serde(default)]
+    pub templates: HashMap<String, String>,
+    /// Local user groups from DEFINE GROUP (group_name -> members), matched
+    /// against grants to `GROUP 'name'`
+    #[serde(default)]
+    pub local_groups: HashMap<String, HashSet<String>>,
+    /// LF-Tag values assigned to users and roles (canonical name -> tag key
+    /// -> value), matched against grants to `TAGGED key='value'`
+    #[serde(default)]
+    pub principal_tags: HashMap<String, HashMap<String, String>>,
+    /// LF-Tag values assigned to databases and tables (`database` or
+    /// `database.table` -> tag key -> value), matched against grants on
+    /// `RESOURCES TAGGED ...`
+    #[serde(default)]
+    pub resource_tags: HashMap<String, HashMap<String, String>>,
+    /// Roles each role inherits through GRANT ROLE (role_name -> inherited
+    /// roles). Kept apart from `roles`, whose members are users.
+    #[serde(default)]
+    pub role_parents: HashMap<String, HashSet<String>>,
 }
 
 impl EmulatorState {
@@ -36,8 +80,230 @@ impl EmulatorState {
             roles: HashMap::new(),
             tags: HashMap::new(),
             session_context: HashMap::new(),
+            registered_locations: HashSet::new(),
+            iam_only_resources: HashSet::new(),
+            table_locations: HashMap::new(),
+            templates: HashMap::new(),
+            local_groups: HashMap::new(),
+            principal_tags: HashMap::new(),
+            resource_tags: HashMap::new(),
+            role_parents: HashMap::new(),
         }
     }
+
+    /// Forget a role's place in the role hierarchy, both the roles it
+    /// inherits and the roles inheriting it
+    pub fn remove_role_parents(&mut self, role: &str) {
+        self.role_parents.remove(role);
+        for parents in self.role_parents.values_mut() {
+            parents.remove(role);
+        }
+        self.role_parents.retain(|_, parents| !parents.is_empty());
+    }
+
+    /// Roles a principal inherits directly: for a user, the roles listing it
+    /// as a member; for a role, the roles granted to it with GRANT ROLE
+    pub(crate) fn parent_roles(&self, principal: &Principal) -> Vec<&str> {
+        let mut parents: Vec<&str> = match principal {
+            Principal::User(name) => self.roles
+                .iter()
+                .filter(|(_, members)| members.iter().any(|member| same_iam_name(member, name)))
+                .map(|(role, _)| role.as_str())
+                .collect(),
+            Principal::Role(name) => self.role_parents
+                .iter()
+                .filter(|(role, _)| same_iam_name(role, name))
+                .flat_map(|(_, parents)| parents.iter().map(String::as_str))
+                .collect(),
+            _ => return Vec::new(),
+        };
+        parents.sort();
+        parents
+    }
+
+    /// Every role whose grants apply to a principal: the role itself, the
+    /// roles it is a member of, and everything those inherit with GRANT ROLE
+    pub fn inherited_roles(&self, principal: &Principal) -> HashSet<String> {
+        let mut roles = HashSet::new();
+        let mut queue = vec![principal.clone()];
+        if let Principal::Role(name) = principal {
+            roles.insert(name.clone());
+        }
+        while let Some(next) = queue.pop() {
+            for parent in self.parent_roles(&next) {
+                if roles.insert(parent.to_string()) {
+                    queue.push(Principal::Role(parent.to_string()));
+                }
+            }
+        }
+        roles
+    }
+
+    /// Access mode of a resource; IAM-only on a database applies to its tables
+    pub fn access_mode(&self, resource: &Resource) -> AccessMode {
+        if self.iam_only_resources.iter().any(|r| resource.is_covered_by(r)) {
+            AccessMode::IamOnly
+        } else {
+            AccessMode::LakeFormation
+        }
+    }
+
+    /// LF-Tags in effect on a database, table or view. Tables and views
+    /// inherit their database's tags unless they assign the key themselves.
+    pub fn resource_tags_for(&self, resource: &Resource) -> HashMap<String, String> {
+        let (database, object) = match resource {
+            Resource::Database { name, .. } => (name, None),
+            Resource::Table { database, table, .. } => (database, Some(table)),
+            Resource::View { database, view } => (database, Some(view)),
+            _ => return HashMap::new(),
+        };
+
+        let mut tags = self.resource_tags.get(database).cloned().unwrap_or_default();
+        if let Some(own) = object.and_then(|object| self.resource_tags.get(&format!("{}.{}", database, object))) {
+            tags.extend(own.iter().map(|(key, value)| (key.clone(), value.clone())));
+        }
+        tags
+    }
+
+    /// S3 location registered for a table with REGISTER TABLE
+    pub fn location_for_table(&self, database: &str, table: &str) -> Option<String> {
+        self.table_locations.get(&format!("{}.{}", database, table)).cloned()
+    }
+
+    /// Data location grants covering a table's registered location, for
+    /// correlating them with grants on the table itself
+    pub fn location_grants_for_table(&self, database: &str, table: &str) -> Vec<&Permission> {
+        let Some(path) = self.location_for_table(database, table) else {
+            return Vec::new();
+        };
+        let location = Resource::DataLocation { path };
+        self.permissions
+            .iter()
+            .filter(|p| matches!(p.resource, Resource::DataLocation { .. }) && location.is_covered_by(&p.resource))
+            .collect()
+    }
+
+    /// Whether a data location path lies under a registered location
+    pub fn is_location_registered(&self, path: &str) -> bool {
+        let location = Resource::DataLocation { path: path.to_string() };
+        self.registered_locations.iter().any(|registered| {
+            location.is_covered_by(&Resource::DataLocation { path: registered.clone() })
+        })
+    }
+
+    /// Extract the part of the state touching one principal: grants to it,
+    /// the roles it inherits and local groups it belongs to (and their grants),
+    /// and the tags and registered locations those grants reference
+    pub fn subset_for_principal(&self, principal: &Principal) -> EmulatorState {
+        let inherited = self.inherited_roles(principal);
+        let roles: HashMap<String, HashSet<String>> = self.roles
+            .iter()
+            .filter(|(role, _)| inherited.contains(*role))
+            .map(|(role, members)| (role.clone(), members.clone()))
+            .collect();
+        let role_parents: HashMap<String, HashSet<String>> = self.role_parents
+            .iter()
+            .filter(|(role, _)| inherited.contains(*role))
+            .map(|(role, parents)| (role.clone(), parents.clone()))
+            .collect();
+
+        let local_groups: HashMap<String, HashSet<String>> = self.local_groups
+            .iter()
+            .filter(|(group, members)| match principal {
+                Principal::SamlGroup(name) => *group == name,
+                Principal::User(user) => members.iter().any(|m| same_iam_name(m, user)),
+                _ => false,
+            })
+            .map(|(group, members)| (group.clone(), members.clone()))
+            .collect();
+
+        let principal_tags: HashMap<String, HashMap<String, String>> = match principal {
+            Principal::User(name) | Principal::Role(name) => self.principal_tags
+                .get_key_value(canonical_iam_name(name))
+                .map(|(name, tags)| (name.clone(), tags.clone()))
+                .into_iter()
+                .collect(),
+            _ => HashMap::new(),
+        };
+
+        let permissions: Vec<Permission> = self.permissions
+            .iter()
+            .filter(|p| match &p.principal {
+                Principal::Role(role) => p.principal == *principal || inherited.contains(role),
+                Principal::SamlGroup(group) => p.principal == *principal || local_groups.contains_key(group),
+                Principal::TaggedPrincipal { tag_key, tag_values } => {
+                    principal_tags.values().any(|tags| tags.get(tag_key).is_some_and(|v| tag_values.contains(v)))
+                },
+                other => other == principal,
+            })
+            .cloned()
+            .collect();
+
+        let mut tag_keys = HashSet::new();
+        for permission in &permissions {
+            if let Principal::TaggedPrincipal { tag_key, .. } = &permission.principal {
+                tag_keys.insert(tag_key);
+            }
+            if let Resource::TaggedResource { tag_conditions } = &permission.resource {
+                tag_keys.extend(tag_conditions.iter().map(|(key, _)| key));
+            }
+        }
+        let registered_locations = self.registered_locations
+            .iter()
+            .filter(|registered| {
+                let registered = Resource::DataLocation { path: (*registered).clone() };
+                permissions.iter().any(|p| {
+                    matches!(p.resource, Resource::DataLocation { .. }) && p.resource.is_covered_by(&registered)
+                })
+            })
+            .cloned()
+            .collect();
+
+        let resource_tags = self.resource_tags
+            .iter()
+            .filter_map(|(target, assigned)| {
+                let assigned: HashMap<String, String> = assigned
+                    .iter()
+                    .filter(|(key, _)| tag_keys.contains(key))
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect();
+                (!assigned.is_empty()).then(|| (target.clone(), assigned))
+            })
+            .collect();
+        let tags = self.tags
+            .iter()
+            .filter(|(key, _)| tag_keys.contains(key))
+            .map(|(key, tag)| (key.clone(), tag.clone()))
+            .collect();
+
+        EmulatorState {
+            permissions,
+            roles,
+            tags,
+            resource_tags,
+            registered_locations,
+            local_groups,
+            principal_tags,
+            role_parents,
+            ..Self::new()
+        }
+    }
+}
+
+/// Re-execute a replay log (see `EmulatorBackend::set_replay_log`) on a
+/// fresh in-memory backend and return the resulting state
+pub async fn replay(log_path: &str) -> Result<EmulatorState> {
+    let log = tokio::fs::read_to_string(log_path).await?;
+    let mut backend = EmulatorBackend::new(None).await?;
+
+    for (i, line) in log.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let sql: String = serde_json::from_str(line)
+            .map_err(|e| anyhow!("Replay log line {}: {}", i + 1, e))?;
+        backend.execute_ddl(&sql).await
+            .map_err(|e| anyhow!("Replay log line {}: {}", i + 1, e))?;
+    }
+
+    Ok(backend.state)
 }
 
 impl Default for EmulatorState {
@@ -46,66 +312,311 @@ impl Default for EmulatorState {
     }
 }
 
+/// How a REVOKE target is matched against existing grants
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RevokeMatch {
+    /// Only grants on exactly the revoked resource, including its column set
+    #[default]
+    Exact,
+    /// Any grant whose resource covers the revoked resource (e.g. a
+    /// whole-table grant when revoking on a column subset)
+    Covering,
+}
+
+/// Whether a REVOKE applies to a grant: same principal, a matching resource
+/// and any of the actions. REVOKE ALL removes the whole grant whatever it lists.
+fn revoke_matches(
+    revoke_match: RevokeMatch,
+    permission: &Permission,
+    principal: &Principal,
+    resource: &Resource,
+    actions: &[Action],
+) -> bool {
+    let resource_matches = match revoke_match {
+        RevokeMatch::Exact => permission.resource == *resource,
+        RevokeMatch::Covering => resource.is_covered_by(&permission.resource),
+    };
+    permission.principal == *principal
+        && resource_matches
+        && (actions.contains(&Action::All) || actions.iter().any(|a| permission.grants_action(a)))
+}
+
+/// Apply a REVOKE to one grant, taking away only the revoked actions.
+/// Returns whether the grant should be kept, i.e. still has actions left.
+fn revoke_from(
+    revoke_match: RevokeMatch,
+    permission: &mut Permission,
+    principal: &Principal,
+    resource: &Resource,
+    actions: &[Action],
+) -> bool {
+    if revoke_matches(revoke_match, permission, principal, resource, actions) {
+        permission.revoke_actions(actions);
+    }
+    !permission.actions.is_empty()
+}
+
 /// Lake Formation Emulator Backend
-pub struct EmulatorBackend {
+pub struct EmulatorBackend<S: Storage = FileStorage> {
     /// Current state
     state: EmulatorState,
-    /// Optional file path for persistence  
-    state_file: Option<String>,
+    /// Optional storage for persistence
+    storage: Option<S>,
     /// Permission evaluation engine
     engine: EmulatorEngine,
+    /// Resource matching used when revoking
+    revoke_match: RevokeMatch,
+    /// Reject data location grants on paths that haven't been registered
+    require_registered_locations: bool,
+    /// Persist after every mutating operation
+    auto_save: bool,
+    /// Drop expired grants when state is loaded
+    prune_expired_on_load: bool,
+    /// Time source for grant expiry
+    clock: Arc<dyn Clock>,
+    /// File recording every executed DDL string, for `replay`
+    replay_log: Option<String>,
+    /// Append-only JSON-lines audit trail of executed statements
+    audit_log: Option<String>,
+    /// Largest column list accepted in a single table grant
+    max_grant_columns: Option<usize>,
+    /// Run DROP ROLE and REVOKE ALL; when false they are refused
+    allow_destructive: bool,
 }
 
-impl EmulatorBackend {
-    /// Create a new emulator backend
+impl EmulatorBackend<FileStorage> {
+    /// Create a new emulator backend, persisting to a state file if given
     pub async fn new(state_file: Option<String>) -> Result<Self> {
+        Self::create(state_file.map(FileStorage::new)).await
+    }
+}
+
+impl<S: Storage> EmulatorBackend<S> {
+    /// Create an emulator backend persisting through the given storage
+    pub async fn with_storage(storage: S) -> Result<Self> {
+        Self::create(Some(storage)).await
+    }
+
+    async fn create(storage: Option<S>) -> Result<Self> {
         let mut backend = Self {
             state: EmulatorState::new(),
-            state_file: state_file.clone(),
+            storage,
             engine: EmulatorEngine::new(),
+            revoke_match: RevokeMatch::default(),
+            require_registered_locations: false,
+            auto_save: true,
+            prune_expired_on_load: false,
+            clock: Arc::new(SystemClock),
+            replay_log: None,
+            audit_log: None,
+            max_grant_columns: None,
+            allow_destructive: true,
+        };
+
+        backend.load_state().await?;
+        Ok(backend)
+    }
+
+    /// (Re)load state from storage, if configured. With pruning enabled,
+    /// grants that have expired by the clock's current time are dropped
+    /// and the pruned state is saved back.
+    pub async fn load_state(&mut self) -> Result<()> {
+        let Some(storage) = &self.storage else {
+            return Ok(());
         };
+        self.state = storage.load().await?;
 
-        // Load existing state if file exists
-        if let Some(ref file_path) = state_file {
-            if Path::new(file_path).exists() {
-                backend.load_state(file_path).await?;
+        if self.prune_expired_on_load {
+            let now = self.clock.now();
+            let before = self.state.permissions.len();
+            self.state.permissions.retain(|p| !p.is_expired(now));
+            let pruned = before - self.state.permissions.len();
+            if pruned > 0 {
+                tracing::info!(pruned, "pruned expired grants on load");
+                self.save_state().await?;
             }
         }
 
-        Ok(backend)
+        self.engine.update_state(&self.state);
+        Ok(())
     }
 
-    /// Load state from file
-    async fn load_state(&mut self, file_path: &str) -> Result<()> {
-        let content = tokio::fs::read_to_string(file_path).await?;
-        self.state = serde_json::from_str(&content)?;
-        self.engine.update_state(&self.state);
-        println!("📂 Loaded emulator state from: {}", file_path);
+    /// Drop expired grants whenever state is loaded (off by default)
+    pub fn set_prune_expired_on_load(&mut self, prune: bool) {
+        self.prune_expired_on_load = prune;
+    }
+
+    /// Replace the time source used for grant expiry
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// Set how REVOKE targets are matched against existing grants
+    pub fn set_revoke_match(&mut self, revoke_match: RevokeMatch) {
+        self.revoke_match = revoke_match;
+    }
+
+    /// Require data locations to be registered before access to them can be
+    /// granted, as Lake Formation does
+    pub fn set_require_registered_locations(&mut self, require: bool) {
+        self.require_registered_locations = require;
+    }
+
+    /// Reject table grants listing more columns than `max` (unlimited when
+    /// `None`, the default), catching lists AWS would refuse
+    pub fn set_max_grant_columns(&mut self, max: Option<usize>) {
+        self.max_grant_columns = max;
+    }
+
+    /// Allow DROP ROLE and REVOKE ALL (the default). When disabled they fail
+    /// with an error listing the grants they would have removed.
+    pub fn set_allow_destructive(&mut self, allow: bool) {
+        self.allow_destructive = allow;
+    }
+
+    /// Save after every mutating operation (the default). When disabled,
+    /// state only reaches storage on `flush`.
+    pub fn set_auto_save(&mut self, auto_save: bool) {
+        self.auto_save = auto_save;
+    }
+
+    /// Append every successfully executed DDL string to a log file, kept
+    /// separate from state, that `replay` can re-run
+    pub fn set_replay_log(&mut self, path: Option<String>) {
+        self.replay_log = path;
+    }
+
+    /// Append an `AuditEntry` for every executed DDL string, successful or
+    /// not, to a JSON-lines file that `AuditLog::load` reads back
+    pub fn set_audit_log(&mut self, path: Option<String>) {
+        self.audit_log = path;
+    }
+
+    /// Append an entry to the audit log, if one is configured
+    async fn record_audit(&self, entry: AuditEntry) -> Result<()> {
+        let Some(path) = &self.audit_log else {
+            return Ok(());
+        };
+
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        Ok(())
+    }
+
+    /// Append a statement to the replay log, one JSON string per line
+    async fn record_statement(&self, sql: &str) -> Result<()> {
+        let Some(path) = &self.replay_log else {
+            return Ok(());
+        };
+
+        let mut line = serde_json::to_string(sql)?;
+        line.push('\n');
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        Ok(())
+    }
+
+    /// Write a full snapshot of the current state to storage, regardless of
+    /// the auto-save setting
+    pub async fn flush(&mut self) -> Result<()> {
+        if let Some(storage) = &self.storage {
+            storage.save(&self.state).await?;
+        }
         Ok(())
     }
 
-    /// Save state to file
+    /// Persist state after a mutation, if storage is configured and
+    /// auto-save is on
     async fn save_state(&self) -> Result<()> {
-        if let Some(ref file_path) = self.state_file {
-            let content = serde_json::to_string_pretty(&self.state)?;
-            tokio::fs::write(file_path, content).await?;
-            println!("💾 Saved emulator state to: {}", file_path);
+        if !self.auto_save {
+            return Ok(());
+        }
+        if let Some(storage) = &self.storage {
+            storage.save(&self.state).await?;
         }
         Ok(())
     }
 
     /// Execute a DDL statement by parsing and applying it
     pub async fn execute_ddl_direct(&mut self, statement: lakesql_parser::DdlStatement) -> Result<DdlResult> {
+        if statement.is_read_only() {
+            self.execute_read_only(statement).await
+        } else {
+            self.execute_mutating(statement).await
+        }
+    }
+
+    /// Execute a SHOW statement. Never touches the state file.
+    async fn execute_read_only(&self, statement: lakesql_parser::DdlStatement) -> Result<DdlResult> {
+        use lakesql_parser::DdlStatement;
+
+        match statement {
+            DdlStatement::ShowPermissions { principal } => {
+                let permissions = if let Some(p) = principal {
+                    self.list_permissions_for_principal(&p).await?
+                } else {
+                    self.state.permissions.clone()
+                };
+                
+                let message = format!("Found {} permissions", permissions.len());
+                Ok(DdlResult::Success { message })
+            },
+            
+            DdlStatement::ShowRoles => {
+                let roles: Vec<_> = self.state.roles.keys().collect();
+                let message = format!("Roles: {:?}", roles);
+                Ok(DdlResult::Success { message })
+            },
+            
+            DdlStatement::ShowTags => {
+                let tags: Vec<_> = self.state.tags.keys().collect();
+                let message = format!("Tags: {:?}", tags);
+                Ok(DdlResult::Success { message })
+            },
+
+            other => Err(anyhow!("Statement is not read-only: {:?}", other)),
+        }
+    }
+
+    /// Execute a statement that changes state and persists it
+    async fn execute_mutating(&mut self, statement: lakesql_parser::DdlStatement) -> Result<DdlResult> {
         use lakesql_parser::DdlStatement;
 
+        if !self.allow_destructive {
+            if let Some(removed) = self.destructive_removals(&statement)? {
+                let listing: String = removed
+                    .iter()
+                    .map(|p| format!("\n  • {:?} → {:?} → {:?}", p.principal, p.actions, p.resource))
+                    .collect();
+                return Err(anyhow!(
+                    "Refusing destructive statement without confirmation; it would remove {} grant(s){}",
+                    removed.len(), listing
+                ));
+            }
+        }
+
         match statement {
-            DdlStatement::Grant { actions, resource, principal, grant_option, row_filter } => {
+            DdlStatement::Grant { actions, resource, principal, grantable_actions, row_filter, principal_condition, comment } => {
                 let permission = Permission {
                     principal,
                     resource,
                     actions,
-                    grant_option,
+                    grantable_actions,
                     row_filter,
+                    principal_condition,
+                    comment,
+                    expires_at: None,
+                    effect: Effect::Allow,
                 };
                 self.grant_permissions(permission).await
             },
@@ -113,16 +624,23 @@ impl EmulatorBackend {
             DdlStatement::Revoke { actions, resource, principal } => {
                 self.revoke_permissions(&principal, &resource, &actions).await
             },
+
+            DdlStatement::Deny { .. } => {
+                let permission = statement.to_permission()?;
+                self.grant_permissions(permission).await
+            },
             
             DdlStatement::CreateRole { name } => {
                 self.state.roles.insert(name.clone(), HashSet::new());
-                self.engine.update_state(&self.state);
+                self.engine.apply_role(&name, HashSet::new());
                 self.save_state().await?;
                 Ok(DdlResult::Success { 
                     message: format!("Created role: {}", name) 
                 })
             },
             
+            DdlStatement::CreateRoles { names } => self.create_roles(&names).await,
+
             DdlStatement::CreateTag { name, values } => {
                 let tag = LfTag {
                     key: name.clone(),
@@ -134,11 +652,12 @@ impl EmulatorBackend {
             
             DdlStatement::DropRole { name } => {
                 self.state.roles.remove(&name);
+                self.state.remove_role_parents(&name);
                 // Remove all permissions for this role
                 self.state.permissions.retain(|p| {
                     !matches!(p.principal, Principal::Role(ref role_name) if role_name == &name)
                 });
-                self.engine.update_state(&self.state);
+                self.engine.apply_drop_role(&name);
                 self.save_state().await?;
                 Ok(DdlResult::Success { 
                     message: format!("Dropped role: {}", name) 
@@ -148,107 +667,488 @@ impl EmulatorBackend {
             DdlStatement::DropTag { name } => {
                 self.delete_tag(&name).await
             },
-            
-            DdlStatement::ShowPermissions { principal } => {
-                let permissions = if let Some(p) = principal {
-                    self.list_permissions_for_principal(&p).await?
-                } else {
-                    self.state.permissions.clone()
+
+            DdlStatement::AlterRole { name, add_users, drop_users } => {
+                let members = self.state.roles
+                    .get_mut(&name)
+                    .ok_or_else(|| anyhow!("Role '{}' does not exist", name))?;
+                for user in &add_users {
+                    if !members.iter().any(|member| same_iam_name(member, user)) {
+                        members.insert(user.clone());
+                    }
+                    self.engine.add_user_to_role(user.clone(), name.clone()).map_err(|e| anyhow!(e))?;
+                }
+                for user in &drop_users {
+                    members.retain(|member| !same_iam_name(member, user));
+                    self.engine.remove_user_from_role(user, &name).map_err(|e| anyhow!(e))?;
+                }
+                self.save_state().await?;
+                Ok(DdlResult::Success {
+                    message: format!(
+                        "Altered role {}: added {} user(s), dropped {} user(s)",
+                        name, add_users.len(), drop_users.len()
+                    )
+                })
+            },
+
+            DdlStatement::GrantRole { role, member } => {
+                for name in [&role, &member] {
+                    if !self.state.roles.contains_key(name) {
+                        return Err(anyhow!("Role '{}' does not exist", name));
+                    }
+                }
+                // member would inherit from itself if role already inherits from member
+                if self.engine.role_inherits(&role, &member) {
+                    return Err(anyhow!(
+                        "Granting role {} to {} would create a cycle: {} already inherits {}",
+                        role, member, role, member
+                    ));
+                }
+                self.state.role_parents.entry(member.clone()).or_default().insert(role.clone());
+                self.engine.apply_role_grant(&role, &member);
+                self.save_state().await?;
+                Ok(DdlResult::Success {
+                    message: format!("Granted role {} to role {}", role, member)
+                })
+            },
+
+            DdlStatement::SetAccessMode { resource, mode } => {
+                let message = format!("Set access mode {:?} on {:?}", mode, resource);
+                match mode {
+                    AccessMode::IamOnly => self.state.iam_only_resources.insert(resource),
+                    AccessMode::LakeFormation => self.state.iam_only_resources.remove(&resource),
                 };
-                
-                let message = format!("Found {} permissions", permissions.len());
+                self.engine.update_state(&self.state);
+                self.save_state().await?;
                 Ok(DdlResult::Success { message })
             },
-            
-            DdlStatement::ShowRoles => {
-                let roles: Vec<_> = self.state.roles.keys().collect();
-                let message = format!("Roles: {:?}", roles);
+
+            DdlStatement::RegisterLocation { path } => {
+                self.state.registered_locations.insert(path.clone());
+                self.engine.update_state(&self.state);
+                self.save_state().await?;
+                Ok(DdlResult::Success {
+                    message: format!("Registered location: {}", path)
+                })
+            },
+
+            DdlStatement::RegisterTable { database, table, location } => {
+                let message = format!("Registered table {}.{} at {}", database, table, location);
+                self.state.table_locations.insert(format!("{}.{}", database, table), location);
+                self.engine.update_state(&self.state);
+                self.save_state().await?;
                 Ok(DdlResult::Success { message })
             },
-            
-            DdlStatement::ShowTags => {
-                let tags: Vec<_> = self.state.tags.keys().collect();
-                let message = format!("Tags: {:?}", tags);
+
+            DdlStatement::DefineGroup { name, members } => {
+                // An ARN and its short name are one member, as for role membership
+                let mut unique: HashSet<String> = HashSet::new();
+                for member in members {
+                    if !unique.iter().any(|m| same_iam_name(m, &member)) {
+                        unique.insert(member);
+                    }
+                }
+                let members = unique;
+                let message = format!("Defined group {} with {} member(s)", name, members.len());
+                self.state.local_groups.insert(name, members);
+                self.engine.update_state(&self.state);
+                self.save_state().await?;
+                Ok(DdlResult::Success { message })
+            },
+
+            DdlStatement::DefineTemplate { name, body } => {
+                let message = format!("Defined template: {}", name);
+                self.state.templates.insert(name, body);
+                self.save_state().await?;
                 Ok(DdlResult::Success { message })
             },
+
+            DdlStatement::ApplyTemplate { name, resource, principal } => {
+                let body = self.state.templates
+                    .get(&name)
+                    .ok_or_else(|| anyhow!("Template '{}' does not exist", name))?;
+                let permissions = lakesql_parser::expand_template(body, &resource, &principal)?
+                    .iter()
+                    .map(|statement| statement.to_permission())
+                    .collect::<Result<Vec<_>>>()?;
+
+                let count = permissions.len();
+                for permission in permissions {
+                    self.grant_permissions(permission).await?;
+                }
+                Ok(DdlResult::Success {
+                    message: format!("Applied template {}: {} grant(s)", name, count)
+                })
+            },
+
+            other => Err(anyhow!("Statement does not mutate state: {:?}", other)),
         }
     }
 
-    /// Get current state (for debugging/inspection)
-    pub fn get_state(&self) -> &EmulatorState {
-        &self.state
+    /// Assign an LF-Tag value to a user or role, so grants to
+    /// `TAGGED key='value'` apply to it. Replaces any value it had for the key.
+    pub async fn assign_principal_tag(&mut self, principal: &Principal, key: &str, value: &str) -> Result<()> {
+        let name = match principal {
+            Principal::User(name) | Principal::Role(name) => canonical_iam_name(name),
+            other => return Err(anyhow!("Only users and roles can be tagged, not {:?}", other)),
+        };
+        self.check_tag_value(key, value)?;
+
+        self.state.principal_tags
+            .entry(name.to_string())
+            .or_default()
+            .insert(key.to_string(), value.to_string());
+        self.engine.update_state(&self.state);
+        self.save_state().await
     }
 
-    /// Test row-level security with custom session context
-    pub async fn test_row_level_security(
-        &mut self,
-        principal: &Principal,
-        resource: &Resource,
-        action: &Action,
-        session_context: HashMap<String, String>
-    ) -> Result<bool> {
-        // Set session context
-        self.state.session_context = session_context;
+    /// Assign an LF-Tag value to a database, table or view, so grants on
+    /// `RESOURCES TAGGED` conditions it satisfies apply to it. A table's
+    /// column list is ignored; tags apply to the whole table.
+    pub async fn assign_resource_tag(&mut self, resource: &Resource, key: &str, value: &str) -> Result<()> {
+        let target = match resource {
+            Resource::Database { name, .. } => name.clone(),
+            Resource::Table { database, table, .. } => format!("{}.{}", database, table),
+            Resource::View { database, view } => format!("{}.{}", database, view),
+            other => return Err(anyhow!("Only databases, tables and views can be tagged, not {:?}", other)),
+        };
+        self.check_tag_value(key, value)?;
+
+        self.state.resource_tags
+            .entry(target)
+            .or_default()
+            .insert(key.to_string(), value.to_string());
         self.engine.update_state(&self.state);
-        
-        // Check permission with row-level filters
-        self.check_permissions(principal, resource, action).await
+        self.save_state().await
     }
-}
 
-#[async_trait]
-impl LakeFormationBackend for EmulatorBackend {
-    async fn execute_ddl(&mut self, sql: &str) -> Result<DdlResult> {
-        use lakesql_parser::parse_ddl;
-        
-        // Parse the DDL statement
-        let statement = parse_ddl(sql)?;
-        
-        // Execute it directly
-        self.execute_ddl_direct(statement).await
+    /// Error unless `key` is a defined LF-Tag allowing `value`
+    fn check_tag_value(&self, key: &str, value: &str) -> Result<()> {
+        let tag = self.state.tags
+            .get(key)
+            .ok_or_else(|| anyhow!("LF-Tag '{}' is not defined", key))?;
+        if !tag.values.iter().any(|allowed| allowed == value) {
+            return Err(anyhow!("'{}' is not an allowed value of LF-Tag '{}'", value, key));
+        }
+        Ok(())
     }
 
-    async fn grant_permissions(&mut self, permission: Permission) -> Result<DdlResult> {
-        // Remove any existing permission for same principal/resource combination
-        self.state.permissions.retain(|p| {
-            !(p.principal == permission.principal && p.resource == permission.resource)
-        });
+    /// Create several roles with a single save. Roles that already exist are
+    /// left untouched, members included.
+    pub async fn create_roles(&mut self, names: &[String]) -> Result<DdlResult> {
+        let mut created = 0;
+        let mut skipped = 0;
+        for name in names {
+            if self.state.roles.contains_key(name) {
+                skipped += 1;
+            } else {
+                self.state.roles.insert(name.clone(), HashSet::new());
+                self.engine.apply_role(name, HashSet::new());
+                created += 1;
+            }
+        }
 
-        // Add the new permission
-        let message = format!(
-            "Granted {:?} on {:?} to {:?}", 
-            permission.actions, permission.resource, permission.principal
-        );
-        
-        self.state.permissions.push(permission);
-        self.engine.update_state(&self.state);
         self.save_state().await?;
-        
-        Ok(DdlResult::Success { message })
+        Ok(DdlResult::Success {
+            message: format!("Created {} role(s), skipped {} existing", created, skipped)
+        })
     }
 
-    async fn revoke_permissions(
-        &mut self, 
-        principal: &Principal, 
-        resource: &Resource, 
-        actions: &[Action]
-    ) -> Result<DdlResult> {
-        let initial_count = self.state.permissions.len();
+    /// Grants a destructive statement (DROP ROLE, REVOKE ALL) would remove,
+    /// or None when the statement isn't destructive
+    fn destructive_removals(&self, statement: &lakesql_parser::DdlStatement) -> Result<Option<Vec<Permission>>> {
+        use lakesql_parser::DdlStatement;
 
-        // Remove permissions that match principal, resource, and any of the actions
-        self.state.permissions.retain(|p| {
-            !(p.principal == *principal && 
-              p.resource == *resource &&
-              actions.iter().any(|a| p.actions.contains(a)))
-        });
+        match statement {
+            DdlStatement::DropRole { name } => Ok(Some(self.permissions_for_role(name)?)),
+            DdlStatement::Revoke { actions, resource, principal } if actions.contains(&Action::All) => {
+                Ok(Some(self.state.permissions
+                    .iter()
+                    .filter(|p| revoke_matches(self.revoke_match, p, principal, resource, actions))
+                    .cloned()
+                    .collect()))
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// Preview the effect of a DDL script without applying it: the script
+    /// runs against a copy of the current state, which is then diffed
+    /// against the current state
+    pub async fn plan_sql(&self, sql: &str) -> Result<StateDiff> {
+        let mut scratch = self.scratch();
+        for statement in lakesql_parser::parse_ddl_script(sql)? {
+            scratch.execute_ddl_direct(statement).await?;
+        }
+
+        Ok(self.state.diff(&scratch.state))
+    }
+
+    /// Check a permission as it would stand after running some DDL, for
+    /// change-impact review. The DDL runs against a copy of the state; the
+    /// real state is left untouched.
+    pub async fn check_after(
+        &self,
+        ddl: &[&str],
+        principal: &Principal,
+        resource: &Resource,
+        action: &Action,
+    ) -> Result<bool> {
+        let mut scratch = self.scratch();
+        for sql in ddl {
+            for statement in lakesql_parser::parse_ddl_many(sql)? {
+                scratch.execute_ddl_direct(statement).await?;
+            }
+        }
+
+        scratch.check_permissions(principal, resource, action).await
+    }
+
+    /// In-memory copy of this backend with the same settings, no storage
+    /// and no logs, for trying out changes
+    fn scratch(&self) -> EmulatorBackend<S> {
+        let mut scratch = EmulatorBackend::<S> {
+            state: self.state.clone(),
+            storage: None,
+            engine: EmulatorEngine::new(),
+            revoke_match: self.revoke_match,
+            require_registered_locations: self.require_registered_locations,
+            auto_save: false,
+            prune_expired_on_load: false,
+            clock: self.clock.clone(),
+            replay_log: None,
+            audit_log: None,
+            max_grant_columns: self.max_grant_columns,
+            allow_destructive: self.allow_destructive,
+        };
+        scratch.engine.update_state(&scratch.state);
+        scratch
+    }
+
+    /// Get current state (for debugging/inspection)
+    pub fn get_state(&self) -> &EmulatorState {
+        &self.state
+    }
+
+    /// Check the current state for internal inconsistencies
+    pub fn validate_state(&self) -> Result<Vec<ValidationIssue>> {
+        Ok(self.state.validate())
+    }
+
+    /// Count the distinct (resource, action) pairs a principal can exercise
+    /// through direct and role-inherited grants. ALL counts as every concrete
+    /// action. The emulator keeps no table catalog, so a database grant counts
+    /// as a single resource rather than being expanded over its tables.
+    pub fn access_breadth(&self, principal: &Principal) -> Result<usize> {
+        let pairs: HashSet<(Resource, Action)> = self.engine
+            .get_effective_permissions(principal)
+            .into_iter()
+            .flat_map(|p| {
+                p.actions
+                    .iter()
+                    .flat_map(Action::expand)
+                    .map(move |action| (p.resource.clone(), action))
+            })
+            .collect();
+        Ok(pairs.len())
+    }
+
+    /// Permissions granted directly to a role, i.e. what `DROP ROLE` would
+    /// cascade-remove
+    pub fn permissions_for_role(&self, role: &str) -> Result<Vec<Permission>> {
+        Ok(self.state.permissions
+            .iter()
+            .filter(|p| matches!(&p.principal, Principal::Role(name) if name == role))
+            .cloned()
+            .collect())
+    }
+
+    /// Membership chain that authorizes a request, e.g. user -> role, for
+    /// explaining inherited access; None if the request is denied
+    pub fn authorizing_path(&self, principal: &Principal, resource: &Resource, action: &Action) -> Option<Vec<Principal>> {
+        self.engine.authorizing_path(principal, resource, action)
+    }
+
+    /// Table grants on a table with this name in any database
+    pub fn list_permissions_by_table_name(&self, table: &str) -> Result<Vec<Permission>> {
+        Ok(self.state.permissions
+            .iter()
+            .filter(|p| matches!(&p.resource, Resource::Table { table: name, .. } if name == table))
+            .cloned()
+            .collect())
+    }
+
+    /// List permissions that reference an LF-Tag, either through a tagged
+    /// principal or a tagged resource condition on that key
+    pub async fn permissions_referencing_tag(&self, tag_key: &str) -> Result<Vec<Permission>> {
+        let permissions = self.state.permissions
+            .iter()
+            .filter(|p| {
+                let principal_match = matches!(
+                    &p.principal,
+                    Principal::TaggedPrincipal { tag_key: key, .. } if key == tag_key
+                );
+                let resource_match = matches!(
+                    &p.resource,
+                    Resource::TaggedResource { tag_conditions }
+                        if tag_conditions.iter().any(|(key, _)| key == tag_key)
+                );
+                principal_match || resource_match
+            })
+            .cloned()
+            .collect();
+        Ok(permissions)
+    }
+
+    /// Evaluate a row filter expression against a sample row and session
+    /// context, without creating a grant
+    pub fn evaluate_filter(
+        &self,
+        expression: &str,
+        row: HashMap<String, String>,
+        session: HashMap<String, String>,
+    ) -> Result<bool> {
+        let mut evaluator = expression::ExpressionEvaluator::new();
+        evaluator.set_row_data(row);
+        evaluator.set_session_context(session);
+        evaluator.evaluate_filter(&RowFilter {
+            expression: expression.to_string(),
+            session_context: None,
+            ast: None,
+        })
+    }
+
+    /// Test row-level security with custom session context
+    pub async fn test_row_level_security(
+        &mut self,
+        principal: &Principal,
+        resource: &Resource,
+        action: &Action,
+        session_context: HashMap<String, String>
+    ) -> Result<bool> {
+        // Set session context
+        self.engine.apply_session_context(session_context.clone());
+        self.state.session_context = session_context;
+        
+        // Check permission with row-level filters
+        self.check_permissions(principal, resource, action).await
+    }
+}
+
+#[async_trait]
+impl<S: Storage> LakeFormationBackend for EmulatorBackend<S> {
+    async fn execute_ddl(&mut self, sql: &str) -> Result<DdlResult> {
+        use lakesql_parser::parse_ddl;
+        
+        // Parse the DDL statement
+        let statement = match parse_ddl(sql) {
+            Ok(statement) => statement,
+            Err(e) => {
+                let result = Err(e);
+                if let Err(e) = self.record_audit(AuditEntry::new(self.clock.now(), sql, None, &result)).await {
+                    tracing::warn!(error = %e, "failed to write audit log entry");
+                }
+                return result;
+            },
+        };
+
+        // Execute it directly
+        let result = self.execute_ddl_direct(statement.clone()).await;
+        // The statement has already run, so an audit failure mustn't change its outcome
+        if let Err(e) = self.record_audit(AuditEntry::new(self.clock.now(), sql, Some(&statement), &result)).await {
+            tracing::warn!(error = %e, "failed to write audit log entry");
+        }
+        let result = result?;
+        if let Err(e) = self.record_statement(sql).await {
+            tracing::warn!(error = %e, "failed to write replay log entry");
+        }
+        Ok(result)
+    }
+
+    async fn grant_permissions(&mut self, mut permission: Permission) -> Result<DdlResult> {
+        permission.resource = permission.resource.normalize();
+        permission.normalize_actions();
+        if let Resource::DataLocation { path } = &permission.resource {
+            if self.require_registered_locations && !self.state.is_location_registered(path) {
+                return Err(anyhow!("Data location '{}' is not registered", path));
+            }
+        }
+
+        // Row filters only restrict reads; on a write they would silently do nothing
+        if permission.row_filter.is_some() {
+            let writes: Vec<_> = permission.actions
+                .iter()
+                .filter(|a| !matches!(a, Action::Select | Action::Describe))
+                .collect();
+            if !writes.is_empty() {
+                return Err(anyhow!(
+                    "Row filters only apply to SELECT; grant {:?} in a separate statement without WHERE",
+                    writes
+                ));
+            }
+        }
+
+        if let (Some(max), Resource::Table { columns: Some(columns), .. }) = (self.max_grant_columns, &permission.resource) {
+            if columns.len() > max {
+                return Err(anyhow!(
+                    "Grant lists {} columns, more than the maximum of {}",
+                    columns.len(), max
+                ));
+            }
+        }
+
+        let message = format!(
+            "Granted {:?} on {:?} to {:?}", 
+            permission.actions, permission.resource, permission.principal
+        );
+        tracing::info!(
+            principal = ?permission.principal,
+            resource = ?permission.resource,
+            actions = ?permission.actions,
+            "granted permissions"
+        );
+
+        // Fold the grant into a matching one; grants with a different row
+        // filter, condition or grant option, and denies, stay separate
+        self.engine.apply_grant(&permission);
+        match self.state.permissions.iter_mut().find(|p| p.merges_with(&permission)) {
+            Some(existing) => existing.merge(&permission),
+            None => self.state.permissions.push(permission),
+        }
+        self.save_state().await?;
+        
+        Ok(DdlResult::Success { message })
+    }
+
+    async fn revoke_permissions(
+        &mut self, 
+        principal: &Principal, 
+        resource: &Resource, 
+        actions: &[Action]
+    ) -> Result<DdlResult> {
+        let initial_count = self.state.permissions.len();
+
+        let revoke_match = self.revoke_match;
+        let affected_count = self.state.permissions
+            .iter()
+            .filter(|p| revoke_matches(revoke_match, p, principal, resource, actions))
+            .count();
+        self.state.permissions.retain_mut(|p| revoke_from(revoke_match, p, principal, resource, actions));
+        self.engine.apply_revoke(|p| revoke_from(revoke_match, p, principal, resource, actions));
 
         let removed_count = initial_count - self.state.permissions.len();
-        self.engine.update_state(&self.state);
+        tracing::info!(
+            principal = ?principal,
+            resource = ?resource,
+            removed = removed_count,
+            narrowed = affected_count - removed_count,
+            "revoked permissions"
+        );
         self.save_state().await?;
 
         let message = format!(
-            "Revoked {} permission(s) for {:?} on {:?}", 
-            removed_count, principal, resource
+            "Revoked {:?} from {} permission(s) for {:?} on {:?}",
+            actions, affected_count, principal, resource
         );
         
         Ok(DdlResult::Success { message })
@@ -275,6 +1175,12 @@ impl LakeFormationBackend for EmulatorBackend {
     async fn delete_tag(&mut self, tag_key: &str) -> Result<DdlResult> {
         self.state.tags.remove(tag_key);
         // TODO: Remove any tag-based permissions
+        for assigned in [&mut self.state.principal_tags, &mut self.state.resource_tags] {
+            for tags in assigned.values_mut() {
+                tags.remove(tag_key);
+            }
+            assigned.retain(|_, tags| !tags.is_empty());
+        }
         self.engine.update_state(&self.state);
         self.save_state().await?;
         Ok(DdlResult::Success { 
@@ -283,9 +1189,10 @@ impl LakeFormationBackend for EmulatorBackend {
     }
 
     async fn list_permissions_for_principal(&self, principal: &Principal) -> Result<Vec<Permission>> {
+        // An IAM ARN and its friendly name are the same principal
         let permissions = self.state.permissions
             .iter()
-            .filter(|p| p.principal.matches(principal))
+            .filter(|p| p.principal == *principal || p.principal.matches(principal))
             .cloned()
             .collect();
         Ok(permissions)
@@ -301,8 +1208,8 @@ impl LakeFormationBackend for EmulatorBackend {
     }
 
     async fn set_session_context(&mut self, context: HashMap<String, String>) -> Result<()> {
+        self.engine.apply_session_context(context.clone());
         self.state.session_context = context;
-        self.engine.update_state(&self.state);
         self.save_state().await?;
         Ok(())
     }
@@ -355,6 +1262,7 @@ mod tests {
             database: "sales".to_string(),
             table: "orders".to_string(),
             columns: None,
+            catalog_id: None,
         };
         let action = Action::Select;
 
@@ -365,4 +1273,1174 @@ mod tests {
         let denied = backend.check_permissions(&principal, &resource, &Action::Delete).await.unwrap();
         assert!(!denied);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_grants_merge_into_one_permission() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.execute_ddl("CREATE ROLE analyst").await.unwrap();
+        backend.execute_ddl("GRANT SELECT ON sales.orders TO ROLE analyst").await.unwrap();
+        backend.execute_ddl("GRANT INSERT ON sales.orders TO ROLE analyst").await.unwrap();
+
+        let analyst = Principal::Role("analyst".to_string());
+        let orders = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        assert_eq!(backend.get_state().permissions.len(), 1);
+        assert_eq!(backend.get_state().permissions[0].actions, vec![Action::Insert, Action::Select]);
+        assert!(backend.check_permissions(&analyst, &orders, &Action::Select).await.unwrap());
+        assert!(backend.check_permissions(&analyst, &orders, &Action::Insert).await.unwrap());
+
+        // A row filter or grant option keeps the grant separate
+        backend.execute_ddl("GRANT SELECT ON sales.orders TO ROLE analyst WHERE region = 'west'").await.unwrap();
+        backend.execute_ddl("GRANT DELETE ON sales.orders TO ROLE analyst WITH GRANT OPTION").await.unwrap();
+        backend.execute_ddl("GRANT UPDATE ON sales.orders TO ROLE analyst WITH GRANT OPTION").await.unwrap();
+        let permissions = &backend.get_state().permissions;
+        assert_eq!(permissions.len(), 3);
+        assert_eq!(permissions[0].actions, vec![Action::Insert, Action::Select]);
+        assert!(permissions[1].row_filter.is_some());
+        assert_eq!(permissions[2].actions, vec![Action::Delete, Action::Update]);
+        assert_eq!(permissions[2].grantable_actions, HashSet::from([Action::Update, Action::Delete]));
+        assert!(backend.get_state().validate().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_revoke_keeps_other_actions() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.execute_ddl("CREATE ROLE analyst").await.unwrap();
+        backend.execute_ddl("GRANT SELECT, INSERT ON sales.orders TO ROLE analyst WITH GRANT OPTION").await.unwrap();
+
+        backend.execute_ddl("REVOKE INSERT ON sales.orders FROM ROLE analyst").await.unwrap();
+
+        let analyst = Principal::Role("analyst".to_string());
+        let orders = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        let permissions = &backend.get_state().permissions;
+        assert_eq!(permissions.len(), 1);
+        assert_eq!(permissions[0].actions, vec![Action::Select]);
+        assert_eq!(permissions[0].grantable_actions, HashSet::from([Action::Select]));
+        assert!(backend.check_permissions(&analyst, &orders, &Action::Select).await.unwrap());
+        assert!(!backend.check_permissions(&analyst, &orders, &Action::Insert).await.unwrap());
+
+        // The last action going takes the grant with it
+        backend.execute_ddl("REVOKE SELECT ON sales.orders FROM ROLE analyst").await.unwrap();
+        assert!(backend.get_state().permissions.is_empty());
+        assert!(!backend.check_permissions(&analyst, &orders, &Action::Select).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_deny_overrides_grant() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.execute_ddl("CREATE ROLE intern").await.unwrap();
+        backend.execute_ddl("ALTER ROLE intern ADD USER 'alice'").await.unwrap();
+        backend.execute_ddl("GRANT SELECT, DESCRIBE ON DATABASE sales TO ROLE intern").await.unwrap();
+
+        let alice = Principal::User("alice".to_string());
+        let orders = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        assert!(backend.check_permissions(&alice, &orders, &Action::Select).await.unwrap());
+
+        // The deny sits alongside the database grant and wins, even through the role
+        backend.execute_ddl("DENY SELECT ON sales.orders TO ROLE intern").await.unwrap();
+        assert_eq!(backend.get_state().permissions.len(), 2);
+        assert!(!backend.check_permissions(&alice, &orders, &Action::Select).await.unwrap());
+        assert!(backend.check_permissions(&alice, &orders, &Action::Describe).await.unwrap());
+
+        // Granting the table directly doesn't replace the deny
+        backend.execute_ddl("GRANT SELECT ON sales.orders TO ROLE intern").await.unwrap();
+        assert!(!backend.check_permissions(&alice, &orders, &Action::Select).await.unwrap());
+
+        backend.execute_ddl("REVOKE SELECT ON sales.orders FROM ROLE intern").await.unwrap();
+        assert!(backend.check_permissions(&alice, &orders, &Action::Select).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_role_deny_overrides_direct_user_grant() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.execute_ddl("CREATE ROLE contractors").await.unwrap();
+        backend.execute_ddl("ALTER ROLE contractors ADD USER 'alice'").await.unwrap();
+        backend.execute_ddl("GRANT SELECT ON sales.orders TO USER 'alice'").await.unwrap();
+
+        let alice = Principal::User("alice".to_string());
+        let orders = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        assert!(backend.check_permissions(&alice, &orders, &Action::Select).await.unwrap());
+
+        // The deny reaches alice through her role and beats her own grant
+        backend.execute_ddl("DENY SELECT ON sales.orders TO ROLE contractors").await.unwrap();
+        assert!(!backend.check_permissions(&alice, &orders, &Action::Select).await.unwrap());
+        let (allowed, reason) = backend.engine.check_permission_with_reason(&alice, &orders, &Action::Select);
+        assert!(!allowed);
+        assert!(reason.contains("explicit deny"), "{}", reason);
+
+        // Leaving the role lifts the deny
+        backend.execute_ddl("ALTER ROLE contractors DROP USER 'alice'").await.unwrap();
+        assert!(backend.check_permissions(&alice, &orders, &Action::Select).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_grant_role_inheritance() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.execute_ddl("CREATE ROLES analyst, senior_analyst, lead").await.unwrap();
+        backend.execute_ddl("ALTER ROLE lead ADD USER 'carol'").await.unwrap();
+        backend.execute_ddl("GRANT SELECT ON sales.orders TO ROLE analyst").await.unwrap();
+        backend.execute_ddl("GRANT INSERT ON sales.orders TO ROLE senior_analyst").await.unwrap();
+
+        // lead -> senior_analyst -> analyst
+        backend.execute_ddl("GRANT ROLE analyst TO ROLE senior_analyst").await.unwrap();
+        backend.execute_ddl("GRANT ROLE senior_analyst TO ROLE lead").await.unwrap();
+
+        let carol = Principal::User("carol".to_string());
+        let senior = Principal::Role("senior_analyst".to_string());
+        let orders = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        assert!(backend.check_permissions(&carol, &orders, &Action::Select).await.unwrap());
+        assert!(backend.check_permissions(&carol, &orders, &Action::Insert).await.unwrap());
+        assert!(backend.check_permissions(&senior, &orders, &Action::Select).await.unwrap());
+        // Inheritance only runs one way
+        let analyst = Principal::Role("analyst".to_string());
+        assert!(!backend.check_permissions(&analyst, &orders, &Action::Insert).await.unwrap());
+        assert_eq!(backend.engine.get_effective_permissions(&carol).len(), 2);
+
+        // Closing the loop, directly or transitively, is refused
+        for sql in ["GRANT ROLE lead TO ROLE analyst", "GRANT ROLE senior_analyst TO ROLE analyst", "GRANT ROLE lead TO ROLE lead"] {
+            let err = backend.execute_ddl(sql).await.unwrap_err();
+            assert!(err.to_string().contains("cycle"), "{}: {}", sql, err);
+        }
+        assert!(!backend.get_state().role_parents.contains_key("analyst"));
+        assert!(backend.execute_ddl("GRANT ROLE analyst TO ROLE missing").await.is_err());
+
+        // Role edges and user members don't mix: a user named like a role
+        // inherits nothing from it, and a user member named like a role
+        // doesn't make that role inherit anything
+        let user_lead = Principal::User("lead".to_string());
+        assert!(!backend.check_permissions(&user_lead, &orders, &Action::Select).await.unwrap());
+        backend.execute_ddl("ALTER ROLE lead ADD USER 'analyst'").await.unwrap();
+        assert!(!backend.check_permissions(&analyst, &orders, &Action::Insert).await.unwrap());
+        let user_analyst = Principal::User("analyst".to_string());
+        assert!(backend.check_permissions(&user_analyst, &orders, &Action::Insert).await.unwrap());
+
+        // Dropping a role removes it from the hierarchy
+        backend.execute_ddl("DROP ROLE senior_analyst").await.unwrap();
+        assert!(backend.get_state().role_parents.is_empty());
+        assert!(!backend.check_permissions(&carol, &orders, &Action::Select).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_permissions_referencing_tag() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+
+        let tagged_principal = Permission::new(
+            Principal::TaggedPrincipal {
+                tag_key: "classification".to_string(),
+                tag_values: vec!["confidential".to_string()],
+            },
+            Resource::Database { name: "sales".to_string(), catalog_id: None },
+            vec![Action::Describe],
+        );
+        let tagged_resource = Permission::new(
+            Principal::Role("analyst".to_string()),
+            Resource::TaggedResource {
+                tag_conditions: vec![
+                    ("department".to_string(), vec!["finance".to_string()]),
+                    ("classification".to_string(), vec!["internal".to_string()]),
+                ],
+            },
+            vec![Action::Select],
+        );
+        let unrelated = Permission::new(
+            Principal::Role("analyst".to_string()),
+            Resource::TaggedResource {
+                tag_conditions: vec![("department".to_string(), vec!["hr".to_string()])],
+            },
+            vec![Action::Select],
+        );
+
+        backend.grant_permissions(tagged_principal.clone()).await.unwrap();
+        backend.grant_permissions(tagged_resource.clone()).await.unwrap();
+        backend.grant_permissions(unrelated).await.unwrap();
+        backend.execute_ddl("GRANT SELECT ON sales.orders TO ROLE analyst").await.unwrap();
+
+        let referencing = backend.permissions_referencing_tag("classification").await.unwrap();
+        assert_eq!(referencing.len(), 2);
+        assert!(referencing.contains(&tagged_principal));
+        assert!(referencing.contains(&tagged_resource));
+
+        let none = backend.permissions_referencing_tag("region").await.unwrap();
+        assert!(none.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_column_revoke_exact_match_keeps_table_grant() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.execute_ddl("GRANT SELECT ON sales.orders TO ROLE analyst").await.unwrap();
+        backend.execute_ddl("GRANT SELECT ON sales.customers(region) TO ROLE analyst").await.unwrap();
+
+        backend.execute_ddl("REVOKE SELECT ON sales.orders(region) FROM ROLE analyst").await.unwrap();
+        assert_eq!(backend.state.permissions.len(), 2);
+
+        backend.execute_ddl("REVOKE SELECT ON sales.customers(region) FROM ROLE analyst").await.unwrap();
+        assert_eq!(backend.state.permissions.len(), 1);
+        assert_eq!(
+            backend.state.permissions[0].resource,
+            Resource::Table {
+                database: "sales".to_string(),
+                table: "orders".to_string(),
+                columns: None,
+                catalog_id: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_column_revoke_covering_match_removes_table_grant() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.set_revoke_match(RevokeMatch::Covering);
+        backend.execute_ddl("GRANT SELECT ON sales.orders TO ROLE analyst").await.unwrap();
+
+        backend.execute_ddl("REVOKE SELECT ON sales.orders(region) FROM ROLE analyst").await.unwrap();
+        assert!(backend.state.permissions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_show_statements_do_not_write_state() {
+        let storage = MemoryStorage::new();
+        let mut backend = EmulatorBackend::with_storage(storage.clone()).await.unwrap();
+        backend.execute_ddl("CREATE ROLE analyst").await.unwrap();
+        backend.execute_ddl("GRANT SELECT ON sales.orders TO ROLE analyst").await.unwrap();
+
+        let saves_before = storage.save_count();
+
+        for sql in ["SHOW PERMISSIONS", "SHOW ROLES", "SHOW TAGS"] {
+            backend.execute_ddl(sql).await.unwrap();
+        }
+
+        assert_eq!(storage.save_count(), saves_before);
+    }
+
+    #[tokio::test]
+    async fn test_state_round_trips_through_storage() {
+        let storage = MemoryStorage::new();
+        let mut backend = EmulatorBackend::with_storage(storage.clone()).await.unwrap();
+        backend.execute_ddl("GRANT SELECT ON sales.orders TO ROLE analyst").await.unwrap();
+        assert_eq!(storage.stored().unwrap().permissions.len(), 1);
+
+        let reloaded = EmulatorBackend::with_storage(storage).await.unwrap();
+        assert!(reloaded.check_permissions(
+            &Principal::Role("analyst".to_string()),
+            &Resource::Table {
+                database: "sales".to_string(),
+                table: "orders".to_string(),
+                columns: None,
+                catalog_id: None,
+            },
+            &Action::Select,
+        ).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_grant_on_registered_location() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.set_require_registered_locations(true);
+
+        backend.execute_ddl("REGISTER LOCATION 's3://data-lake/sales'").await.unwrap();
+        assert!(backend.state.registered_locations.contains("s3://data-lake/sales"));
+
+        backend.execute_ddl(
+            "GRANT DATA_LOCATION_ACCESS ON 's3://data-lake/sales/orders' TO ROLE etl"
+        ).await.unwrap();
+        assert_eq!(backend.state.permissions.len(), 1);
+
+        // A sibling sharing the registered path as a string prefix isn't under it
+        assert!(!backend.state.is_location_registered("s3://data-lake/sales2"));
+        assert!(backend.execute_ddl(
+            "GRANT DATA_LOCATION_ACCESS ON 's3://data-lake/sales2/orders' TO ROLE etl"
+        ).await.is_err());
+        assert_eq!(backend.state.permissions.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_grant_on_unregistered_location() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        let sql = "GRANT DATA_LOCATION_ACCESS ON 's3://data-lake/hr' TO ROLE etl";
+
+        backend.set_require_registered_locations(true);
+        assert!(backend.execute_ddl(sql).await.is_err());
+        assert!(backend.state.permissions.is_empty());
+
+        backend.set_require_registered_locations(false);
+        backend.execute_ddl(sql).await.unwrap();
+        assert_eq!(backend.state.permissions.len(), 1);
+    }
+
+    /// Collects formatted tracing output so tests can assert on emitted events
+    #[derive(Clone, Default)]
+    struct CapturedLogs(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for CapturedLogs {
+        type Writer = CapturedLogs;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_grant_and_revoke_emit_tracing_events() {
+        let logs = CapturedLogs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing_subscriber::filter::LevelFilter::DEBUG)
+            .with_writer(logs.clone())
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let dir = tempfile::tempdir().unwrap();
+        let state_file = dir.path().join("state.json").to_string_lossy().to_string();
+        let mut backend = EmulatorBackend::new(Some(state_file)).await.unwrap();
+        backend.execute_ddl("GRANT SELECT ON sales.orders TO ROLE analyst").await.unwrap();
+        backend.execute_ddl("REVOKE SELECT ON sales.orders FROM ROLE analyst").await.unwrap();
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines.iter().any(|l| l.contains("INFO") && l.contains("granted permissions")));
+        assert!(lines.iter().any(|l| l.contains("INFO") && l.contains("revoked permissions") && l.contains("removed=1")));
+        assert!(lines.iter().any(|l| l.contains("DEBUG") && l.contains("saved emulator state")));
+    }
+
+    #[tokio::test]
+    async fn test_assign_principal_tag() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.execute_ddl("CREATE TAG department VALUES ('finance', 'hr')").await.unwrap();
+        backend.execute_ddl("GRANT DESCRIBE ON DATABASE sales TO TAGGED department='finance'").await.unwrap();
+
+        let analyst = Principal::Role("analyst".to_string());
+        let sales = Resource::Database { name: "sales".to_string(), catalog_id: None };
+        assert!(!backend.check_permissions(&analyst, &sales, &Action::Describe).await.unwrap());
+
+        backend.assign_principal_tag(&analyst, "department", "finance").await.unwrap();
+        assert!(backend.check_permissions(&analyst, &sales, &Action::Describe).await.unwrap());
+        assert_eq!(backend.get_state().subset_for_principal(&analyst).permissions.len(), 1);
+
+        // Reassigning replaces the value
+        backend.assign_principal_tag(&analyst, "department", "hr").await.unwrap();
+        assert!(!backend.check_permissions(&analyst, &sales, &Action::Describe).await.unwrap());
+
+        assert!(backend.assign_principal_tag(&analyst, "department", "legal").await.is_err());
+        assert!(backend.assign_principal_tag(&analyst, "region", "west").await.is_err());
+        assert!(backend.assign_principal_tag(&Principal::SamlGroup("g".to_string()), "department", "hr").await.is_err());
+
+        // Dropping the tag drops its assignments
+        backend.execute_ddl("DROP TAG department").await.unwrap();
+        assert!(backend.get_state().principal_tags.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_assign_resource_tag() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.execute_ddl("CREATE TAG classification VALUES ('confidential', 'public')").await.unwrap();
+        backend.execute_ddl("GRANT SELECT ON RESOURCES TAGGED classification='confidential' TO ROLE auditor").await.unwrap();
+
+        let auditor = Principal::Role("auditor".to_string());
+        let table = |name: &str| Resource::Table {
+            database: "sales".to_string(),
+            table: name.to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        let sales = Resource::Database { name: "sales".to_string(), catalog_id: None };
+        assert!(!backend.check_permissions(&auditor, &table("orders"), &Action::Select).await.unwrap());
+
+        backend.assign_resource_tag(&table("orders"), "classification", "confidential").await.unwrap();
+        assert!(backend.check_permissions(&auditor, &table("orders"), &Action::Select).await.unwrap());
+        assert!(!backend.check_permissions(&auditor, &table("refunds"), &Action::Select).await.unwrap());
+
+        // Tables inherit their database's tags unless they set the key
+        backend.assign_resource_tag(&sales, "classification", "confidential").await.unwrap();
+        assert!(backend.check_permissions(&auditor, &table("refunds"), &Action::Select).await.unwrap());
+        backend.assign_resource_tag(&table("refunds"), "classification", "public").await.unwrap();
+        assert!(!backend.check_permissions(&auditor, &table("refunds"), &Action::Select).await.unwrap());
+
+        assert!(backend.assign_resource_tag(&table("orders"), "classification", "secret").await.is_err());
+        assert!(backend.assign_resource_tag(&Resource::LfTag { key: "x".to_string() }, "classification", "public").await.is_err());
+
+        backend.execute_ddl("DROP TAG classification").await.unwrap();
+        assert!(backend.get_state().resource_tags.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_subset_for_principal() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        for sql in [
+            "CREATE TAG department VALUES ('finance', 'hr')",
+            "CREATE TAG region VALUES ('us', 'eu')",
+            "GRANT SELECT ON sales.orders TO ROLE analyst",
+            "GRANT INSERT ON sales.orders TO ROLE engineer",
+            "GRANT DESCRIBE ON DATABASE sales TO USER 'bob@company.com'",
+        ] {
+            backend.execute_ddl(sql).await.unwrap();
+        }
+        for (role, tag_key, value) in [("analyst", "department", "finance"), ("engineer", "region", "eu")] {
+            backend.state.permissions.push(Permission::new(
+                Principal::Role(role.to_string()),
+                Resource::TaggedResource {
+                    tag_conditions: vec![(tag_key.to_string(), vec![value.to_string()])],
+                },
+                vec![Action::Select],
+            ));
+        }
+        backend.state.roles.insert(
+            "analyst".to_string(),
+            HashSet::from(["alice@company.com".to_string()]),
+        );
+        backend.state.roles.insert(
+            "engineer".to_string(),
+            HashSet::from(["bob@company.com".to_string()]),
+        );
+
+        let subset = backend.get_state().subset_for_principal(&Principal::Role("analyst".to_string()));
+        assert_eq!(subset.permissions.len(), 2);
+        assert!(subset.permissions.iter().all(|p| p.principal == Principal::Role("analyst".to_string())));
+        assert_eq!(subset.roles.keys().collect::<Vec<_>>(), vec!["analyst"]);
+        assert_eq!(subset.tags.keys().collect::<Vec<_>>(), vec!["department"]);
+
+        // A user brings along the roles it belongs to and their grants
+        let subset = backend.get_state().subset_for_principal(&Principal::User("bob@company.com".to_string()));
+        assert_eq!(subset.permissions.len(), 3);
+        assert!(subset.permissions.iter().all(|p| p.principal != Principal::Role("analyst".to_string())));
+        assert_eq!(subset.roles.keys().collect::<Vec<_>>(), vec!["engineer"]);
+        assert_eq!(subset.tags.keys().collect::<Vec<_>>(), vec!["region"]);
+    }
+
+    #[tokio::test]
+    async fn test_subset_for_principal_follows_role_inheritance() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        for sql in [
+            "CREATE ROLES analyst, senior_analyst, lead",
+            "ALTER ROLE lead ADD USER 'carol'",
+            "GRANT SELECT ON sales.orders TO ROLE analyst",
+            "GRANT INSERT ON sales.orders TO ROLE senior_analyst",
+            "GRANT DELETE ON sales.orders TO ROLE lead",
+            // lead -> senior_analyst -> analyst
+            "GRANT ROLE analyst TO ROLE senior_analyst",
+            "GRANT ROLE senior_analyst TO ROLE lead",
+        ] {
+            backend.execute_ddl(sql).await.unwrap();
+        }
+        let orders = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+
+        // The subset answers the same way the full state does
+        for principal in [Principal::User("carol".to_string()), Principal::Role("senior_analyst".to_string())] {
+            let subset = backend.get_state().subset_for_principal(&principal);
+            let mut restored = EmulatorBackend::new(None).await.unwrap();
+            restored.state = subset;
+            restored.engine.update_state(&restored.state);
+            for action in [Action::Select, Action::Insert, Action::Delete] {
+                assert_eq!(
+                    restored.check_permissions(&principal, &orders, &action).await.unwrap(),
+                    backend.check_permissions(&principal, &orders, &action).await.unwrap(),
+                    "{:?} {:?}", principal, action,
+                );
+            }
+        }
+
+        let subset = backend.get_state().subset_for_principal(&Principal::User("carol".to_string()));
+        assert_eq!(subset.permissions.len(), 3);
+        let mut roles: Vec<_> = subset.roles.keys().cloned().collect();
+        roles.sort();
+        assert_eq!(roles, vec!["analyst", "lead", "senior_analyst"]);
+
+        let subset = backend.get_state().subset_for_principal(&Principal::Role("senior_analyst".to_string()));
+        assert_eq!(subset.permissions.len(), 2);
+        assert!(!subset.roles.contains_key("lead"));
+        assert!(!subset.role_parents.contains_key("lead"));
+    }
+
+    #[tokio::test]
+    async fn test_access_mode_on_same_table() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        let analyst = Principal::Role("analyst".to_string());
+        let orders = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+
+        // Lake Formation mode: no grant, no access
+        assert_eq!(backend.get_state().access_mode(&orders), AccessMode::LakeFormation);
+        assert!(!backend.check_permissions(&analyst, &orders, &Action::Select).await.unwrap());
+
+        // IAM-only mode: Lake Formation checks are bypassed
+        backend.execute_ddl("SET ACCESS MODE IAM_ONLY ON sales.orders").await.unwrap();
+        assert_eq!(backend.get_state().access_mode(&orders), AccessMode::IamOnly);
+        assert!(backend.check_permissions(&analyst, &orders, &Action::Select).await.unwrap());
+
+        // An explicit DENY still applies in IAM-only mode
+        backend.execute_ddl("DENY SELECT ON sales.orders TO ROLE analyst").await.unwrap();
+        assert!(!backend.check_permissions(&analyst, &orders, &Action::Select).await.unwrap());
+        assert!(backend.check_permissions(&analyst, &orders, &Action::Insert).await.unwrap());
+        let (allowed, reason) = backend.engine.check_permission_with_reason(&analyst, &orders, &Action::Select);
+        assert!(!allowed);
+        assert!(reason.contains("explicit deny"), "{}", reason);
+        assert!(!backend.engine.check_column_permission(&analyst, "sales", "orders", &["id".to_string()], &Action::Select));
+
+        // Back to Lake Formation mode
+        backend.execute_ddl("SET ACCESS MODE LAKE_FORMATION ON sales.orders").await.unwrap();
+        assert!(!backend.check_permissions(&analyst, &orders, &Action::Select).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_flush_writes_snapshot_without_auto_save() {
+        let storage = MemoryStorage::new();
+        let mut backend = EmulatorBackend::with_storage(storage.clone()).await.unwrap();
+        backend.set_auto_save(false);
+
+        for sql in [
+            "CREATE TAG department VALUES ('finance', 'hr')",
+            "GRANT SELECT, INSERT ON sales.orders TO ROLE analyst",
+            "GRANT SELECT ON sales.customers TO ROLE analyst",
+            "REVOKE SELECT, INSERT ON sales.orders FROM ROLE analyst",
+        ] {
+            backend.execute_ddl(sql).await.unwrap();
+        }
+        assert_eq!(storage.save_count(), 0);
+
+        backend.flush().await.unwrap();
+        assert_eq!(storage.save_count(), 1);
+
+        let reloaded = EmulatorBackend::with_storage(storage).await.unwrap();
+        let state = reloaded.get_state();
+        assert!(state.tags.contains_key("department"));
+        assert_eq!(state.permissions.len(), 1);
+        assert_eq!(state.permissions[0].resource, Resource::Table {
+            database: "sales".to_string(),
+            table: "customers".to_string(),
+            columns: None,
+            catalog_id: None,
+        });
+    }
+
+    #[tokio::test]
+    async fn test_plan_sql_previews_without_mutating() {
+        let storage = MemoryStorage::new();
+        let mut backend = EmulatorBackend::with_storage(storage.clone()).await.unwrap();
+        backend.execute_ddl("GRANT SELECT, INSERT ON sales.orders TO ROLE analyst").await.unwrap();
+        backend.execute_ddl("GRANT SELECT ON sales.refunds TO ROLE analyst").await.unwrap();
+        let saves = storage.save_count();
+
+        let diff = backend.plan_sql(
+            "REVOKE SELECT, INSERT ON sales.refunds FROM ROLE analyst;\n\
+             GRANT SELECT ON sales.customers TO ROLE analyst;\n\
+             SHOW PERMISSIONS;"
+        ).await.unwrap();
+
+        let table = |name: &str| Resource::Table {
+            database: "sales".to_string(),
+            table: name.to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].resource, table("customers"));
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].resource, table("refunds"));
+
+        // Nothing was applied or persisted
+        assert_eq!(backend.get_state().permissions.len(), 2);
+        assert_eq!(storage.save_count(), saves);
+        assert!(backend.plan_sql("SHOW ROLES").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_expired_grants_pruned_on_load() {
+        let mut state = EmulatorState::new();
+        for (table, expires_at) in [("orders", Some(1_000)), ("customers", Some(3_000)), ("refunds", None)] {
+            state.permissions.push(Permission {
+                expires_at,
+                ..Permission::new(
+                    Principal::Role("analyst".to_string()),
+                    Resource::Table {
+                        database: "sales".to_string(),
+                        table: table.to_string(),
+                        columns: None,
+                        catalog_id: None,
+                    },
+                    vec![Action::Select],
+                )
+            });
+        }
+        let storage = MemoryStorage::with_state(state);
+
+        // Off by default: nothing is dropped
+        let mut backend = EmulatorBackend::with_storage(storage.clone()).await.unwrap();
+        assert_eq!(backend.get_state().permissions.len(), 3);
+
+        backend.set_clock(Arc::new(FixedClock(2_000)));
+        backend.set_prune_expired_on_load(true);
+        backend.load_state().await.unwrap();
+
+        let tables: Vec<_> = backend.get_state().permissions.iter().map(|p| match &p.resource {
+            Resource::Table { table, .. } => table.as_str(),
+            _ => unreachable!(),
+        }).collect();
+        assert_eq!(tables, vec!["customers", "refunds"]);
+        assert_eq!(storage.stored().unwrap().permissions.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_access_breadth_narrow_vs_broad() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        for sql in [
+            "CREATE ROLE admin",
+            "GRANT SELECT ON sales.orders TO ROLE analyst",
+            "GRANT SELECT ON sales.orders TO ROLE analyst",
+            "GRANT ALL ON DATABASE sales TO ROLE admin",
+            "GRANT SELECT, INSERT ON sales.orders TO ROLE admin",
+            "GRANT DESCRIBE ON DATABASE hr TO USER 'carol@company.com'",
+        ] {
+            backend.execute_ddl(sql).await.unwrap();
+        }
+        backend.state.roles.get_mut("admin").unwrap().insert("carol@company.com".to_string());
+        backend.engine.update_state(&backend.state);
+
+        let breadth = |name: &str| backend.access_breadth(&Principal::Role(name.to_string())).unwrap();
+        assert_eq!(breadth("analyst"), 1);
+        assert_eq!(breadth("admin"), Action::CONCRETE.len() + 2);
+        assert_eq!(breadth("nobody"), 0);
+
+        // Users inherit the breadth of their roles
+        let carol = Principal::User("carol@company.com".to_string());
+        assert_eq!(backend.access_breadth(&carol).unwrap(), Action::CONCRETE.len() + 3);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_all_removes_multi_action_grant() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.execute_ddl("GRANT SELECT, INSERT, DELETE ON sales.orders TO ROLE analyst").await.unwrap();
+        backend.execute_ddl("GRANT SELECT ON sales.customers TO ROLE analyst").await.unwrap();
+
+        backend.execute_ddl("REVOKE ALL ON sales.orders FROM ROLE analyst").await.unwrap();
+
+        let analyst = Principal::Role("analyst".to_string());
+        let table = |name: &str| Resource::Table {
+            database: "sales".to_string(),
+            table: name.to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        for action in [Action::Select, Action::Insert, Action::Delete] {
+            assert!(!backend.check_permissions(&analyst, &table("orders"), &action).await.unwrap());
+        }
+        assert!(backend.check_permissions(&analyst, &table("customers"), &Action::Select).await.unwrap());
+        assert_eq!(backend.get_state().permissions.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_permissions_for_role_previews_drop() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        for sql in [
+            "CREATE ROLE analyst",
+            "GRANT SELECT ON sales.orders TO ROLE analyst",
+            "GRANT DESCRIBE ON DATABASE hr TO ROLE analyst",
+            "GRANT SELECT ON sales.orders TO ROLE analyst_admin",
+            "GRANT SELECT ON sales.orders TO USER 'analyst'",
+        ] {
+            backend.execute_ddl(sql).await.unwrap();
+        }
+
+        let preview = backend.permissions_for_role("analyst").unwrap();
+        assert_eq!(preview.len(), 2);
+        assert!(preview.iter().all(|p| p.principal == Principal::Role("analyst".to_string())));
+        assert!(backend.permissions_for_role("auditor").unwrap().is_empty());
+
+        // The preview is exactly what DROP ROLE removes
+        let before = backend.get_state().permissions.len();
+        backend.execute_ddl("DROP ROLE analyst").await.unwrap();
+        assert_eq!(backend.get_state().permissions.len(), before - preview.len());
+        assert!(backend.permissions_for_role("analyst").unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_filter_in_isolation() {
+        let backend = EmulatorBackend::new(None).await.unwrap();
+        let expression = "region = SESSION_CONTEXT('user_region')";
+        let row = expression::create_sample_row(vec![("region", "west")]);
+
+        let west = expression::create_session_context(vec![("user_region", "west")]);
+        assert!(backend.evaluate_filter(expression, row.clone(), west).unwrap());
+
+        let east = expression::create_session_context(vec![("user_region", "east")]);
+        assert!(!backend.evaluate_filter(expression, row.clone(), east).unwrap());
+
+        // Missing session keys are reported rather than treated as a denial
+        assert!(backend.evaluate_filter(expression, row, HashMap::new()).is_err());
+        assert!(backend.get_state().permissions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_table_location_correlates_with_location_grants() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        for sql in [
+            "REGISTER TABLE sales.orders LOCATION 's3://data-lake/sales/orders'",
+            "GRANT SELECT ON sales.orders TO ROLE analyst",
+            "GRANT DATA_LOCATION_ACCESS ON 's3://data-lake/sales' TO ROLE etl",
+            "GRANT DATA_LOCATION_ACCESS ON 's3://data-lake/hr' TO ROLE hr_etl",
+            r#"REGISTER TABLE "raw-events".clicks LOCATION 's3://data-lake/raw/clicks'"#,
+        ] {
+            backend.execute_ddl(sql).await.unwrap();
+        }
+
+        let state = backend.get_state();
+        assert_eq!(state.location_for_table("sales", "orders").as_deref(), Some("s3://data-lake/sales/orders"));
+        assert_eq!(state.location_for_table("sales", "customers"), None);
+
+        let grants = state.location_grants_for_table("sales", "orders");
+        assert_eq!(grants.len(), 1);
+        assert_eq!(grants[0].principal, Principal::Role("etl".to_string()));
+        assert!(state.location_grants_for_table("sales", "customers").is_empty());
+
+        let sql = storage::StateExporter::to_sql_ddl(state);
+        assert!(sql.contains("REGISTER TABLE sales.orders LOCATION 's3://data-lake/sales/orders';"));
+        assert!(sql.contains(r#"REGISTER TABLE "raw-events".clicks LOCATION 's3://data-lake/raw/clicks';"#));
+        assert_eq!(state.location_for_table("raw-events", "clicks").as_deref(), Some("s3://data-lake/raw/clicks"));
+    }
+
+    #[tokio::test]
+    async fn test_replay_log_reproduces_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_path = dir.path().join("session.log").to_string_lossy().to_string();
+
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.set_replay_log(Some(log_path.clone()));
+        for sql in [
+            "CREATE ROLE analyst",
+            "CREATE TAG region VALUES ('us', 'eu')",
+            "GRANT SELECT, INSERT ON sales.orders TO ROLE analyst -- quarterly reporting",
+            "GRANT DESCRIBE ON DATABASE hr TO USER 'o''brien@company.com'",
+            "REVOKE INSERT ON sales.orders FROM ROLE analyst",
+            "SHOW ROLES",
+        ] {
+            backend.execute_ddl(sql).await.unwrap();
+        }
+        // Failed statements leave state untouched and are not recorded
+        assert!(backend.execute_ddl("GRANT BOGUS ON sales.orders TO ROLE analyst").await.is_err());
+
+        let log = tokio::fs::read_to_string(&log_path).await.unwrap();
+        assert_eq!(log.lines().count(), 6);
+
+        let replayed = replay(&log_path).await.unwrap();
+        let state = backend.get_state();
+        assert_eq!(replayed.permissions, state.permissions);
+        assert_eq!(replayed.roles, state.roles);
+        assert_eq!(replayed.tags, state.tags);
+    }
+
+    #[tokio::test]
+    async fn test_unwritable_replay_log_keeps_statement_result() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_path = dir.path().join("missing").join("session.log").to_string_lossy().to_string();
+
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.set_replay_log(Some(log_path));
+        backend.execute_ddl("GRANT SELECT ON sales.orders TO ROLE analyst").await.unwrap();
+        assert_eq!(backend.state.permissions.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_max_grant_columns() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.set_max_grant_columns(Some(2));
+
+        backend.execute_ddl("GRANT SELECT ON sales.orders (id, amount) TO ROLE analyst").await.unwrap();
+        let err = backend.execute_ddl("GRANT SELECT ON sales.orders (id, amount, region) TO ROLE auditor").await.unwrap_err();
+        assert!(err.to_string().contains("3 columns"));
+        assert_eq!(backend.get_state().permissions.len(), 1);
+
+        // Whole-table grants have no column list to limit
+        backend.execute_ddl("GRANT SELECT ON sales.orders TO ROLE auditor").await.unwrap();
+
+        backend.set_max_grant_columns(None);
+        backend.execute_ddl("GRANT SELECT ON sales.orders (id, amount, region) TO ROLE engineer").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_list_permissions_by_arn_or_name() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.execute_ddl("GRANT SELECT ON sales.orders TO USER 'arn:aws:iam::123456789012:user/alice'").await.unwrap();
+        backend.execute_ddl("GRANT DESCRIBE ON DATABASE sales TO ROLE analyst").await.unwrap();
+
+        let by_name = backend.list_permissions_for_principal(&Principal::User("alice".to_string())).await.unwrap();
+        assert_eq!(by_name.len(), 1);
+        assert!(matches!(by_name[0].resource, Resource::Table { .. }));
+
+        let by_arn = backend
+            .list_permissions_for_principal(&Principal::Role("arn:aws:iam::123456789012:role/analyst".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(by_arn.len(), 1);
+        assert!(matches!(by_arn[0].resource, Resource::Database { .. }));
+
+        // A user and a role with the same name stay distinct
+        let other_kind = backend.list_permissions_for_principal(&Principal::User("analyst".to_string())).await.unwrap();
+        assert!(other_kind.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_row_filter_only_on_select() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+
+        backend.execute_ddl("GRANT SELECT ON sales.orders TO ROLE analyst WHERE region = 'west'").await.unwrap();
+
+        let err = backend.execute_ddl("GRANT SELECT, INSERT ON sales.orders TO ROLE clerk WHERE region = 'west'").await.unwrap_err();
+        assert!(err.to_string().contains("Insert"));
+
+        let err = backend.execute_ddl("GRANT DELETE ON sales.orders TO ROLE janitor WHERE region = 'west'").await.unwrap_err();
+        assert!(err.to_string().contains("Delete"));
+
+        assert_eq!(backend.get_state().permissions.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_create_roles_skips_existing() {
+        let storage = MemoryStorage::new();
+        let mut backend = EmulatorBackend::with_storage(storage.clone()).await.unwrap();
+        backend.execute_ddl("CREATE ROLE analyst").await.unwrap();
+        backend.state.roles.get_mut("analyst").unwrap().insert("alice".to_string());
+
+        let saves_before = storage.save_count();
+        let names = ["analyst", "engineer", "auditor", "engineer"].map(String::from);
+        match backend.create_roles(&names).await.unwrap() {
+            DdlResult::Success { message } => assert_eq!(message, "Created 2 role(s), skipped 2 existing"),
+            other => panic!("Expected success, got {:?}", other),
+        }
+        assert_eq!(storage.save_count(), saves_before + 1);
+
+        // Existing roles keep their members
+        let roles = &backend.get_state().roles;
+        assert_eq!(roles.len(), 3);
+        assert!(roles["analyst"].contains("alice"));
+
+        backend.execute_ddl("CREATE ROLES steward, analyst").await.unwrap();
+        assert_eq!(backend.get_state().roles.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_all_tables_grant() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.execute_ddl("GRANT SELECT ON ALL TABLES IN DATABASE sales TO ROLE analyst").await.unwrap();
+
+        let analyst = Principal::Role("analyst".to_string());
+        let table = |db: &str| Resource::Table {
+            database: db.to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        assert!(backend.check_permissions(&analyst, &table("sales"), &Action::Select).await.unwrap());
+        assert!(!backend.check_permissions(&analyst, &table("hr"), &Action::Select).await.unwrap());
+        assert!(!backend.check_permissions(&analyst, &table("sales"), &Action::Insert).await.unwrap());
+
+        let exported = storage::StateExporter::to_sql_ddl(backend.get_state());
+        assert!(exported.contains("GRANT SELECT ON ALL TABLES IN DATABASE sales TO ROLE analyst"));
+    }
+
+    #[tokio::test]
+    async fn test_alter_role_membership() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.execute_ddl("CREATE ROLE analyst").await.unwrap();
+        backend.execute_ddl("GRANT SELECT ON sales.orders TO ROLE analyst").await.unwrap();
+
+        let alice = Principal::User("arn:aws:iam::123456789012:user/alice".to_string());
+        let resource = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        assert!(!backend.check_permissions(&alice, &resource, &Action::Select).await.unwrap());
+
+        backend.execute_ddl("ALTER ROLE analyst ADD USER 'alice', 'bob'").await.unwrap();
+        assert_eq!(backend.get_state().roles["analyst"].len(), 2);
+        assert!(backend.check_permissions(&alice, &resource, &Action::Select).await.unwrap());
+
+        backend.execute_ddl("ALTER ROLE analyst DROP USER 'alice'").await.unwrap();
+        assert!(!backend.check_permissions(&alice, &resource, &Action::Select).await.unwrap());
+        assert!(backend.get_state().roles["analyst"].contains("bob"));
+
+        assert!(backend.execute_ddl("ALTER ROLE missing ADD USER 'alice'").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_apply_template() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.execute_ddl(
+            "DEFINE TEMPLATE read_access AS (\
+                GRANT SELECT, DESCRIBE ON {resource} TO ROLE {principal}; \
+                GRANT DESCRIBE ON DATABASE sales TO ROLE {principal})"
+        ).await.unwrap();
+
+        backend.execute_ddl("APPLY TEMPLATE read_access ON sales.orders TO ROLE analyst").await.unwrap();
+        backend.execute_ddl("APPLY TEMPLATE read_access ON sales.refunds TO USER 'alice'").await.unwrap();
+
+        let analyst = backend.list_permissions_for_principal(&Principal::Role("analyst".to_string())).await.unwrap();
+        assert_eq!(analyst.len(), 2);
+        assert!(analyst.iter().any(|p| p.actions == vec![Action::Describe, Action::Select]
+            && p.resource == Resource::Table {
+                database: "sales".to_string(),
+                table: "orders".to_string(),
+                columns: None,
+                catalog_id: None,
+            }));
+
+        // The principal type comes from APPLY, not from the template
+        let alice = backend.list_permissions_for_principal(&Principal::User("alice".to_string())).await.unwrap();
+        assert_eq!(alice.len(), 2);
+
+        let err = backend.execute_ddl("APPLY TEMPLATE write_access ON sales.orders TO ROLE analyst").await.unwrap_err();
+        assert!(err.to_string().contains("does not exist"));
+    }
+
+    #[tokio::test]
+    async fn test_destructive_statements_need_confirmation() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.execute_ddl("CREATE ROLE analyst").await.unwrap();
+        backend.execute_ddl("GRANT SELECT ON sales.orders TO ROLE analyst").await.unwrap();
+        backend.execute_ddl("GRANT DESCRIBE ON DATABASE sales TO ROLE analyst").await.unwrap();
+        backend.set_allow_destructive(false);
+
+        let err = backend.execute_ddl("DROP ROLE analyst").await.unwrap_err();
+        assert!(err.to_string().contains("remove 2 grant(s)"));
+        let err = backend.execute_ddl("REVOKE ALL ON sales.orders FROM ROLE analyst").await.unwrap_err();
+        assert!(err.to_string().contains("remove 1 grant(s)"));
+        assert!(backend.get_state().roles.contains_key("analyst"));
+        assert_eq!(backend.get_state().permissions.len(), 2);
+
+        // A targeted revoke is not gated
+        backend.execute_ddl("REVOKE DESCRIBE ON DATABASE sales FROM ROLE analyst").await.unwrap();
+        assert_eq!(backend.get_state().permissions.len(), 1);
+
+        backend.set_allow_destructive(true);
+        backend.execute_ddl("DROP ROLE analyst").await.unwrap();
+        assert!(backend.get_state().permissions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_local_group_members_get_group_grants() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.execute_ddl("DEFINE GROUP analysts AS ('alice', 'arn:aws:iam::123456789012:user/bob')").await.unwrap();
+        backend.execute_ddl("GRANT SELECT ON sales.orders TO GROUP 'analysts'").await.unwrap();
+
+        let orders = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        let check = |backend: &EmulatorBackend, user: &str| {
+            backend.engine.check_permission(&Principal::User(user.to_string()), &orders, &Action::Select)
+        };
+        assert!(check(&backend, "alice"));
+        assert!(check(&backend, "bob"));
+        assert!(check(&backend, "arn:aws:iam::123456789012:user/alice"));
+        assert!(!check(&backend, "carol"));
+
+        let subset = backend.get_state().subset_for_principal(&Principal::User("bob".to_string()));
+        assert_eq!(subset.permissions.len(), 1);
+        assert!(subset.local_groups.contains_key("analysts"));
+
+        // Redefining a group replaces its members
+        backend.execute_ddl("DEFINE GROUP analysts AS ('carol')").await.unwrap();
+        assert!(!check(&backend, "alice"));
+        assert!(check(&backend, "carol"));
+        assert!(storage::StateExporter::to_sql_ddl(backend.get_state()).contains("DEFINE GROUP analysts AS ('carol');"));
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_records_every_statement() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_path = dir.path().join("audit.jsonl").to_string_lossy().to_string();
+
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.set_clock(Arc::new(FixedClock(1_700_000_000)));
+        backend.set_audit_log(Some(log_path.clone()));
+        backend.execute_ddl("GRANT SELECT ON sales.orders TO ROLE analyst").await.unwrap();
+        assert!(backend.execute_ddl("GRANT BOGUS ON sales.orders TO ROLE analyst").await.is_err());
+        assert!(backend.execute_ddl("APPLY TEMPLATE missing ON sales.orders TO ROLE analyst").await.is_err());
+
+        let log = AuditLog::load(&log_path).await.unwrap();
+        assert_eq!(log.entries.len(), 3);
+        assert_eq!(log.entries.iter().map(|e| e.success).collect::<Vec<_>>(), vec![true, false, false]);
+        assert!(log.entries.iter().all(|e| e.timestamp == 1_700_000_000));
+        assert_eq!(log.entries[2].principal, Some(Principal::Role("analyst".to_string())));
+        assert!(log.entries[2].result.contains("does not exist"));
+    }
+
+    #[tokio::test]
+    async fn test_unwritable_audit_log_keeps_statement_result() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_path = dir.path().join("missing").join("audit.jsonl").to_string_lossy().to_string();
+
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.set_audit_log(Some(log_path));
+        backend.execute_ddl("GRANT SELECT ON sales.orders TO ROLE analyst").await.unwrap();
+        assert_eq!(backend.state.permissions.len(), 1);
+
+        let err = backend.execute_ddl("GRANT BOGUS ON sales.orders TO ROLE analyst").await.unwrap_err();
+        assert!(!err.to_string().contains("No such file"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_list_permissions_by_table_name() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        for sql in [
+            "GRANT SELECT ON sales.orders TO ROLE analyst",
+            "GRANT SELECT ON archive.orders (id, amount) TO ROLE auditor",
+            "GRANT SELECT ON sales.customers TO ROLE analyst",
+            "GRANT DESCRIBE ON DATABASE orders TO ROLE analyst",
+        ] {
+            backend.execute_ddl(sql).await.unwrap();
+        }
+
+        let databases: Vec<String> = backend.list_permissions_by_table_name("orders")
+            .unwrap()
+            .into_iter()
+            .map(|p| match p.resource {
+                Resource::Table { database, .. } => database,
+                other => panic!("Expected a table grant, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(databases, vec!["sales", "archive"]);
+        assert!(backend.list_permissions_by_table_name("refunds").unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_incremental_engine_matches_full_rebuild() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        for sql in [
+            "CREATE ROLES analyst, engineer",
+            "GRANT SELECT, INSERT ON sales.orders TO ROLE analyst",
+            "GRANT DESCRIBE ON DATABASE hr TO ROLE engineer",
+            "GRANT SELECT ON sales.orders TO ROLE analyst WHERE region = SESSION_CONTEXT('user_region')",
+            "GRANT SELECT ON sales.refunds TO USER 'alice'",
+            "REVOKE SELECT ON sales.refunds FROM USER 'alice'",
+            "CREATE ROLE steward",
+            "GRANT ALL ON DATABASE finance TO ROLE steward",
+            "DROP ROLE engineer",
+        ] {
+            backend.execute_ddl(sql).await.unwrap();
+        }
+        backend.engine.add_user_to_role("bob".to_string(), "analyst".to_string()).unwrap();
+        backend.state.roles.get_mut("analyst").unwrap().insert("bob".to_string());
+        backend.set_session_context(HashMap::from([("user_region".to_string(), "west".to_string())])).await.unwrap();
+
+        let mut rebuilt = EmulatorEngine::new();
+        rebuilt.update_state(&backend.state);
+
+        let table = |database: &str, table: &str| Resource::Table {
+            database: database.to_string(),
+            table: table.to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        let principals = [
+            Principal::Role("analyst".to_string()),
+            Principal::Role("engineer".to_string()),
+            Principal::Role("steward".to_string()),
+            Principal::User("alice".to_string()),
+            Principal::User("bob".to_string()),
+        ];
+        let resources = [
+            table("sales", "orders"),
+            table("sales", "refunds"),
+            table("hr", "employees"),
+            Resource::Database { name: "finance".to_string(), catalog_id: None },
+        ];
+        for principal in &principals {
+            assert_eq!(
+                backend.engine.get_effective_permissions(principal),
+                rebuilt.get_effective_permissions(principal),
+                "{:?}", principal,
+            );
+            for resource in &resources {
+                for action in [Action::Select, Action::Insert, Action::Describe] {
+                    assert_eq!(
+                        backend.engine.check_permission(principal, resource, &action),
+                        rebuilt.check_permission(principal, resource, &action),
+                        "{:?} {:?} {:?}", principal, resource, action,
+                    );
+                }
+            }
+        }
+        assert!(backend.engine.check_permission(&Principal::User("bob".to_string()), &table("sales", "orders"), &Action::Select));
+    }
+
+    #[tokio::test]
+    async fn test_check_after_proposed_ddl() {
+        let mut backend = EmulatorBackend::new(None).await.unwrap();
+        backend.execute_ddl("CREATE ROLE analyst").await.unwrap();
+        backend.execute_ddl("GRANT SELECT ON sales.orders TO ROLE analyst").await.unwrap();
+
+        let analyst = Principal::Role("analyst".to_string());
+        let orders = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        let refunds = Resource::Table {
+            database: "sales".to_string(),
+            table: "refunds".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+
+        assert!(backend.check_after(&[], &analyst, &orders, &Action::Select).await.unwrap());
+        let revoke = ["REVOKE SELECT ON sales.orders FROM ROLE analyst"];
+        assert!(!backend.check_after(&revoke, &analyst, &orders, &Action::Select).await.unwrap());
+        let regrant = ["REVOKE SELECT ON sales.orders FROM ROLE analyst; GRANT SELECT ON DATABASE sales TO ROLE analyst"];
+        assert!(backend.check_after(&regrant, &analyst, &orders, &Action::Select).await.unwrap());
+        assert!(backend.check_after(&regrant, &analyst, &refunds, &Action::Select).await.unwrap());
+
+        // The real state is untouched
+        assert!(backend.check_permissions(&analyst, &orders, &Action::Select).await.unwrap());
+        assert!(!backend.check_permissions(&analyst, &refunds, &Action::Select).await.unwrap());
+        assert_eq!(backend.get_state().permissions.len(), 1);
+
+        assert!(backend.check_after(&["GRANT NOTHING"], &analyst, &orders, &Action::Select).await.is_err());
+    }
+}
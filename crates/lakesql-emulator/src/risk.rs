@@ -0,0 +1,165 @@
+//! Policy lint for grants that are wider than they probably need to be
+
+use crate::EmulatorState;
+use lakesql_core::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How urgently a finding deserves a look
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum RiskSeverity {
+    Low,
+    Medium,
+    High,
+}
+
+/// Kind of over-permission a grant exhibits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RiskKind {
+    /// Granted to everyone (PUBLIC or IAM_ALLOWED_PRINCIPALS)
+    PublicGrant,
+    /// A whole database granted to a group, external account or tag expression
+    BroadDatabaseGrant,
+    /// Grants ALL actions
+    AllActions,
+    /// Lets the grantee pass actions on (WITH GRANT OPTION)
+    GrantOption,
+}
+
+impl RiskKind {
+    pub fn severity(&self) -> RiskSeverity {
+        match self {
+            RiskKind::PublicGrant | RiskKind::BroadDatabaseGrant => RiskSeverity::High,
+            RiskKind::AllActions => RiskSeverity::Medium,
+            RiskKind::GrantOption => RiskSeverity::Low,
+        }
+    }
+}
+
+/// A risky grant found by `EmulatorState::risk_report`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RiskFinding {
+    pub permission_index: usize,
+    pub kind: RiskKind,
+    pub severity: RiskSeverity,
+}
+
+impl fmt::Display for RiskFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self.kind {
+            RiskKind::PublicGrant => "grants to everyone",
+            RiskKind::BroadDatabaseGrant => "grants a whole database to a broad principal",
+            RiskKind::AllActions => "grants ALL actions",
+            RiskKind::GrantOption => "allows re-granting (WITH GRANT OPTION)",
+        };
+        write!(f, "[{:?}] permission {} {}", self.severity, self.permission_index, description)
+    }
+}
+
+impl EmulatorState {
+    /// Flag grants to PUBLIC, database grants to broad principals, ALL grants
+    /// and grants WITH GRANT OPTION, most severe first. Denies are never flagged.
+    pub fn risk_report(&self) -> Vec<RiskFinding> {
+        let mut findings = Vec::new();
+
+        for (index, permission) in self.permissions.iter().enumerate() {
+            // Denies only take access away
+            if !permission.effect.is_allow() {
+                continue;
+            }
+            let mut kinds = Vec::new();
+            if is_public(&permission.principal) {
+                kinds.push(RiskKind::PublicGrant);
+            } else if is_broad(&permission.principal)
+                && matches!(permission.resource, Resource::Database { .. })
+            {
+                kinds.push(RiskKind::BroadDatabaseGrant);
+            }
+            if permission.actions.contains(&Action::All) {
+                kinds.push(RiskKind::AllActions);
+            }
+            if permission.grant_option() {
+                kinds.push(RiskKind::GrantOption);
+            }
+
+            findings.extend(kinds.into_iter().map(|kind| RiskFinding {
+                permission_index: index,
+                kind,
+                severity: kind.severity(),
+            }));
+        }
+
+        findings.sort_by(|a, b| {
+            b.severity.cmp(&a.severity).then(a.permission_index.cmp(&b.permission_index))
+        });
+        findings
+    }
+}
+
+/// Lake Formation's stand-ins for "every principal"
+fn is_public(principal: &Principal) -> bool {
+    match principal {
+        Principal::Role(name) | Principal::SamlGroup(name) => {
+            name.eq_ignore_ascii_case("PUBLIC") || name.eq_ignore_ascii_case("IAM_ALLOWED_PRINCIPALS")
+        },
+        _ => false,
+    }
+}
+
+/// Principals that stand for an open-ended set of identities
+fn is_broad(principal: &Principal) -> bool {
+    matches!(
+        principal,
+        Principal::SamlGroup(_) | Principal::ExternalAccount(_) | Principal::TaggedPrincipal { .. }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(state: &EmulatorState, index: usize) -> Vec<RiskKind> {
+        state.risk_report()
+            .into_iter()
+            .filter(|f| f.permission_index == index)
+            .map(|f| f.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_risk_categories() {
+        let sales = Resource::Database { name: "sales".to_string(), catalog_id: None };
+        let orders = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+
+        let mut state = EmulatorState::new();
+        // 0: narrow and harmless
+        state.permissions.push(Permission::new(Principal::Role("analyst".to_string()), orders.clone(), vec![Action::Select]));
+        // 1: public
+        state.permissions.push(Permission::new(Principal::Role("public".to_string()), orders.clone(), vec![Action::Select]));
+        // 2: database to a group
+        state.permissions.push(Permission::new(Principal::SamlGroup("org:everyone".to_string()), sales.clone(), vec![Action::Describe]));
+        // 3: ALL actions
+        state.permissions.push(Permission::new(Principal::Role("admin".to_string()), orders.clone(), vec![Action::All]));
+        // 4: grant option
+        let mut grantable = Permission::new(Principal::Role("steward".to_string()), orders.clone(), vec![Action::Select]);
+        grantable.grantable_actions.insert(Action::Select);
+        state.permissions.push(grantable);
+        // 5: database to a single role is not broad
+        state.permissions.push(Permission::new(Principal::Role("dba".to_string()), sales, vec![Action::Describe]));
+
+        assert!(kinds(&state, 0).is_empty());
+        assert_eq!(kinds(&state, 1), vec![RiskKind::PublicGrant]);
+        assert_eq!(kinds(&state, 2), vec![RiskKind::BroadDatabaseGrant]);
+        assert_eq!(kinds(&state, 3), vec![RiskKind::AllActions]);
+        assert_eq!(kinds(&state, 4), vec![RiskKind::GrantOption]);
+        assert!(kinds(&state, 5).is_empty());
+
+        let severities: Vec<_> = state.risk_report().iter().map(|f| f.severity).collect();
+        assert_eq!(severities, vec![RiskSeverity::High, RiskSeverity::High, RiskSeverity::Medium, RiskSeverity::Low]);
+    }
+}
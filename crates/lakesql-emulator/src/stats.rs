@@ -0,0 +1,138 @@
+//! Grouped summaries of the permission list, for status output
+
+use crate::EmulatorState;
+use anyhow::{anyhow, Result};
+use lakesql_core::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// What to group permissions by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GroupBy {
+    /// Resource type: databases, tables, views, locations, tagged resources, LF-Tags
+    Resource,
+    /// Grantee, with IAM ARNs folded into their names
+    Principal,
+    /// Each granted action; a grant with several actions appears in each group
+    Action,
+}
+
+impl FromStr for GroupBy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "resource" => Ok(GroupBy::Resource),
+            "principal" => Ok(GroupBy::Principal),
+            "action" => Ok(GroupBy::Action),
+            _ => Err(anyhow!("Unknown grouping '{}', expected resource, principal or action", s)),
+        }
+    }
+}
+
+/// Permissions sharing one group key, as returned by `EmulatorState::group_permissions`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PermissionGroup {
+    pub key: String,
+    pub count: usize,
+    pub permissions: Vec<Permission>,
+}
+
+impl EmulatorState {
+    /// Group the permissions by resource type, principal or action, ordered by key
+    pub fn group_permissions(&self, group_by: GroupBy) -> Vec<PermissionGroup> {
+        let mut groups: BTreeMap<String, Vec<Permission>> = BTreeMap::new();
+
+        for permission in &self.permissions {
+            let keys = match group_by {
+                GroupBy::Resource => vec![permission.resource.kind().label().to_string()],
+                GroupBy::Principal => vec![principal_label(&permission.principal.normalize())],
+                GroupBy::Action => permission.actions
+                    .iter()
+                    .map(|a| a.to_ddl_keyword().to_string())
+                    .collect(),
+            };
+            for key in keys {
+                groups.entry(key).or_default().push(permission.clone());
+            }
+        }
+
+        groups
+            .into_iter()
+            .map(|(key, permissions)| PermissionGroup { key, count: permissions.len(), permissions })
+            .collect()
+    }
+}
+
+/// DDL-style label for a principal, e.g. `ROLE analyst`
+fn principal_label(principal: &Principal) -> String {
+    match principal {
+        Principal::Role(name) => format!("ROLE {}", name),
+        Principal::User(name) => format!("USER {}", name),
+        Principal::SamlGroup(name) => format!("GROUP {}", name),
+        Principal::ExternalAccount(account) => format!("EXTERNAL_ACCOUNT {}", account),
+        Principal::TaggedPrincipal { tag_key, tag_values } => {
+            format!("TAGGED {}={}", tag_key, tag_values.join(","))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state() -> EmulatorState {
+        let table = |name: &str| Resource::Table {
+            database: "sales".to_string(),
+            table: name.to_string(),
+            columns: None,
+            catalog_id: None,
+        };
+        EmulatorState {
+            permissions: vec![
+                Permission::new(Principal::Role("analyst".to_string()), table("orders"), vec![Action::Select]),
+                Permission::new(
+                    Principal::Role("arn:aws:iam::123456789012:role/analyst".to_string()),
+                    Resource::Database { name: "sales".to_string(), catalog_id: None },
+                    vec![Action::Describe],
+                ),
+                Permission::new(Principal::User("alice".to_string()), table("refunds"), vec![Action::Select, Action::Insert]),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn summary(groups: &[PermissionGroup]) -> Vec<(&str, usize)> {
+        groups.iter().map(|g| (g.key.as_str(), g.count)).collect()
+    }
+
+    #[test]
+    fn test_group_permissions() {
+        let state = state();
+
+        assert_eq!(summary(&state.group_permissions(GroupBy::Resource)), vec![("DATABASE", 1), ("TABLE", 2)]);
+        assert_eq!(summary(&state.group_permissions(GroupBy::Principal)), vec![("ROLE analyst", 2), ("USER alice", 1)]);
+        assert_eq!(
+            summary(&state.group_permissions(GroupBy::Action)),
+            vec![("DESCRIBE", 1), ("INSERT", 1), ("SELECT", 2)],
+        );
+        assert!("Action".parse::<GroupBy>().is_ok());
+        assert!("tag".parse::<GroupBy>().is_err());
+    }
+
+    #[test]
+    fn test_grouped_json_structure() {
+        let groups = state().group_permissions(GroupBy::Resource);
+        let json = serde_json::to_value(&groups).unwrap();
+
+        let groups = json.as_array().unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0]["key"], "DATABASE");
+        assert_eq!(groups[0]["count"], 1);
+        assert_eq!(groups[1]["key"], "TABLE");
+        assert_eq!(groups[1]["count"], 2);
+        assert_eq!(groups[1]["permissions"].as_array().unwrap().len(), 2);
+        assert_eq!(groups[1]["permissions"][0]["resource"]["Table"]["table"], "orders");
+    }
+}
@@ -2,8 +2,20 @@
 
 use crate::EmulatorState;
 use anyhow::Result;
+use async_trait::async_trait;
 // serde traits already available through EmulatorState
 use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Where the emulator persists its state
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Load the stored state, or an empty state if nothing has been saved
+    async fn load(&self) -> Result<EmulatorState>;
+
+    /// Replace the stored state
+    async fn save(&self, state: &EmulatorState) -> Result<()>;
+}
 
 /// Storage backend for emulator state
 #[derive(Debug)]
@@ -16,19 +28,28 @@ impl FileStorage {
         Self { file_path }
     }
 
+    /// Check if storage file exists
+    pub fn exists(&self) -> bool {
+        Path::new(&self.file_path).exists()
+    }
+}
+
+#[async_trait]
+impl Storage for FileStorage {
     /// Load state from file
-    pub async fn load(&self) -> Result<EmulatorState> {
+    async fn load(&self) -> Result<EmulatorState> {
         if !Path::new(&self.file_path).exists() {
             return Ok(EmulatorState::new());
         }
 
         let content = tokio::fs::read_to_string(&self.file_path).await?;
         let state: EmulatorState = serde_json::from_str(&content)?;
+        tracing::debug!(path = %self.file_path, "loaded emulator state");
         Ok(state)
     }
 
     /// Save state to file
-    pub async fn save(&self, state: &EmulatorState) -> Result<()> {
+    async fn save(&self, state: &EmulatorState) -> Result<()> {
         let content = serde_json::to_string_pretty(state)?;
         
         // Create parent directory if it doesn't exist
@@ -37,12 +58,58 @@ impl FileStorage {
         }
         
         tokio::fs::write(&self.file_path, content).await?;
+        tracing::debug!(path = %self.file_path, "saved emulator state");
         Ok(())
     }
+}
 
-    /// Check if storage file exists
-    pub fn exists(&self) -> bool {
-        Path::new(&self.file_path).exists()
+/// In-memory storage for tests. Clones share the same stored state, so a
+/// test can keep a handle after passing one to the backend.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage {
+    inner: Arc<Mutex<MemoryStorageInner>>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryStorageInner {
+    state: Option<EmulatorState>,
+    saves: usize,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start from an existing state, as if it had been saved earlier
+    pub fn with_state(state: EmulatorState) -> Self {
+        let storage = Self::default();
+        storage.inner.lock().unwrap().state = Some(state);
+        storage
+    }
+
+    /// The last saved state, if any
+    pub fn stored(&self) -> Option<EmulatorState> {
+        self.inner.lock().unwrap().state.clone()
+    }
+
+    /// Number of times state has been saved
+    pub fn save_count(&self) -> usize {
+        self.inner.lock().unwrap().saves
+    }
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn load(&self) -> Result<EmulatorState> {
+        Ok(self.stored().unwrap_or_default())
+    }
+
+    async fn save(&self, state: &EmulatorState) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.state = Some(state.clone());
+        inner.saves += 1;
+        Ok(())
     }
 }
 
@@ -57,8 +124,28 @@ impl StateExporter {
         sql.push_str("-- Generated DDL statements to recreate this state\n\n");
 
         // Export roles
-        for role_name in state.roles.keys() {
+        for (role_name, members) in &state.roles {
             sql.push_str(&format!("CREATE ROLE {};\n", role_name));
+            if !members.is_empty() {
+                let mut members: Vec<_> = members.iter().collect();
+                members.sort();
+                let users_str = members
+                    .iter()
+                    .map(|m| format!("'{}'", m.replace('\'', "''")))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                sql.push_str(&format!("ALTER ROLE {} ADD USER {};\n", role_name, users_str));
+            }
+        }
+
+        // Export role inheritance once every role exists
+        let mut inherited: Vec<(&String, &String)> = state.role_parents
+            .iter()
+            .flat_map(|(member, roles)| roles.iter().map(move |role| (role, member)))
+            .collect();
+        inherited.sort();
+        for (role, member) in inherited {
+            sql.push_str(&format!("GRANT ROLE {} TO ROLE {};\n", role, member));
         }
         sql.push_str("\n");
 
@@ -73,66 +160,208 @@ impl StateExporter {
         }
         sql.push_str("\n");
 
-        // Export permissions as GRANT statements
+        // Export registered data locations
+        let mut locations: Vec<_> = state.registered_locations.iter().collect();
+        locations.sort();
+        for path in locations {
+            sql.push_str(&format!("REGISTER LOCATION '{}';\n", path.replace('\'', "''")));
+        }
+        if !state.registered_locations.is_empty() {
+            sql.push('\n');
+        }
+
+        // Export table locations
+        let mut table_locations: Vec<_> = state.table_locations.iter().collect();
+        table_locations.sort();
+        for (table, path) in &table_locations {
+            let table = match table.split_once('.') {
+                Some((database, table)) => format!("{}.{}", sql_name(database), sql_name(table)),
+                None => table.to_string(),
+            };
+            sql.push_str(&format!("REGISTER TABLE {} LOCATION '{}';\n", table, path.replace('\'', "''")));
+        }
+        if !table_locations.is_empty() {
+            sql.push('\n');
+        }
+
+        // Export IAM-only access modes
+        let mut iam_only: Vec<_> = state.iam_only_resources.iter().map(resource_sql).collect();
+        iam_only.sort();
+        for resource in &iam_only {
+            sql.push_str(&format!("SET ACCESS MODE IAM_ONLY ON {};\n", resource));
+        }
+        if !iam_only.is_empty() {
+            sql.push('\n');
+        }
+
+        // Export local groups
+        let mut groups: Vec<_> = state.local_groups.iter().collect();
+        groups.sort_by_key(|(name, _)| *name);
+        for (name, members) in &groups {
+            let mut members: Vec<_> = members.iter().map(|m| format!("'{}'", m.replace('\'', "''"))).collect();
+            members.sort();
+            sql.push_str(&format!("DEFINE GROUP {} AS ({});\n", name, members.join(", ")));
+        }
+        if !groups.is_empty() {
+            sql.push('\n');
+        }
+
+        // Export grant templates
+        let mut templates: Vec<_> = state.templates.iter().collect();
+        templates.sort();
+        for (name, body) in &templates {
+            sql.push_str(&format!("DEFINE TEMPLATE {} AS ({});\n", name, body));
+        }
+        if !templates.is_empty() {
+            sql.push('\n');
+        }
+
+        // Export permissions as GRANT statements, and denies as DENY
         for permission in &state.permissions {
+            // A trailing WITH GRANT OPTION covers every action; otherwise
+            // mark the grantable ones individually
+            let all_grantable = permission.actions
+                .iter()
+                .all(|a| permission.grantable_actions.contains(a));
             let actions_str = permission.actions
                 .iter()
-                .map(|a| format!("{:?}", a).to_uppercase())
+                .map(|a| {
+                    let name = a.to_ddl_keyword();
+                    if !all_grantable && permission.grantable_actions.contains(a) {
+                        format!("{} WITH GRANT OPTION", name)
+                    } else {
+                        name.to_string()
+                    }
+                })
                 .collect::<Vec<_>>()
                 .join(", ");
 
             let principal_str = match &permission.principal {
                 lakesql_core::Principal::Role(name) => format!("ROLE {}", name),
-                lakesql_core::Principal::User(name) => format!("USER '{}'", name),
-                lakesql_core::Principal::SamlGroup(name) => format!("GROUP '{}'", name),
-                lakesql_core::Principal::ExternalAccount(account) => format!("EXTERNAL_ACCOUNT '{}'", account),
-                lakesql_core::Principal::TaggedPrincipal { tag_key, tag_values } => {
-                    format!("TAGGED {}='{}'", tag_key, tag_values.join(","))
-                },
-            };
-
-            let resource_str = match &permission.resource {
-                lakesql_core::Resource::Database { name } => format!("DATABASE {}", name),
-                lakesql_core::Resource::Table { database, table, columns } => {
-                    if let Some(cols) = columns {
-                        let cols_str = cols.join(", ");
-                        format!("{}.{}({})", database, table, cols_str)
-                    } else {
-                        format!("{}.{}", database, table)
-                    }
+                lakesql_core::Principal::User(name) => format!("USER '{}'", name.replace('\'', "''")),
+                lakesql_core::Principal::SamlGroup(name) => format!("GROUP '{}'", name.replace('\'', "''")),
+                lakesql_core::Principal::ExternalAccount(account) => {
+                    format!("EXTERNAL_ACCOUNT '{}'", account.replace('\'', "''"))
                 },
-                lakesql_core::Resource::DataLocation { path } => format!("'{}'", path),
-                lakesql_core::Resource::TaggedResource { tag_conditions } => {
-                    let conditions_str = tag_conditions
+                lakesql_core::Principal::TaggedPrincipal { tag_key, tag_values } => {
+                    let values = tag_values
                         .iter()
-                        .map(|(k, vs)| format!("{}='{}'", k, vs.join(",")))
+                        .map(|v| format!("'{}'", v.replace('\'', "''")))
                         .collect::<Vec<_>>()
-                        .join(" AND ");
-                    format!("RESOURCES TAGGED {}", conditions_str)
+                        .join(",");
+                    format!("TAGGED {}={}", tag_key, values)
                 },
             };
 
-            let grant_option_str = if permission.grant_option {
+            let resource_str = resource_sql(&permission.resource);
+
+            if permission.effect == lakesql_core::Effect::Deny {
+                sql.push_str(&format!("DENY {} ON {} TO {};\n", actions_str, resource_str, principal_str));
+                continue;
+            }
+
+            let grant_option_str = if permission.grant_option() && all_grantable {
                 " WITH GRANT OPTION"
             } else {
                 ""
             };
 
             let row_filter_str = if let Some(filter) = &permission.row_filter {
-                format!(" WHERE {}", filter.expression)
+                // Parsed row filters keep their WHERE keyword
+                format!(" WHERE {}", strip_keyword(&filter.expression, "WHERE"))
+            } else {
+                String::new()
+            };
+
+            let condition_str = if let Some(condition) = &permission.principal_condition {
+                format!(" WHEN {}", condition.expression)
+            } else {
+                String::new()
+            };
+
+            let comment_str = if let Some(comment) = &permission.comment {
+                format!(" -- {}", comment.replace('\n', " "))
             } else {
                 String::new()
             };
 
             sql.push_str(&format!(
-                "GRANT {} ON {} TO {}{}{};\\n",
-                actions_str, resource_str, principal_str, grant_option_str, row_filter_str
+                "GRANT {} ON {} TO {}{}{}{};{}\n",
+                actions_str, resource_str, principal_str, grant_option_str, row_filter_str, condition_str, comment_str
             ));
         }
 
         sql
     }
 
+    /// Export permissions as CSV, one row per (principal, resource, action)
+    pub fn to_csv(state: &EmulatorState) -> String {
+        let mut csv = String::from(
+            "principal_type,principal,resource_type,resource,columns,action,grant_option,row_filter,effect\n",
+        );
+
+        for permission in &state.permissions {
+            let (principal_type, principal) = match &permission.principal {
+                lakesql_core::Principal::Role(name) => ("ROLE", name.clone()),
+                lakesql_core::Principal::User(name) => ("USER", name.clone()),
+                lakesql_core::Principal::SamlGroup(name) => ("GROUP", name.clone()),
+                lakesql_core::Principal::ExternalAccount(account) => ("EXTERNAL_ACCOUNT", account.clone()),
+                lakesql_core::Principal::TaggedPrincipal { tag_key, tag_values } => {
+                    ("TAGGED", format!("{}={}", tag_key, tag_values.join(",")))
+                },
+            };
+
+            let (resource_type, resource, columns) = match &permission.resource {
+                lakesql_core::Resource::Database { name, .. } => ("DATABASE", name.clone(), String::new()),
+                lakesql_core::Resource::Table { database, table, columns, .. } => (
+                    "TABLE",
+                    format!("{}.{}", database, table),
+                    columns.as_ref().map(|c| c.join(";")).unwrap_or_default(),
+                ),
+                lakesql_core::Resource::AllTables { database, .. } => {
+                    ("TABLE", format!("{}.*", database), String::new())
+                },
+                lakesql_core::Resource::View { database, view } => {
+                    ("VIEW", format!("{}.{}", database, view), String::new())
+                },
+                lakesql_core::Resource::DataLocation { path } => ("DATA_LOCATION", path.clone(), String::new()),
+                lakesql_core::Resource::TaggedResource { tag_conditions } => {
+                    let conditions = tag_conditions
+                        .iter()
+                        .map(|(k, vs)| format!("{}={}", k, vs.join(",")))
+                        .collect::<Vec<_>>()
+                        .join(" AND ");
+                    ("TAGGED_RESOURCE", conditions, String::new())
+                },
+                lakesql_core::Resource::LfTag { key } => ("LF_TAG", key.clone(), String::new()),
+            };
+
+            let row_filter = permission.row_filter
+                .as_ref()
+                .map(|f| f.expression.clone())
+                .unwrap_or_default();
+
+            for action in &permission.actions {
+                let fields = [
+                    principal_type.to_string(),
+                    principal.clone(),
+                    resource_type.to_string(),
+                    resource.clone(),
+                    columns.clone(),
+                    action.to_ddl_keyword().to_string(),
+                    permission.is_grantable(action).to_string(),
+                    row_filter.clone(),
+                    format!("{:?}", permission.effect).to_uppercase(),
+                ];
+                let row = fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(",");
+                csv.push_str(&row);
+                csv.push('\n');
+            }
+        }
+
+        csv
+    }
+
     /// Export state as a human-readable summary
     pub fn to_summary(state: &EmulatorState) -> String {
         let mut summary = String::new();
@@ -176,6 +405,74 @@ impl StateExporter {
     }
 }
 
+/// Drop a leading keyword (matched case-insensitively) from an expression
+fn strip_keyword<'a>(expression: &'a str, keyword: &str) -> &'a str {
+    let expression = expression.trim_start();
+    match expression.get(..keyword.len()) {
+        Some(prefix)
+            if prefix.eq_ignore_ascii_case(keyword)
+                && expression[keyword.len()..].starts_with(char::is_whitespace) =>
+        {
+            expression[keyword.len()..].trim_start()
+        },
+        _ => expression,
+    }
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Quote a database, table or column name unless it is a bare identifier
+fn sql_name(name: &str) -> String {
+    let mut chars = name.chars();
+    let bare = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if bare {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+}
+
+/// DDL form of a resource, as accepted by the parser
+fn resource_sql(resource: &lakesql_core::Resource) -> String {
+    match resource {
+        lakesql_core::Resource::Database { name, .. } => format!("DATABASE {}", sql_name(name)),
+        lakesql_core::Resource::Table { database, table, columns, .. } => {
+            if let Some(cols) = columns {
+                let cols_str = cols.iter().map(|c| sql_name(c)).collect::<Vec<_>>().join(", ");
+                format!("{}.{}({})", sql_name(database), sql_name(table), cols_str)
+            } else {
+                format!("{}.{}", sql_name(database), sql_name(table))
+            }
+        },
+        lakesql_core::Resource::AllTables { database, .. } => format!("ALL TABLES IN DATABASE {}", sql_name(database)),
+        lakesql_core::Resource::View { database, view } => format!("VIEW {}.{}", sql_name(database), sql_name(view)),
+        lakesql_core::Resource::DataLocation { path } => format!("'{}'", path.replace('\'', "''")),
+        lakesql_core::Resource::TaggedResource { tag_conditions } => {
+            let conditions_str = tag_conditions
+                .iter()
+                .map(|(k, vs)| {
+                    let values: Vec<_> = vs.iter().map(|v| format!("'{}'", v.replace('\'', "''"))).collect();
+                    match values.as_slice() {
+                        [value] => format!("{}={}", k, value),
+                        _ => format!("{}=({})", k, values.join(", ")),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" AND ");
+            format!("RESOURCES TAGGED {}", conditions_str)
+        },
+        lakesql_core::Resource::LfTag { key } => format!("LF-TAG {}", key),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -197,6 +494,20 @@ mod tests {
         assert!(loaded_state.roles.contains_key("test_role"));
     }
 
+    #[tokio::test]
+    async fn test_memory_storage() {
+        let storage = MemoryStorage::new();
+        assert!(storage.load().await.unwrap().roles.is_empty());
+
+        let mut state = EmulatorState::new();
+        state.roles.insert("test_role".to_string(), std::collections::HashSet::new());
+        storage.clone().save(&state).await.unwrap();
+
+        // Clones share the stored state
+        assert!(storage.load().await.unwrap().roles.contains_key("test_role"));
+        assert_eq!(storage.save_count(), 1);
+    }
+
     #[test]
     fn test_sql_export() {
         let mut state = EmulatorState::new();
@@ -205,4 +516,213 @@ mod tests {
         let sql = StateExporter::to_sql_ddl(&state);
         assert!(sql.contains("CREATE ROLE analyst"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_sql_export_keeps_role_inheritance() {
+        let mut state = EmulatorState::new();
+        for role in ["analyst", "senior_analyst"] {
+            state.roles.insert(role.to_string(), std::collections::HashSet::new());
+        }
+        state.roles.get_mut("senior_analyst").unwrap().insert("alice".to_string());
+        state.role_parents.insert(
+            "senior_analyst".to_string(),
+            std::collections::HashSet::from(["analyst".to_string()]),
+        );
+
+        let sql = StateExporter::to_sql_ddl(&state);
+        assert!(!sql.contains("ADD USER 'analyst'") && !sql.contains("ADD USER 'senior_analyst'"));
+        let line = sql.lines().find(|line| line.starts_with("GRANT ROLE")).unwrap();
+        assert_eq!(line, "GRANT ROLE analyst TO ROLE senior_analyst;");
+        assert_eq!(
+            lakesql_parser::parse_ddl(line).unwrap(),
+            lakesql_parser::DdlStatement::GrantRole {
+                role: "analyst".to_string(),
+                member: "senior_analyst".to_string(),
+            },
+        );
+        // Every role is created before the first GRANT ROLE
+        let last_create = sql.rfind("CREATE ROLE").unwrap();
+        assert!(last_create < sql.find("GRANT ROLE").unwrap());
+    }
+
+    #[test]
+    fn test_sql_export_preserves_grant_comment() {
+        let sql = "GRANT SELECT ON sales.orders TO ROLE analyst -- needed for the weekly dashboard";
+        let permission = lakesql_parser::parse_ddl(sql).unwrap().to_permission().unwrap();
+
+        let mut state = EmulatorState::new();
+        state.permissions.push(permission.clone());
+
+        let exported = StateExporter::to_sql_ddl(&state);
+        let grant_line = exported
+            .lines()
+            .find(|line| line.starts_with("GRANT"))
+            .expect("Expected a GRANT line in export");
+        assert!(grant_line.ends_with("-- needed for the weekly dashboard"));
+
+        let reparsed = lakesql_parser::parse_ddl(grant_line).unwrap().to_permission().unwrap();
+        assert_eq!(reparsed.comment, permission.comment);
+    }
+
+    #[test]
+    fn test_sql_export_quotes_special_names() {
+        let sql = r#"GRANT SELECT ON "raw-events"."2024_orders" ("unit price", id) TO ROLE analyst"#;
+        let permission = lakesql_parser::parse_ddl(sql).unwrap().to_permission().unwrap();
+
+        let mut state = EmulatorState::new();
+        state.permissions.push(permission.clone());
+
+        let exported = StateExporter::to_sql_ddl(&state);
+        let grant_line = exported.lines().find(|line| line.starts_with("GRANT")).unwrap();
+        assert!(grant_line.contains(r#""raw-events"."2024_orders"("unit price", id)"#));
+
+        let reparsed = lakesql_parser::parse_ddl(grant_line).unwrap().to_permission().unwrap();
+        assert_eq!(reparsed.resource, permission.resource);
+    }
+
+    #[test]
+    fn test_sql_export_lines_parse() {
+        let script = "\
+            CREATE ROLE analyst;
+            ALTER ROLE analyst ADD USER 'alice';
+            CREATE TAG department VALUES ('finance', 'hr');
+            REGISTER LOCATION 's3://lake/sales';
+            REGISTER TABLE sales.orders LOCATION 's3://lake/sales/orders';
+            SET ACCESS MODE IAM_ONLY ON DATABASE legacy;
+            DEFINE GROUP auditors AS ('carol');
+            GRANT CREATE_TABLE, DROP_TABLE, ALTER_TABLE ON DATABASE sales TO ROLE analyst;
+            GRANT DATA_LOCATION_ACCESS ON 's3://lake/sales' TO ROLE analyst;
+            GRANT SELECT, DESCRIBE ON sales.orders (id, region) TO ROLE analyst WITH GRANT OPTION WHERE region = 'west';
+            GRANT INSERT ON sales.orders TO USER 'bob' WHEN SESSION_CONTEXT('mfa') = 'true' -- on call";
+        let mut state = EmulatorState::new();
+        for statement in lakesql_parser::parse_ddl_script(script).unwrap() {
+            match statement {
+                lakesql_parser::DdlStatement::Grant { .. } => {
+                    let mut permission = statement.to_permission().unwrap();
+                    permission.normalize_actions();
+                    state.permissions.push(permission);
+                },
+                lakesql_parser::DdlStatement::CreateRole { name } => {
+                    state.roles.insert(name, std::collections::HashSet::new());
+                },
+                lakesql_parser::DdlStatement::AlterRole { name, add_users, .. } => {
+                    state.roles.get_mut(&name).unwrap().extend(add_users);
+                },
+                lakesql_parser::DdlStatement::CreateTag { name, values } => {
+                    state.tags.insert(name.clone(), lakesql_core::LfTag { key: name, values, description: None });
+                },
+                lakesql_parser::DdlStatement::RegisterLocation { path } => {
+                    state.registered_locations.insert(path);
+                },
+                lakesql_parser::DdlStatement::RegisterTable { database, table, location } => {
+                    state.table_locations.insert(format!("{}.{}", database, table), location);
+                },
+                lakesql_parser::DdlStatement::SetAccessMode { resource, .. } => {
+                    state.iam_only_resources.insert(resource);
+                },
+                lakesql_parser::DdlStatement::DefineGroup { name, members } => {
+                    state.local_groups.insert(name, members.into_iter().collect());
+                },
+                other => panic!("Unexpected statement {:?}", other),
+            }
+        }
+
+        let exported = StateExporter::to_sql_ddl(&state);
+        assert!(!exported.contains("\\n"));
+        assert!(exported.contains("GRANT ALTER_TABLE, CREATE_TABLE, DROP_TABLE ON DATABASE sales TO ROLE analyst;"));
+
+        let statements: Vec<_> = exported
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with("--"))
+            .map(|line| lakesql_parser::parse_ddl(line).unwrap_or_else(|e| panic!("{}: {}", line, e)))
+            .collect();
+        assert_eq!(statements.len(), 11);
+
+        let reparsed: Vec<_> = statements.iter().filter_map(|s| s.to_permission().ok()).collect();
+        assert_eq!(reparsed, state.permissions);
+    }
+
+    #[test]
+    fn test_sql_export_round_trips_tagged_resources() {
+        let permission = lakesql_core::Permission::new(
+            lakesql_core::Principal::Role("analyst".to_string()),
+            lakesql_core::Resource::TaggedResource {
+                tag_conditions: vec![
+                    ("department".to_string(), vec!["finance".to_string(), "hr".to_string()]),
+                    ("classification".to_string(), vec!["confidential".to_string()]),
+                ],
+            },
+            vec![lakesql_core::Action::Select],
+        );
+        let mut state = EmulatorState::new();
+        state.permissions.push(permission.clone());
+
+        let exported = StateExporter::to_sql_ddl(&state);
+        let grant_line = exported.lines().find(|line| line.starts_with("GRANT")).unwrap();
+        assert!(grant_line.contains("RESOURCES TAGGED department=('finance', 'hr') AND classification='confidential'"));
+
+        let reparsed = lakesql_parser::parse_ddl(grant_line).unwrap().to_permission().unwrap();
+        assert_eq!(reparsed.resource, permission.resource);
+    }
+
+    #[test]
+    fn test_sql_export_round_trips_principals() {
+        let principals = [
+            "ROLE analyst",
+            "USER 'o''brien@company.com'",
+            "GROUP 'org:finance'",
+            "EXTERNAL_ACCOUNT '123456789012'",
+            "TAGGED department='finance','hr'",
+        ];
+        for principal in principals {
+            let sql = format!("GRANT SELECT ON DATABASE sales TO {}", principal);
+            let permission = lakesql_parser::parse_ddl(&sql).unwrap().to_permission().unwrap();
+
+            let mut state = EmulatorState::new();
+            state.permissions.push(permission.clone());
+            let exported = StateExporter::to_sql_ddl(&state);
+            let grant_line = exported.lines().find(|line| line.starts_with("GRANT")).unwrap();
+            assert!(grant_line.contains(principal), "{} not in {}", principal, grant_line);
+
+            let reparsed = lakesql_parser::parse_ddl(grant_line).unwrap().to_permission().unwrap();
+            assert_eq!(reparsed.principal, permission.principal);
+        }
+    }
+
+    #[test]
+    fn test_sql_export_round_trips_deny() {
+        let permission = lakesql_parser::parse_ddl("DENY SELECT ON sales.orders TO ROLE intern")
+            .unwrap()
+            .to_permission()
+            .unwrap();
+
+        let mut state = EmulatorState::new();
+        state.permissions.push(permission.clone());
+
+        let exported = StateExporter::to_sql_ddl(&state);
+        assert!(!exported.contains("GRANT SELECT"));
+        let deny_line = exported.lines().find(|line| line.starts_with("DENY")).unwrap();
+        assert_eq!(deny_line, "DENY SELECT ON sales.orders TO ROLE intern;");
+
+        let reparsed = lakesql_parser::parse_ddl(deny_line).unwrap().to_permission().unwrap();
+        assert_eq!(reparsed, permission);
+        assert!(StateExporter::to_csv(&state).lines().nth(1).unwrap().ends_with(",DENY"));
+    }
+
+    #[test]
+    fn test_csv_export() {
+        let sql = "GRANT SELECT, DESCRIBE ON sales.orders (id, region) TO ROLE analyst WHERE region = 'EU, West'";
+        let mut permission = lakesql_parser::parse_ddl(sql).unwrap().to_permission().unwrap();
+        permission.grantable_actions.insert(lakesql_core::Action::Select);
+
+        let mut state = EmulatorState::new();
+        state.permissions.push(permission);
+
+        let csv = StateExporter::to_csv(&state);
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines[0], "principal_type,principal,resource_type,resource,columns,action,grant_option,row_filter,effect");
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].contains(",DESCRIBE,false,"));
+        assert_eq!(lines[2], "ROLE,analyst,TABLE,sales.orders,id;region,SELECT,true,\"WHERE region = 'EU, West'\",ALLOW");
+    }
+}
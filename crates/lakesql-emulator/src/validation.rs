@@ -0,0 +1,247 @@
+//! Internal consistency checks for emulator state
+
+use crate::EmulatorState;
+use lakesql_core::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A single inconsistency found in emulator state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ValidationIssue {
+    /// A tagged principal or resource references a tag that isn't defined
+    UnknownTag {
+        permission_index: usize,
+        tag_key: String,
+    },
+    /// A tagged principal or resource uses a value the tag doesn't allow
+    UnknownTagValue {
+        permission_index: usize,
+        tag_key: String,
+        value: String,
+    },
+    /// A role member that can't identify a principal
+    MalformedRoleMember {
+        role: String,
+        member: String,
+    },
+    /// Two permissions that a grant would have merged into one
+    DuplicatePermission {
+        first_index: usize,
+        duplicate_index: usize,
+    },
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationIssue::UnknownTag { permission_index, tag_key } => {
+                write!(f, "permission {} references undefined tag '{}'", permission_index, tag_key)
+            },
+            ValidationIssue::UnknownTagValue { permission_index, tag_key, value } => {
+                write!(f, "permission {} uses value '{}' not defined for tag '{}'", permission_index, value, tag_key)
+            },
+            ValidationIssue::MalformedRoleMember { role, member } => {
+                write!(f, "role '{}' has malformed member '{}'", role, member)
+            },
+            ValidationIssue::DuplicatePermission { first_index, duplicate_index } => {
+                write!(f, "permission {} duplicates principal/resource of permission {}", duplicate_index, first_index)
+            },
+        }
+    }
+}
+
+impl EmulatorState {
+    /// Check the state for dangling tag references, malformed role members
+    /// and duplicate grants
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+
+        for (index, permission) in self.permissions.iter().enumerate() {
+            for (tag_key, values) in referenced_tags(permission) {
+                self.check_tag_reference(index, tag_key, values, &mut issues);
+            }
+        }
+
+        let mut roles: Vec<_> = self.roles.iter().collect();
+        roles.sort_by(|a, b| a.0.cmp(b.0));
+        for (role, members) in roles {
+            let mut members: Vec<_> = members.iter().collect();
+            members.sort();
+            for member in members {
+                if !is_well_formed_member(member) {
+                    issues.push(ValidationIssue::MalformedRoleMember {
+                        role: role.clone(),
+                        member: member.clone(),
+                    });
+                }
+            }
+        }
+
+        for (duplicate_index, permission) in self.permissions.iter().enumerate() {
+            let first = self.permissions[..duplicate_index].iter().position(|p| p.merges_with(permission));
+            if let Some(first_index) = first {
+                issues.push(ValidationIssue::DuplicatePermission { first_index, duplicate_index });
+            }
+        }
+
+        issues
+    }
+
+    fn check_tag_reference(
+        &self,
+        permission_index: usize,
+        tag_key: &str,
+        values: &[String],
+        issues: &mut Vec<ValidationIssue>,
+    ) {
+        let Some(tag) = self.tags.get(tag_key) else {
+            issues.push(ValidationIssue::UnknownTag {
+                permission_index,
+                tag_key: tag_key.to_string(),
+            });
+            return;
+        };
+
+        for value in values {
+            if !tag.values.contains(value) {
+                issues.push(ValidationIssue::UnknownTagValue {
+                    permission_index,
+                    tag_key: tag_key.to_string(),
+                    value: value.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// Tag keys (with values) referenced by a permission's principal or resource
+fn referenced_tags(permission: &Permission) -> Vec<(&str, &[String])> {
+    let mut tags = Vec::new();
+    if let Principal::TaggedPrincipal { tag_key, tag_values } = &permission.principal {
+        tags.push((tag_key.as_str(), tag_values.as_slice()));
+    }
+    if let Resource::TaggedResource { tag_conditions } = &permission.resource {
+        for (tag_key, values) in tag_conditions {
+            tags.push((tag_key.as_str(), values.as_slice()));
+        }
+    }
+    tags
+}
+
+/// Members must be non-empty, contain no whitespace, and if given as an
+/// ARN, have all six `arn:partition:service:region:account:resource` parts
+fn is_well_formed_member(member: &str) -> bool {
+    if member.is_empty() || member.chars().any(char::is_whitespace) {
+        return false;
+    }
+    if member.starts_with("arn:") {
+        return member.splitn(6, ':').count() == 6;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn permission(principal: Principal, resource: Resource) -> Permission {
+        Permission::new(principal, resource, vec![Action::Select])
+    }
+
+    fn state_with_tag() -> EmulatorState {
+        let mut state = EmulatorState::new();
+        state.tags.insert("department".to_string(), LfTag {
+            key: "department".to_string(),
+            values: vec!["finance".to_string(), "hr".to_string()],
+            description: None,
+        });
+        state
+    }
+
+    #[test]
+    fn test_valid_state_has_no_issues() {
+        let mut state = state_with_tag();
+        state.roles.insert(
+            "analyst".to_string(),
+            HashSet::from(["arn:aws:iam::123456789012:user/alice".to_string()]),
+        );
+        state.permissions.push(permission(
+            Principal::Role("analyst".to_string()),
+            Resource::TaggedResource {
+                tag_conditions: vec![("department".to_string(), vec!["finance".to_string()])],
+            },
+        ));
+
+        assert!(state.validate().is_empty());
+    }
+
+    #[test]
+    fn test_unknown_tag_and_value() {
+        let mut state = state_with_tag();
+        state.permissions.push(permission(
+            Principal::TaggedPrincipal {
+                tag_key: "team".to_string(),
+                tag_values: vec!["analysts".to_string()],
+            },
+            Resource::Database { name: "sales".to_string(), catalog_id: None },
+        ));
+        state.permissions.push(permission(
+            Principal::Role("analyst".to_string()),
+            Resource::TaggedResource {
+                tag_conditions: vec![("department".to_string(), vec!["legal".to_string()])],
+            },
+        ));
+
+        let issues = state.validate();
+        assert_eq!(issues, vec![
+            ValidationIssue::UnknownTag {
+                permission_index: 0,
+                tag_key: "team".to_string(),
+            },
+            ValidationIssue::UnknownTagValue {
+                permission_index: 1,
+                tag_key: "department".to_string(),
+                value: "legal".to_string(),
+            },
+        ]);
+    }
+
+    #[test]
+    fn test_malformed_role_members() {
+        let mut state = EmulatorState::new();
+        state.roles.insert(
+            "analyst".to_string(),
+            HashSet::from([
+                "alice@company.com".to_string(),
+                "".to_string(),
+                "bob smith".to_string(),
+                "arn:aws:iam::user".to_string(),
+            ]),
+        );
+
+        let issues = state.validate();
+        assert_eq!(issues.len(), 3);
+        for member in ["", "bob smith", "arn:aws:iam::user"] {
+            assert!(issues.contains(&ValidationIssue::MalformedRoleMember {
+                role: "analyst".to_string(),
+                member: member.to_string(),
+            }));
+        }
+    }
+
+    #[test]
+    fn test_duplicate_permissions() {
+        let mut state = EmulatorState::new();
+        let principal = Principal::Role("analyst".to_string());
+        let resource = Resource::Database { name: "sales".to_string(), catalog_id: None };
+        state.permissions.push(permission(principal.clone(), resource.clone()));
+        state.permissions.push(permission(principal.clone(), Resource::Database { name: "hr".to_string(), catalog_id: None }));
+        state.permissions.push(permission(principal, resource));
+
+        assert_eq!(state.validate(), vec![ValidationIssue::DuplicatePermission {
+            first_index: 0,
+            duplicate_index: 2,
+        }]);
+    }
+}
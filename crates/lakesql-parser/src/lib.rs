@@ -6,29 +6,51 @@ use pest::Parser;
 use pest_derive::Parser;
 use anyhow::{Result, anyhow};
 use lakesql_core::types::*;
+use std::collections::HashSet;
 
 #[derive(Parser)]
 #[grammar = "grammar.pest"]
 pub struct LakeSqlParser;
 
 /// Abstract Syntax Tree for Lake Formation DDL
+// Statements are short-lived parse results, so GRANT's size is not worth boxing
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum DdlStatement {
     Grant {
         actions: Vec<Action>,
         resource: Resource,
         principal: Principal,
-        grant_option: bool,
+        /// Actions granted WITH GRANT OPTION
+        grantable_actions: HashSet<Action>,
         row_filter: Option<RowFilter>,
+        /// `WHEN SESSION_CONTEXT(...)` condition the caller's session must satisfy
+        principal_condition: Option<RowFilter>,
+        /// Trailing `-- comment` on the statement, if any
+        comment: Option<String>,
     },
     Revoke {
         actions: Vec<Action>,
         resource: Resource,
         principal: Principal,
     },
+    /// Make `member` a member of `role`, so it inherits every grant `role` holds
+    GrantRole {
+        role: String,
+        member: String,
+    },
+    /// Explicitly deny actions; a deny wins over any grant that also matches
+    Deny {
+        actions: Vec<Action>,
+        resource: Resource,
+        principal: Principal,
+    },
     CreateRole {
         name: String,
     },
+    CreateRoles {
+        names: Vec<String>,
+    },
     CreateTag {
         name: String,
         values: Vec<String>,
@@ -39,6 +61,39 @@ pub enum DdlStatement {
     DropTag {
         name: String,
     },
+    /// Change the users that are members of a role
+    AlterRole {
+        name: String,
+        add_users: Vec<String>,
+        drop_users: Vec<String>,
+    },
+    RegisterLocation {
+        path: String,
+    },
+    RegisterTable {
+        database: String,
+        table: String,
+        location: String,
+    },
+    SetAccessMode {
+        resource: Resource,
+        mode: AccessMode,
+    },
+    /// A local group of users, granted to as `GROUP 'name'`
+    DefineGroup {
+        name: String,
+        members: Vec<String>,
+    },
+    /// A named bundle of GRANTs over `{resource}` and `{principal}`
+    DefineTemplate {
+        name: String,
+        body: String,
+    },
+    ApplyTemplate {
+        name: String,
+        resource: Resource,
+        principal: Principal,
+    },
     ShowPermissions {
         principal: Option<Principal>,
     },
@@ -47,54 +102,545 @@ pub enum DdlStatement {
 }
 
 impl DdlStatement {
-    /// Convert DDL statement to Permission (for GRANT/REVOKE)
+    /// Whether the statement only inspects state (SHOW ...) and never mutates it
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            DdlStatement::ShowPermissions { .. } | DdlStatement::ShowRoles | DdlStatement::ShowTags
+        )
+    }
+
+    /// Convert DDL statement to Permission (for GRANT/DENY)
     pub fn to_permission(&self) -> Result<Permission> {
         match self {
-            DdlStatement::Grant { actions, resource, principal, grant_option, row_filter } => {
-                Ok(Permission {
+            DdlStatement::Grant { actions, resource, principal, grantable_actions, row_filter, principal_condition, comment } => {
+                let mut permission = Permission {
                     principal: principal.clone(),
                     resource: resource.clone(),
                     actions: actions.clone(),
-                    grant_option: *grant_option,
+                    grantable_actions: grantable_actions.clone(),
                     row_filter: row_filter.clone(),
-                })
+                    principal_condition: principal_condition.clone(),
+                    comment: comment.clone(),
+                    expires_at: None,
+                    effect: Effect::Allow,
+                };
+                permission.normalize_actions();
+                Ok(permission)
+            },
+            DdlStatement::Deny { actions, resource, principal } => {
+                let mut permission = Permission {
+                    principal: principal.clone(),
+                    resource: resource.clone(),
+                    actions: actions.clone(),
+                    grantable_actions: HashSet::new(),
+                    row_filter: None,
+                    principal_condition: None,
+                    comment: None,
+                    expires_at: None,
+                    effect: Effect::Deny,
+                };
+                permission.normalize_actions();
+                Ok(permission)
+            },
+            _ => Err(anyhow!("Statement is not a GRANT or DENY and cannot be converted to Permission")),
+        }
+    }
+}
+
+/// Options controlling how DDL is parsed
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Reject action lists that repeat an action (e.g. `SELECT, SELECT`)
+    /// instead of silently deduplicating them
+    pub strict_actions: bool,
+    /// Accept a bare double-quoted principal (`TO "alice@company.com"`, as
+    /// Athena and Trino emit) as a user
+    pub bare_principal_as_user: bool,
+}
+
+/// Bind `$1`, `$2`, ... placeholders in a DDL template to parameter values.
+///
+/// Placeholders inside a single-quoted literal (e.g. `USER '$1'`) are
+/// substituted with embedded quotes escaped as `''`. Bare placeholders
+/// (e.g. `ON $1`) stand for identifiers, so their values must be plain or
+/// dotted identifiers; anything else is rejected rather than spliced into
+/// the statement. Comments and double-quoted identifiers are copied as-is,
+/// so an apostrophe in them doesn't open a literal.
+pub fn bind_params(sql: &str, params: &[String]) -> Result<String> {
+    let mut bound = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    let mut in_literal = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                bound.push(c);
+                if in_literal && chars.peek() == Some(&'\'') {
+                    // Escaped quote inside a literal
+                    bound.push(chars.next().unwrap());
+                } else {
+                    in_literal = !in_literal;
+                }
+            },
+            '-' if !in_literal && chars.peek() == Some(&'-') => {
+                bound.push(c);
+                for c in chars.by_ref() {
+                    bound.push(c);
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            },
+            '/' if !in_literal && chars.peek() == Some(&'*') => {
+                bound.push(c);
+                bound.push(chars.next().unwrap());
+                let mut prev = None;
+                for c in chars.by_ref() {
+                    bound.push(c);
+                    if prev == Some('*') && c == '/' {
+                        break;
+                    }
+                    prev = Some(c);
+                }
+            },
+            // An escaped `""` just closes and reopens the identifier
+            '"' if !in_literal => {
+                bound.push(c);
+                for c in chars.by_ref() {
+                    bound.push(c);
+                    if c == '"' {
+                        break;
+                    }
+                }
+            },
+            '$' if chars.peek().is_some_and(|d| d.is_ascii_digit()) => {
+                let mut digits = String::new();
+                while let Some(d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                    digits.push(*d);
+                    chars.next();
+                }
+                let index: usize = digits.parse()?;
+                let value = index
+                    .checked_sub(1)
+                    .and_then(|i| params.get(i))
+                    .ok_or_else(|| anyhow!("No parameter bound for ${}", index))?;
+
+                if in_literal {
+                    bound.push_str(&value.replace('\'', "''"));
+                } else if is_dotted_identifier(value) {
+                    bound.push_str(value);
+                } else {
+                    return Err(anyhow!(
+                        "Parameter ${} must be an identifier, got '{}' (quote the placeholder to bind a string)",
+                        index, value
+                    ));
+                }
             },
-            _ => Err(anyhow!("Statement is not a GRANT and cannot be converted to Permission")),
+            _ => bound.push(c),
         }
     }
+
+    if in_literal {
+        return Err(anyhow!("Unterminated string literal in DDL template"));
+    }
+
+    Ok(bound)
+}
+
+fn is_dotted_identifier(value: &str) -> bool {
+    !value.is_empty() && value.split('.').all(|part| {
+        let mut chars = part.chars();
+        chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
 }
 
 /// Parse a Lake Formation DDL statement
 pub fn parse_ddl(sql: &str) -> Result<DdlStatement> {
-    let pairs = LakeSqlParser::parse(Rule::program, sql)
-        .map_err(|e| anyhow!("Parse error: {}", e))?;
-
-    for pair in pairs {
-        match pair.as_rule() {
-            Rule::program => {
-                for inner_pair in pair.into_inner() {
-                    if inner_pair.as_rule() == Rule::ddl_statement {
-                        return parse_ddl_statement(inner_pair);
+    parse_ddl_with_options(sql, &ParseOptions::default())
+}
+
+/// Parse a single Lake Formation DDL statement with custom parse options;
+/// input holding several statements is an error
+pub fn parse_ddl_with_options(sql: &str, options: &ParseOptions) -> Result<DdlStatement> {
+    let mut statements = parse_ddl_many_with_options(sql, options)?;
+    match statements.len() {
+        0 => Err(anyhow!("No valid DDL statement found")),
+        1 => Ok(statements.remove(0)),
+        n => Err(anyhow!("Expected a single DDL statement, found {}; use parse_ddl_many for scripts", n)),
+    }
+}
+
+/// Parse every `;`-separated DDL statement in the input, in order
+pub fn parse_ddl_many(sql: &str) -> Result<Vec<DdlStatement>> {
+    parse_ddl_many_with_options(sql, &ParseOptions::default())
+}
+
+/// Parse every `;`-separated DDL statement in the input with custom parse options
+pub fn parse_ddl_many_with_options(sql: &str, options: &ParseOptions) -> Result<Vec<DdlStatement>> {
+    let pairs = LakeSqlParser::parse(Rule::program, sql).map_err(|e| {
+        let (position, message, expected) = describe_pest_error(sql, &e);
+        ParseError::at(sql, position, statement_index_at(sql, position), message, expected)
+    })?;
+
+    let mut statements = Vec::new();
+    for pair in pairs.flat_map(|pair| pair.into_inner()) {
+        if pair.as_rule() == Rule::ddl_statement {
+            statements.push(parse_ddl_statement_with_comment(pair, sql, options)?);
+        }
+    }
+
+    Ok(statements)
+}
+
+/// Parse a script of `;`-separated DDL statements (same as `parse_ddl_many`)
+pub fn parse_ddl_script(sql: &str) -> Result<Vec<DdlStatement>> {
+    parse_ddl_many(sql)
+}
+
+/// Parse a script of `;`-separated DDL statements with custom parse options
+pub fn parse_ddl_script_with_options(sql: &str, options: &ParseOptions) -> Result<Vec<DdlStatement>> {
+    parse_ddl_many_with_options(sql, options)
+}
+
+/// A statement that failed to parse, located in its script. Displays as a
+/// short message with the offending line and a caret under the error:
+///
+/// ```text
+/// unexpected token at line 1 col 14: expected ON
+///   GRANT SELECT sales.orders TO ROLE analyst
+///                ^
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// Zero-based index of the statement within the script
+    pub statement_index: usize,
+    /// 1-based line of the error in the script
+    pub line: usize,
+    /// 1-based column of the error in the script
+    pub column: usize,
+    /// What went wrong, e.g. `unexpected token`
+    pub message: String,
+    /// Tokens the grammar would have accepted here, if known
+    pub expected: Vec<String>,
+    /// The script line holding the error
+    pub source_line: String,
+}
+
+impl ParseError {
+    /// Locate an error at a byte position in `sql`
+    fn at(sql: &str, position: usize, statement_index: usize, message: String, expected: Vec<String>) -> Self {
+        let (line, column) = line_col(sql, position);
+        let source_line = sql.lines().nth(line - 1).unwrap_or_default().trim_end().to_string();
+        Self { statement_index, line, column, message, expected, source_line }
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at line {} col {}", self.message, self.line, self.column)?;
+        if let Some((last, rest)) = self.expected.split_last() {
+            if rest.is_empty() {
+                write!(f, ": expected {}", last)?;
+            } else {
+                write!(f, ": expected {} or {}", rest.join(", "), last)?;
+            }
+        }
+
+        // Keep tabs so the caret lines up with the source
+        let padding: String = self.source_line
+            .chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        write!(f, "\n  {}\n  {}^", self.source_line, padding)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Byte position, message and expected tokens of a grammar error
+fn describe_pest_error(sql: &str, e: &pest::error::Error<Rule>) -> (usize, String, Vec<String>) {
+    let position = match e.location {
+        pest::error::InputLocation::Pos(pos) => pos,
+        pest::error::InputLocation::Span((start, _)) => start,
+    };
+
+    let expected = match &e.variant {
+        pest::error::ErrorVariant::ParsingError { positives, .. } => {
+            let mut expected: Vec<String> = positives.iter().map(|rule| describe_rule(*rule)).collect();
+            expected.dedup();
+            expected
+        },
+        pest::error::ErrorVariant::CustomError { .. } => Vec::new(),
+    };
+
+    let message = if sql[position..].trim().is_empty() {
+        "unexpected end of input"
+    } else {
+        "unexpected token"
+    };
+    (position, message.to_string(), expected)
+}
+
+/// How a grammar rule reads in an error message: keywords in upper case,
+/// other rules as words (`grant_action_list` becomes `grant action list`)
+fn describe_rule(rule: Rule) -> String {
+    let name = format!("{:?}", rule);
+    match rule {
+        Rule::EOI => "end of input".to_string(),
+        Rule::lf_tag => "LF-TAG".to_string(),
+        Rule::grant | Rule::revoke | Rule::deny | Rule::create | Rule::drop | Rule::alter | Rule::on | Rule::to
+        | Rule::from | Rule::with | Rule::option | Rule::role | Rule::user | Rule::group
+        | Rule::database | Rule::table | Rule::tag | Rule::values | Rule::r#where | Rule::when | Rule::r#in | Rule::not | Rule::null | Rule::is | Rule::current_user
+        | Rule::current_timestamp | Rule::current_date
+        | Rule::session_context | Rule::external_account | Rule::data_location_access | Rule::tagged
+        | Rule::resources | Rule::register | Rule::location | Rule::roles | Rule::define
+        | Rule::template | Rule::r#as | Rule::apply | Rule::view | Rule::add => name.trim_start_matches("r#").to_uppercase(),
+        _ => name.replace('_', " "),
+    }
+}
+
+/// Index of the statement holding a byte position, counting statements as
+/// `parse_ddl_script_collect` does
+fn statement_index_at(sql: &str, position: usize) -> usize {
+    let mut offset = 0;
+    let mut index = 0;
+    while offset < sql.len() {
+        let (chunk, has_content) = next_statement_chunk(&sql[offset..]);
+        // An error at the very end belongs to the last statement
+        if offset + chunk.len() > position || offset + chunk.len() == sql.len() {
+            break;
+        }
+        if has_content {
+            index += 1;
+        }
+        offset += chunk.len();
+    }
+    index
+}
+
+/// Parse a script of `;`-separated DDL statements, resynchronizing at the
+/// next `;` after a bad statement so every error is reported in one pass
+pub fn parse_ddl_script_collect(sql: &str) -> (Vec<DdlStatement>, Vec<ParseError>) {
+    parse_ddl_script_collect_with_options(sql, &ParseOptions::default())
+}
+
+/// Parse a script collecting every error, with custom parse options
+pub fn parse_ddl_script_collect_with_options(
+    sql: &str,
+    options: &ParseOptions,
+) -> (Vec<DdlStatement>, Vec<ParseError>) {
+    let mut statements = Vec::new();
+    let mut errors = Vec::new();
+    let mut offset = 0;
+    let mut statement_index = 0;
+
+    while offset < sql.len() {
+        let (chunk, has_content) = next_statement_chunk(&sql[offset..]);
+        if has_content {
+            match parse_located(chunk, options) {
+                Ok(statement) => statements.push(statement),
+                Err((position, message, expected)) => {
+                    errors.push(ParseError::at(sql, offset + position, statement_index, message, expected));
+                },
+            }
+            statement_index += 1;
+        }
+        offset += chunk.len();
+    }
+
+    (statements, errors)
+}
+
+/// Parse one statement, reporting the byte position of a failure (where
+/// the grammar stopped matching, or the start of the statement for errors
+/// found while building it), the message and any expected tokens
+fn parse_located(
+    sql: &str,
+    options: &ParseOptions,
+) -> std::result::Result<DdlStatement, (usize, String, Vec<String>)> {
+    let statement_start = sql.len() - sql.trim_start().len();
+
+    let mut pairs = LakeSqlParser::parse(Rule::program, sql).map_err(|e| describe_pest_error(sql, &e))?;
+
+    pairs
+        .next()
+        .and_then(|program| program.into_inner().find(|p| p.as_rule() == Rule::ddl_statement))
+        .ok_or_else(|| anyhow!("No valid DDL statement found"))
+        .and_then(|pair| parse_ddl_statement_with_comment(pair, sql, options))
+        .map_err(|e| (statement_start, e.to_string(), Vec::new()))
+}
+
+/// 1-based line and column of a byte position
+fn line_col(sql: &str, position: usize) -> (usize, usize) {
+    let before = &sql[..position];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rfind('\n').map_or(before, |nl| &before[nl + 1..]).chars().count() + 1;
+    (line, column)
+}
+
+/// Lazily parse a script of `;`-separated DDL statements one at a time, so
+/// large files can be applied without holding every statement in memory
+pub fn parse_ddl_stream(sql: &str) -> DdlStream<'_> {
+    parse_ddl_stream_with_options(sql, ParseOptions::default())
+}
+
+/// Lazily parse a script of `;`-separated DDL statements with custom parse options
+pub fn parse_ddl_stream_with_options(sql: &str, options: ParseOptions) -> DdlStream<'_> {
+    DdlStream { rest: sql, options, index: 0 }
+}
+
+/// Iterator over the statements of a DDL script, returned by [`parse_ddl_stream`].
+///
+/// A statement that fails to parse yields an error and the stream moves on
+/// to the next one, so callers decide whether to stop.
+#[derive(Debug, Clone)]
+pub struct DdlStream<'a> {
+    rest: &'a str,
+    options: ParseOptions,
+    index: usize,
+}
+
+impl Iterator for DdlStream<'_> {
+    type Item = Result<DdlStatement>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.rest.is_empty() {
+            let (chunk, has_content) = next_statement_chunk(self.rest);
+            self.rest = &self.rest[chunk.len()..];
+
+            // Skip stretches holding only whitespace and comments
+            if has_content {
+                self.index += 1;
+                let index = self.index;
+                return Some(
+                    parse_ddl_with_options(chunk, &self.options)
+                        .map_err(|e| anyhow!("Statement {}: {}", index, e))
+                );
+            }
+        }
+        None
+    }
+}
+
+/// Split off the next statement up to and including its `;` plus any
+/// `-- comment` trailing it on the same line. Also reports whether the
+/// chunk holds anything besides whitespace and comments.
+fn next_statement_chunk(sql: &str) -> (&str, bool) {
+    let bytes = sql.as_bytes();
+    let mut has_content = false;
+    let mut depth = 0usize;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"') => {
+                has_content = true;
+                i += 1;
+                while i < bytes.len() {
+                    if bytes[i] == quote {
+                        if bytes.get(i + 1) == Some(&quote) {
+                            // Escaped quote inside a literal
+                            i += 2;
+                            continue;
+                        }
+                        break;
                     }
+                    i += 1;
+                }
+                i = (i + 1).min(bytes.len());
+            },
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i = sql[i..].find('\n').map_or(bytes.len(), |end| i + end);
+            },
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = sql[i + 2..].find("*/").map_or(bytes.len(), |end| i + 2 + end + 2);
+            },
+            // A template body's statements sit inside parentheses
+            b'(' => {
+                has_content = true;
+                depth += 1;
+                i += 1;
+            },
+            b')' => {
+                depth = depth.saturating_sub(1);
+                i += 1;
+            },
+            b';' if depth > 0 => i += 1,
+            b';' => {
+                i += 1;
+                let after = &sql[i..];
+                let trimmed = after.trim_start_matches([' ', '\t']);
+                if trimmed.starts_with("--") {
+                    i += after.len() - trimmed.len() + trimmed.find('\n').unwrap_or(trimmed.len());
                 }
+                return (&sql[..i], has_content);
+            },
+            c => {
+                has_content |= !c.is_ascii_whitespace();
+                i += 1;
             },
-            _ => continue,
         }
     }
 
-    Err(anyhow!("No valid DDL statement found"))
+    (sql, has_content)
+}
+
+/// Parse a statement, attaching any trailing `-- comment` to a GRANT
+fn parse_ddl_statement_with_comment(
+    pair: pest::iterators::Pair<Rule>,
+    sql: &str,
+    options: &ParseOptions,
+) -> Result<DdlStatement> {
+    // The statement span can swallow a trailing comment as implicit
+    // whitespace, so measure from the end of its last real token
+    let end = pair.clone().into_inner().flatten()
+        .filter(|p| p.clone().into_inner().next().is_none())
+        .map(|p| p.as_span().end())
+        .max()
+        .unwrap_or_else(|| pair.as_span().end());
+    let mut statement = parse_ddl_statement(pair, options)?;
+    if let DdlStatement::Grant { comment, .. } = &mut statement {
+        *comment = trailing_comment(&sql[end..]);
+    }
+    Ok(statement)
+}
+
+/// Extract the text of a `-- comment` that trails a statement (after an
+/// optional semicolon) on the same line
+fn trailing_comment(rest: &str) -> Option<String> {
+    let rest = rest.trim_start_matches([' ', '\t']);
+    let rest = rest.strip_prefix(';').unwrap_or(rest).trim_start_matches([' ', '\t']);
+    let comment = rest.strip_prefix("--")?.lines().next()?.trim();
+    if comment.is_empty() {
+        None
+    } else {
+        Some(comment.to_string())
+    }
 }
 
-fn parse_ddl_statement(pair: pest::iterators::Pair<Rule>) -> Result<DdlStatement> {
+fn parse_ddl_statement(pair: pest::iterators::Pair<Rule>, options: &ParseOptions) -> Result<DdlStatement> {
     for inner_pair in pair.into_inner() {
         return match inner_pair.as_rule() {
-            Rule::grant_statement => parse_grant_statement(inner_pair),
-            Rule::revoke_statement => parse_revoke_statement(inner_pair),
+            Rule::grant_statement => parse_grant_statement(inner_pair, options),
+            Rule::grant_role_statement => parse_grant_role_statement(inner_pair),
+            Rule::revoke_statement => parse_revoke_statement(inner_pair, options),
+            Rule::deny_statement => parse_deny_statement(inner_pair, options),
             Rule::create_role_statement => parse_create_role_statement(inner_pair),
+            Rule::create_roles_statement => parse_create_roles_statement(inner_pair),
             Rule::create_tag_statement => parse_create_tag_statement(inner_pair),
             Rule::drop_role_statement => parse_drop_role_statement(inner_pair),
             Rule::drop_tag_statement => parse_drop_tag_statement(inner_pair),
-            Rule::show_statement => parse_show_statement(inner_pair),
+            Rule::alter_role_statement => parse_alter_role_statement(inner_pair),
+            Rule::register_location_statement => parse_register_location_statement(inner_pair),
+            Rule::register_table_statement => parse_register_table_statement(inner_pair),
+            Rule::set_access_mode_statement => parse_set_access_mode_statement(inner_pair),
+            Rule::define_group_statement => parse_define_group_statement(inner_pair),
+            Rule::define_template_statement => parse_define_template_statement(inner_pair),
+            Rule::apply_template_statement => parse_apply_template_statement(inner_pair, options),
+            Rule::show_statement => parse_show_statement(inner_pair, options),
             _ => Err(anyhow!("Unknown DDL statement type")),
         };
     }
@@ -102,45 +648,60 @@ fn parse_ddl_statement(pair: pest::iterators::Pair<Rule>) -> Result<DdlStatement
     Err(anyhow!("Empty DDL statement"))
 }
 
-fn parse_grant_statement(pair: pest::iterators::Pair<Rule>) -> Result<DdlStatement> {
+fn parse_grant_statement(pair: pest::iterators::Pair<Rule>, options: &ParseOptions) -> Result<DdlStatement> {
     let mut actions = Vec::new();
+    let mut grantable_actions = HashSet::new();
     let mut resource = None;
     let mut principal = None;
     let mut grant_option = false;
     let mut row_filter = None;
+    let mut principal_condition = None;
 
     for inner_pair in pair.into_inner() {
         match inner_pair.as_rule() {
-            Rule::action_list => {
-                actions = parse_action_list(inner_pair)?;
+            Rule::grant_action_list => {
+                (actions, grantable_actions) = parse_grant_action_list(inner_pair, options)?;
             },
             Rule::resource => {
                 resource = Some(parse_resource(inner_pair)?);
             },
             Rule::principal => {
-                principal = Some(parse_principal(inner_pair)?);
+                principal = Some(parse_principal(inner_pair, options)?);
             },
-            Rule::grant => {
-                // Look for "WITH GRANT OPTION"
+            Rule::grant_option_clause => {
+                // Statement-level "WITH GRANT OPTION" covers every action,
+                // whether it follows the principal or ends the statement
+                if grant_option {
+                    return Err(anyhow!("WITH GRANT OPTION given more than once in GRANT"));
+                }
                 grant_option = true;
             },
             Rule::row_filter => {
                 row_filter = Some(parse_row_filter(inner_pair)?);
             },
+            Rule::grant_condition => {
+                principal_condition = Some(parse_grant_condition(inner_pair)?);
+            },
             _ => {},
         }
     }
 
+    if grant_option {
+        grantable_actions = actions.iter().cloned().collect();
+    }
+
     Ok(DdlStatement::Grant {
         actions,
         resource: resource.ok_or_else(|| anyhow!("Missing resource in GRANT"))?,
         principal: principal.ok_or_else(|| anyhow!("Missing principal in GRANT"))?,
-        grant_option,
+        grantable_actions,
         row_filter,
+        principal_condition,
+        comment: None,
     })
 }
 
-fn parse_revoke_statement(pair: pest::iterators::Pair<Rule>) -> Result<DdlStatement> {
+fn parse_revoke_statement(pair: pest::iterators::Pair<Rule>, options: &ParseOptions) -> Result<DdlStatement> {
     let mut actions = Vec::new();
     let mut resource = None;
     let mut principal = None;
@@ -148,13 +709,13 @@ fn parse_revoke_statement(pair: pest::iterators::Pair<Rule>) -> Result<DdlStatem
     for inner_pair in pair.into_inner() {
         match inner_pair.as_rule() {
             Rule::action_list => {
-                actions = parse_action_list(inner_pair)?;
+                actions = parse_action_list(inner_pair, options)?;
             },
             Rule::resource => {
                 resource = Some(parse_resource(inner_pair)?);
             },
             Rule::principal => {
-                principal = Some(parse_principal(inner_pair)?);
+                principal = Some(parse_principal(inner_pair, options)?);
             },
             _ => {},
         }
@@ -167,6 +728,45 @@ fn parse_revoke_statement(pair: pest::iterators::Pair<Rule>) -> Result<DdlStatem
     })
 }
 
+fn parse_grant_role_statement(pair: pest::iterators::Pair<Rule>) -> Result<DdlStatement> {
+    let mut names = pair
+        .into_inner()
+        .filter(|p| p.as_rule() == Rule::identifier)
+        .map(|p| p.as_str().to_string());
+
+    Ok(DdlStatement::GrantRole {
+        role: names.next().ok_or_else(|| anyhow!("Missing role name in GRANT ROLE"))?,
+        member: names.next().ok_or_else(|| anyhow!("Missing member role in GRANT ROLE"))?,
+    })
+}
+
+fn parse_deny_statement(pair: pest::iterators::Pair<Rule>, options: &ParseOptions) -> Result<DdlStatement> {
+    let mut actions = Vec::new();
+    let mut resource = None;
+    let mut principal = None;
+
+    for inner_pair in pair.into_inner() {
+        match inner_pair.as_rule() {
+            Rule::action_list => {
+                actions = parse_action_list(inner_pair, options)?;
+            },
+            Rule::resource => {
+                resource = Some(parse_resource(inner_pair)?);
+            },
+            Rule::principal => {
+                principal = Some(parse_principal(inner_pair, options)?);
+            },
+            _ => {},
+        }
+    }
+
+    Ok(DdlStatement::Deny {
+        actions,
+        resource: resource.ok_or_else(|| anyhow!("Missing resource in DENY"))?,
+        principal: principal.ok_or_else(|| anyhow!("Missing principal in DENY"))?,
+    })
+}
+
 fn parse_create_role_statement(pair: pest::iterators::Pair<Rule>) -> Result<DdlStatement> {
     for inner_pair in pair.into_inner() {
         if inner_pair.as_rule() == Rule::identifier {
@@ -178,6 +778,15 @@ fn parse_create_role_statement(pair: pest::iterators::Pair<Rule>) -> Result<DdlS
     Err(anyhow!("Missing role name in CREATE ROLE"))
 }
 
+fn parse_create_roles_statement(pair: pest::iterators::Pair<Rule>) -> Result<DdlStatement> {
+    let names = pair
+        .into_inner()
+        .filter(|p| p.as_rule() == Rule::identifier)
+        .map(|p| p.as_str().to_string())
+        .collect();
+    Ok(DdlStatement::CreateRoles { names })
+}
+
 fn parse_create_tag_statement(pair: pest::iterators::Pair<Rule>) -> Result<DdlStatement> {
     let mut name = None;
     let mut values = Vec::new();
@@ -222,108 +831,466 @@ fn parse_drop_tag_statement(pair: pest::iterators::Pair<Rule>) -> Result<DdlStat
     Err(anyhow!("Missing tag name in DROP TAG"))
 }
 
-fn parse_show_statement(pair: pest::iterators::Pair<Rule>) -> Result<DdlStatement> {
+fn parse_alter_role_statement(pair: pest::iterators::Pair<Rule>) -> Result<DdlStatement> {
+    let mut name = None;
+    let mut add_users = Vec::new();
+    let mut drop_users = Vec::new();
+
     for inner_pair in pair.into_inner() {
-        return match inner_pair.as_rule() {
-            Rule::show_permissions_statement => {
-                // TODO: Parse optional principal
-                Ok(DdlStatement::ShowPermissions { principal: None })
+        match inner_pair.as_rule() {
+            Rule::identifier => name = Some(inner_pair.as_str().to_string()),
+            Rule::add_users_clause | Rule::drop_users_clause => {
+                let users = match inner_pair.clone().into_inner().find(|p| p.as_rule() == Rule::string_list) {
+                    Some(list) => parse_string_list(list)?,
+                    None => Vec::new(),
+                };
+                if inner_pair.as_rule() == Rule::add_users_clause {
+                    add_users.extend(users);
+                } else {
+                    drop_users.extend(users);
+                }
             },
-            Rule::show_roles_statement => Ok(DdlStatement::ShowRoles),
-            Rule::show_tags_statement => Ok(DdlStatement::ShowTags),
-            _ => Err(anyhow!("Unknown SHOW statement type")),
-        };
+            _ => {},
+        }
     }
-    Err(anyhow!("Empty SHOW statement"))
+
+    Ok(DdlStatement::AlterRole {
+        name: name.ok_or_else(|| anyhow!("Missing role name in ALTER ROLE"))?,
+        add_users,
+        drop_users,
+    })
 }
 
-// Helper parsing functions
-fn parse_action_list(pair: pest::iterators::Pair<Rule>) -> Result<Vec<Action>> {
-    let mut actions = Vec::new();
+fn parse_register_location_statement(pair: pest::iterators::Pair<Rule>) -> Result<DdlStatement> {
     for inner_pair in pair.into_inner() {
-        if inner_pair.as_rule() == Rule::action {
-            actions.push(parse_action(inner_pair)?);
+        match inner_pair.as_rule() {
+            Rule::string_literal => {
+                return Ok(DdlStatement::RegisterLocation {
+                    path: canonical_location(&unquote_string_literal(inner_pair.as_str())),
+                });
+            },
+            Rule::s3_path => {
+                return Ok(DdlStatement::RegisterLocation {
+                    path: canonical_location(inner_pair.as_str()),
+                });
+            },
+            _ => {},
         }
     }
-    Ok(actions)
+    Err(anyhow!("Missing path in REGISTER LOCATION"))
 }
 
-fn parse_action(pair: pest::iterators::Pair<Rule>) -> Result<Action> {
-    match pair.as_str().to_uppercase().as_str() {
-        "SELECT" => Ok(Action::Select),
-        "INSERT" => Ok(Action::Insert), 
-        "UPDATE" => Ok(Action::Update),
-        "DELETE" => Ok(Action::Delete),
-        "CREATE_TABLE" => Ok(Action::CreateTable),
-        "DROP_TABLE" => Ok(Action::DropTable),
-        "ALTER_TABLE" => Ok(Action::AlterTable),
-        "DESCRIBE" => Ok(Action::Describe),
-        "DATA_LOCATION_ACCESS" => Ok(Action::DataLocationAccess),
-        _ => Err(anyhow!("Unknown action: {}", pair.as_str())),
+fn parse_register_table_statement(pair: pest::iterators::Pair<Rule>) -> Result<DdlStatement> {
+    let mut identifiers = Vec::new();
+    let mut location = None;
+
+    for inner_pair in pair.into_inner() {
+        match inner_pair.as_rule() {
+            Rule::object_name => identifiers.push(unquote_identifier(inner_pair.as_str())),
+            Rule::string_literal => location = Some(canonical_location(&unquote_string_literal(inner_pair.as_str()))),
+            Rule::s3_path => location = Some(canonical_location(inner_pair.as_str())),
+            _ => {},
+        }
+    }
+
+    match (identifiers.as_slice(), location) {
+        ([database, table], Some(location)) => Ok(DdlStatement::RegisterTable {
+            database: database.clone(),
+            table: table.clone(),
+            location,
+        }),
+        _ => Err(anyhow!("REGISTER TABLE needs database.table and a LOCATION")),
     }
 }
 
-fn parse_principal(pair: pest::iterators::Pair<Rule>) -> Result<Principal> {
+fn parse_define_group_statement(pair: pest::iterators::Pair<Rule>) -> Result<DdlStatement> {
+    let mut name = None;
+    let mut members = Vec::new();
+
     for inner_pair in pair.into_inner() {
-        return match inner_pair.as_rule() {
-            Rule::role_principal => {
-                for p in inner_pair.into_inner() {
-                    if p.as_rule() == Rule::identifier {
-                        return Ok(Principal::Role(p.as_str().to_string()));
-                    }
-                }
-                Err(anyhow!("Missing role name"))
-            },
-            Rule::user_principal => {
-                for p in inner_pair.into_inner() {
-                    if p.as_rule() == Rule::string_literal {
-                        let user = p.as_str().trim_matches('\'').to_string();
-                        return Ok(Principal::User(user));
-                    }
-                }
-                Err(anyhow!("Missing user name"))
-            },
-            Rule::group_principal => {
-                for p in inner_pair.into_inner() {
-                    if p.as_rule() == Rule::string_literal {
-                        let group = p.as_str().trim_matches('\'').to_string();
-                        return Ok(Principal::SamlGroup(group));
-                    }
-                }
-                Err(anyhow!("Missing group name"))
-            },
-            Rule::external_account_principal => {
-                for p in inner_pair.into_inner() {
-                    if p.as_rule() == Rule::string_literal {
-                        let account = p.as_str().trim_matches('\'').to_string();
-                        return Ok(Principal::ExternalAccount(account));
-                    }
-                }
-                Err(anyhow!("Missing external account"))
-            },
-            _ => Err(anyhow!("Unknown principal type")),
-        };
+        match inner_pair.as_rule() {
+            Rule::identifier => name = Some(inner_pair.as_str().to_string()),
+            Rule::string_list => members = parse_string_list(inner_pair)?,
+            _ => {},
+        }
     }
-    Err(anyhow!("Empty principal"))
+
+    Ok(DdlStatement::DefineGroup {
+        name: name.ok_or_else(|| anyhow!("Missing group name in DEFINE GROUP"))?,
+        members,
+    })
 }
 
-fn parse_resource(pair: pest::iterators::Pair<Rule>) -> Result<Resource> {
+fn parse_define_template_statement(pair: pest::iterators::Pair<Rule>) -> Result<DdlStatement> {
+    let mut name = None;
+    let mut body = None;
+
     for inner_pair in pair.into_inner() {
-        return match inner_pair.as_rule() {
-            Rule::database_resource => {
+        match inner_pair.as_rule() {
+            Rule::identifier => name = Some(inner_pair.as_str().to_string()),
+            Rule::template_body => body = Some(inner_pair.as_str().trim().to_string()),
+            _ => {},
+        }
+    }
+
+    let name = name.ok_or_else(|| anyhow!("Missing template name in DEFINE TEMPLATE"))?;
+    let body = body.ok_or_else(|| anyhow!("Missing body in DEFINE TEMPLATE"))?;
+
+    // Catch mistakes when the template is defined rather than when it is applied
+    expand_template(&body, &template_resource(), &Principal::Role(TEMPLATE_PRINCIPAL.to_string()))
+        .map_err(|e| anyhow!("Invalid template '{}': {}", name, e))?;
+
+    Ok(DdlStatement::DefineTemplate { name, body })
+}
+
+fn parse_apply_template_statement(pair: pest::iterators::Pair<Rule>, options: &ParseOptions) -> Result<DdlStatement> {
+    let mut name = None;
+    let mut resource = None;
+    let mut principal = None;
+
+    for inner_pair in pair.into_inner() {
+        match inner_pair.as_rule() {
+            Rule::identifier => name = Some(inner_pair.as_str().to_string()),
+            Rule::resource => resource = Some(parse_resource(inner_pair)?),
+            Rule::principal => principal = Some(parse_principal(inner_pair, options)?),
+            _ => {},
+        }
+    }
+
+    Ok(DdlStatement::ApplyTemplate {
+        name: name.ok_or_else(|| anyhow!("Missing template name in APPLY TEMPLATE"))?,
+        resource: resource.ok_or_else(|| anyhow!("Missing resource in APPLY TEMPLATE"))?,
+        principal: principal.ok_or_else(|| anyhow!("Missing principal in APPLY TEMPLATE"))?,
+    })
+}
+
+/// Stand-ins for `{resource}` and `{principal}` while a template body is parsed
+const TEMPLATE_DATABASE: &str = "__template";
+const TEMPLATE_TABLE: &str = "__resource";
+const TEMPLATE_PRINCIPAL: &str = "__template_principal";
+
+fn template_resource() -> Resource {
+    Resource::Table {
+        database: TEMPLATE_DATABASE.to_string(),
+        table: TEMPLATE_TABLE.to_string(),
+        columns: None,
+        catalog_id: None,
+    }
+}
+
+/// Expand a template body into GRANT statements on the given resource and
+/// principal. `{principal}` takes the applied principal wholesale, type
+/// included, so `TO ROLE {principal}` applied to a user grants that user.
+pub fn expand_template(body: &str, resource: &Resource, principal: &Principal) -> Result<Vec<DdlStatement>> {
+    let sql = body
+        .replace("{resource}", &format!("{}.{}", TEMPLATE_DATABASE, TEMPLATE_TABLE))
+        .replace("{principal}", TEMPLATE_PRINCIPAL);
+
+    parse_ddl_script(&sql)?
+        .into_iter()
+        .map(|statement| match statement {
+            DdlStatement::Grant {
+                actions,
+                resource: granted_resource,
+                principal: granted_principal,
+                grantable_actions,
+                row_filter,
+                principal_condition,
+                comment,
+            } => {
+                let is_placeholder = matches!(
+                    &granted_principal,
+                    Principal::Role(name) | Principal::User(name) | Principal::SamlGroup(name) | Principal::ExternalAccount(name)
+                        if name == TEMPLATE_PRINCIPAL
+                );
+                Ok(DdlStatement::Grant {
+                    actions,
+                    resource: substitute_template_resource(granted_resource, resource)?,
+                    principal: if is_placeholder { principal.clone() } else { granted_principal },
+                    grantable_actions,
+                    row_filter,
+                    principal_condition,
+                    comment,
+                })
+            },
+            other => Err(anyhow!("Templates may only contain GRANT statements, found {:?}", other)),
+        })
+        .collect()
+}
+
+/// The resource a templated grant applies to: `{resource}` becomes the
+/// applied resource, and `{resource}(a, b)` that table restricted to the
+/// listed columns. Any other resource is left as written.
+fn substitute_template_resource(granted: Resource, applied: &Resource) -> Result<Resource> {
+    match granted {
+        Resource::Table { database, table, columns: Some(columns), .. }
+            if database == TEMPLATE_DATABASE && table == TEMPLATE_TABLE =>
+        {
+            match applied {
+                Resource::Table { database, table, catalog_id, .. } => Ok(Resource::Table {
+                    database: database.clone(),
+                    table: table.clone(),
+                    columns: Some(columns),
+                    catalog_id: catalog_id.clone(),
+                }),
+                other => Err(anyhow!("{{resource}}({}) needs a table, not {:?}", columns.join(", "), other)),
+            }
+        },
+        granted if granted == template_resource() => Ok(applied.clone()),
+        granted => Ok(granted),
+    }
+}
+
+fn parse_set_access_mode_statement(pair: pest::iterators::Pair<Rule>) -> Result<DdlStatement> {
+    let mut mode = None;
+    let mut resource = None;
+
+    for inner_pair in pair.into_inner() {
+        match inner_pair.as_rule() {
+            Rule::access_mode => {
+                mode = Some(match inner_pair.as_str().to_uppercase().as_str() {
+                    "IAM_ONLY" => AccessMode::IamOnly,
+                    _ => AccessMode::LakeFormation,
+                });
+            },
+            Rule::resource => {
+                resource = Some(parse_resource(inner_pair)?);
+            },
+            _ => {},
+        }
+    }
+
+    Ok(DdlStatement::SetAccessMode {
+        resource: resource.ok_or_else(|| anyhow!("Missing resource in SET ACCESS MODE"))?,
+        mode: mode.ok_or_else(|| anyhow!("Missing access mode"))?,
+    })
+}
+
+fn parse_show_statement(pair: pest::iterators::Pair<Rule>, options: &ParseOptions) -> Result<DdlStatement> {
+    for inner_pair in pair.into_inner() {
+        return match inner_pair.as_rule() {
+            Rule::show_permissions_statement => {
+                let principal = inner_pair
+                    .into_inner()
+                    .find(|p| p.as_rule() == Rule::principal)
+                    .map(|p| parse_principal(p, options))
+                    .transpose()?;
+                Ok(DdlStatement::ShowPermissions { principal })
+            },
+            Rule::show_roles_statement => Ok(DdlStatement::ShowRoles),
+            Rule::show_tags_statement => Ok(DdlStatement::ShowTags),
+            _ => Err(anyhow!("Unknown SHOW statement type")),
+        };
+    }
+    Err(anyhow!("Empty SHOW statement"))
+}
+
+// Helper parsing functions
+fn parse_action_list(pair: pest::iterators::Pair<Rule>, options: &ParseOptions) -> Result<Vec<Action>> {
+    let mut actions = Vec::new();
+    for inner_pair in pair.into_inner() {
+        if inner_pair.as_rule() == Rule::action {
+            push_action(&mut actions, parse_action(inner_pair)?, options)?;
+        }
+    }
+    Ok(actions)
+}
+
+/// Parse a GRANT action list, returning the actions and those marked
+/// WITH GRANT OPTION
+fn parse_grant_action_list(
+    pair: pest::iterators::Pair<Rule>,
+    options: &ParseOptions,
+) -> Result<(Vec<Action>, HashSet<Action>)> {
+    let mut actions = Vec::new();
+    let mut grantable_actions = HashSet::new();
+    for grant_action in pair.into_inner() {
+        let mut action = None;
+        let mut grantable = false;
+        for inner_pair in grant_action.into_inner() {
+            match inner_pair.as_rule() {
+                Rule::action => action = Some(parse_action(inner_pair)?),
+                Rule::grant_option_clause => grantable = true,
+                _ => {},
+            }
+        }
+        let action = action.ok_or_else(|| anyhow!("Missing action in GRANT"))?;
+        if grantable {
+            grantable_actions.insert(action.clone());
+        }
+        push_action(&mut actions, action, options)?;
+    }
+    Ok((actions, grantable_actions))
+}
+
+/// Append an action, skipping or rejecting repeats per `strict_actions`
+fn push_action(actions: &mut Vec<Action>, action: Action, options: &ParseOptions) -> Result<()> {
+    if actions.contains(&action) {
+        if options.strict_actions {
+            return Err(anyhow!("Duplicate action in list: {:?}", action));
+        }
+        return Ok(());
+    }
+    actions.push(action);
+    Ok(())
+}
+
+fn parse_action(pair: pest::iterators::Pair<Rule>) -> Result<Action> {
+    match normalize_action_name(pair.as_str()).as_str() {
+        "SELECT" => Ok(Action::Select),
+        "INSERT" => Ok(Action::Insert), 
+        "UPDATE" => Ok(Action::Update),
+        "DELETE" => Ok(Action::Delete),
+        "CREATE_TABLE" => Ok(Action::CreateTable),
+        "DROP_TABLE" => Ok(Action::DropTable),
+        "ALTER_TABLE" => Ok(Action::AlterTable),
+        "DESCRIBE" => Ok(Action::Describe),
+        "DATA_LOCATION_ACCESS" => Ok(Action::DataLocationAccess),
+        "ASSOCIATE" => Ok(Action::Associate),
+        "ALL" => Ok(Action::All),
+        "GRANT_WITH_GRANT_OPTION" => Ok(Action::GrantWithGrantOption),
+        _ => Err(anyhow!("Unknown action: {}", pair.as_str())),
+    }
+}
+
+/// Uppercase an action name and join its words with `_`, so `create table`
+/// and `CREATE_TABLE` compare equal
+fn normalize_action_name(name: &str) -> String {
+    name.split(|c: char| c == '_' || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+        .to_uppercase()
+}
+
+fn parse_principal(pair: pest::iterators::Pair<Rule>, options: &ParseOptions) -> Result<Principal> {
+    for inner_pair in pair.into_inner() {
+        return match inner_pair.as_rule() {
+            Rule::role_principal => {
+                for p in inner_pair.into_inner() {
+                    if p.as_rule() == Rule::identifier {
+                        return Ok(Principal::Role(p.as_str().to_string()));
+                    }
+                }
+                Err(anyhow!("Missing role name"))
+            },
+            Rule::user_principal => {
+                for p in inner_pair.into_inner() {
+                    if p.as_rule() == Rule::string_literal {
+                        let user = unquote_string_literal(p.as_str());
+                        return Ok(Principal::User(user));
+                    }
+                }
+                Err(anyhow!("Missing user name"))
+            },
+            Rule::group_principal => {
+                for p in inner_pair.into_inner() {
+                    if p.as_rule() == Rule::string_literal {
+                        let group = unquote_string_literal(p.as_str());
+                        return Ok(Principal::SamlGroup(group));
+                    }
+                }
+                Err(anyhow!("Missing group name"))
+            },
+            Rule::external_account_principal => {
+                for p in inner_pair.into_inner() {
+                    if p.as_rule() == Rule::string_literal {
+                        let account = unquote_string_literal(p.as_str());
+                        return Ok(Principal::ExternalAccount(account));
+                    }
+                }
+                Err(anyhow!("Missing external account"))
+            },
+            Rule::tagged_principal => {
+                let mut tag_key = None;
+                let mut tag_values = Vec::new();
+                for p in inner_pair.into_inner() {
+                    match p.as_rule() {
+                        Rule::identifier => tag_key = Some(p.as_str().to_string()),
+                        Rule::tag_value => tag_values.push(parse_tag_value(p)),
+                        _ => {},
+                    }
+                }
+                Ok(Principal::TaggedPrincipal {
+                    tag_key: tag_key.ok_or_else(|| anyhow!("Missing tag key"))?,
+                    tag_values,
+                })
+            },
+            Rule::bare_user_principal => {
+                if !options.bare_principal_as_user {
+                    return Err(anyhow!(
+                        "Principal {} needs a type keyword (USER, ROLE, ...)",
+                        inner_pair.as_str()
+                    ));
+                }
+                Ok(Principal::User(unquote_identifier(inner_pair.as_str())))
+            },
+            _ => Err(anyhow!("Unknown principal type")),
+        };
+    }
+    Err(anyhow!("Empty principal"))
+}
+
+fn parse_resource(pair: pest::iterators::Pair<Rule>) -> Result<Resource> {
+    for inner_pair in pair.into_inner() {
+        return match inner_pair.as_rule() {
+            Rule::lf_tag_resource => {
                 for p in inner_pair.into_inner() {
                     if p.as_rule() == Rule::identifier {
+                        return Ok(Resource::LfTag {
+                            key: p.as_str().to_string(),
+                        });
+                    }
+                }
+                Err(anyhow!("Missing LF-Tag key"))
+            },
+            Rule::database_resource => {
+                for p in inner_pair.into_inner() {
+                    if p.as_rule() == Rule::object_name {
                         return Ok(Resource::Database {
-                            name: p.as_str().to_string(),
+                            name: unquote_identifier(p.as_str()),
+                            catalog_id: None,
                         });
                     }
                 }
                 Err(anyhow!("Missing database name"))
             },
+            Rule::all_tables_resource => {
+                let database = inner_pair
+                    .into_inner()
+                    .find(|p| p.as_rule() == Rule::object_name)
+                    .ok_or_else(|| anyhow!("Missing database name"))?;
+                Ok(Resource::AllTables {
+                    database: unquote_identifier(database.as_str()),
+                    catalog_id: None,
+                })
+            },
+            Rule::view_resource => {
+                let mut identifiers = inner_pair
+                    .into_inner()
+                    .filter(|p| p.as_rule() == Rule::object_name)
+                    .map(|p| unquote_identifier(p.as_str()));
+                Ok(Resource::View {
+                    database: identifiers.next().ok_or_else(|| anyhow!("Missing database name"))?,
+                    view: identifiers.next().ok_or_else(|| anyhow!("Missing view name"))?,
+                })
+            },
             Rule::table_resource => parse_table_resource(inner_pair),
+            Rule::tagged_resource_match => {
+                let conditions = inner_pair
+                    .into_inner()
+                    .find(|p| p.as_rule() == Rule::tag_conditions)
+                    .ok_or_else(|| anyhow!("Missing tag conditions"))?;
+                Ok(Resource::TaggedResource { tag_conditions: parse_tag_conditions(conditions) })
+            },
             Rule::data_location_resource => {
-                let path = inner_pair.as_str().trim_matches('\'').to_string();
-                Ok(Resource::DataLocation { path })
+                let location = inner_pair.into_inner().next()
+                    .ok_or_else(|| anyhow!("Missing data location path"))?;
+                let path = match location.as_rule() {
+                    Rule::string_literal => unquote_string_literal(location.as_str()),
+                    _ => location.as_str().to_string(),
+                };
+                Ok(Resource::DataLocation { path: canonical_location(&path) })
             },
             _ => Err(anyhow!("Unknown resource type")),
         };
@@ -339,8 +1306,8 @@ fn parse_table_resource(pair: pest::iterators::Pair<Rule>) -> Result<Resource> {
     let inner_pairs: Vec<_> = pair.into_inner().collect();
     
     if inner_pairs.len() >= 2 {
-        database = Some(inner_pairs[0].as_str().to_string());
-        table = Some(inner_pairs[1].as_str().to_string());
+        database = Some(unquote_identifier(inner_pairs[0].as_str()));
+        table = Some(unquote_identifier(inner_pairs[1].as_str()));
         
         if inner_pairs.len() > 2 && inner_pairs[2].as_rule() == Rule::column_list {
             columns = Some(parse_column_list(inner_pairs[2].clone())?);
@@ -351,6 +1318,7 @@ fn parse_table_resource(pair: pest::iterators::Pair<Rule>) -> Result<Resource> {
         database: database.ok_or_else(|| anyhow!("Missing database name"))?,
         table: table.ok_or_else(|| anyhow!("Missing table name"))?,
         columns,
+        catalog_id: None,
     })
 }
 
@@ -358,26 +1326,295 @@ fn parse_column_list(pair: pest::iterators::Pair<Rule>) -> Result<Vec<String>> {
     let mut columns = Vec::new();
     for inner_pair in pair.into_inner() {
         if matches!(inner_pair.as_rule(), Rule::column_name) {
-            columns.push(inner_pair.as_str().trim_matches('"').to_string());
+            columns.push(unquote_identifier(inner_pair.as_str()));
         }
     }
     Ok(columns)
 }
 
 fn parse_row_filter(pair: pest::iterators::Pair<Rule>) -> Result<RowFilter> {
-    // For now, just capture the raw expression
-    // TODO: Implement proper expression parsing
+    let expression = strip_trailing_trivia(pair.as_str()).to_string();
+    let ast = pair
+        .into_inner()
+        .find(|p| p.as_rule() == Rule::filter_expression)
+        .map(build_filter_expr)
+        .transpose()?;
+
+    Ok(RowFilter {
+        expression,
+        session_context: None,
+        ast,
+    })
+}
+
+/// Drop trailing whitespace and comments, which pest keeps in the span of a
+/// rule that ends in an optional repetition (e.g. `a = 1 -- note`)
+fn strip_trailing_trivia(text: &str) -> &str {
+    let mut text = text.trim_end();
+    loop {
+        if text.ends_with("*/") {
+            if let Some(start) = text.rfind("/*") {
+                text = text[..start].trim_end();
+                continue;
+            }
+        }
+
+        // A `--` outside string literals on the last line starts a comment
+        let line_start = text.rfind('\n').map_or(0, |i| i + 1);
+        let mut in_literal = false;
+        let mut comment_start = None;
+        for (i, c) in text[line_start..].char_indices() {
+            match c {
+                '\'' => in_literal = !in_literal,
+                '-' if !in_literal && text[line_start + i..].starts_with("--") => {
+                    comment_start = Some(line_start + i);
+                    break;
+                },
+                _ => {},
+            }
+        }
+        match comment_start {
+            Some(start) => text = text[..start].trim_end(),
+            None => return text,
+        }
+    }
+}
+
+fn parse_grant_condition(pair: pest::iterators::Pair<Rule>) -> Result<RowFilter> {
+    // Keep only the condition itself, without the WHEN keyword
+    let expression = pair
+        .into_inner()
+        .find(|p| p.as_rule() == Rule::condition_expression)
+        .ok_or_else(|| anyhow!("Missing condition after WHEN"))?;
+
     Ok(RowFilter {
-        expression: pair.as_str().to_string(),
+        expression: strip_trailing_trivia(expression.as_str()).to_string(),
         session_context: None,
+        ast: Some(build_filter_expr(expression)?),
     })
 }
 
+/// Parse a row filter (with or without its leading WHERE) or a WHEN
+/// condition into a `FilterExpr`
+pub fn parse_filter_expression(expression: &str) -> Result<FilterExpr> {
+    let pair = LakeSqlParser::parse(Rule::filter_input, expression)
+        .map_err(|e| {
+            let (position, message, expected) = describe_pest_error(expression, &e);
+            ParseError::at(expression, position, 0, message, expected)
+        })?
+        .next()
+        .and_then(|input| {
+            input
+                .into_inner()
+                .find(|p| matches!(p.as_rule(), Rule::filter_expression | Rule::condition_expression))
+        })
+        .ok_or_else(|| anyhow!("Empty filter expression"))?;
+    build_filter_expr(pair)
+}
+
+/// Build a `FilterExpr` from a `filter_expression` or `condition_expression`
+/// pair. The grammar yields a flat list of terms, so precedence is applied
+/// here: runs of AND are grouped first, then joined by OR.
+fn build_filter_expr(pair: pest::iterators::Pair<Rule>) -> Result<FilterExpr> {
+    let mut alternatives = Vec::new();
+    let mut current: Option<FilterExpr> = None;
+    let mut is_or = false;
+
+    for inner_pair in pair.into_inner() {
+        match inner_pair.as_rule() {
+            Rule::logical_op => is_or = inner_pair.as_str().eq_ignore_ascii_case("OR"),
+            Rule::filter_term | Rule::condition_term => {
+                let term = build_filter_term(inner_pair)?;
+                current = Some(match current.take() {
+                    Some(left) if !is_or => FilterExpr::And(Box::new(left), Box::new(term)),
+                    Some(left) => {
+                        alternatives.push(left);
+                        term
+                    },
+                    None => term,
+                });
+            },
+            _ => {},
+        }
+    }
+
+    alternatives.extend(current);
+    alternatives
+        .into_iter()
+        .reduce(|left, right| FilterExpr::Or(Box::new(left), Box::new(right)))
+        .ok_or_else(|| anyhow!("Empty filter expression"))
+}
+
+fn build_filter_term(pair: pest::iterators::Pair<Rule>) -> Result<FilterExpr> {
+    let mut operands = Vec::new();
+    let mut op = None;
+    let mut list = None;
+    // Set by IS [NOT] NULL, to whether NOT was given
+    let mut null_check = None;
+
+    for inner_pair in pair.into_inner() {
+        match inner_pair.as_rule() {
+            // Parenthesized group
+            Rule::filter_expression | Rule::condition_expression => return build_filter_expr(inner_pair),
+            // Operand of a leading NOT
+            Rule::filter_term | Rule::condition_term => {
+                return Ok(FilterExpr::Not(Box::new(build_filter_term(inner_pair)?)));
+            },
+            Rule::column_reference | Rule::value | Rule::builtin_value | Rule::session_context_ref | Rule::function_call => {
+                operands.push(build_filter_operand(inner_pair)?);
+            },
+            Rule::comparison_op => op = Some(parse_comparison_op(inner_pair.as_str())?),
+            Rule::is => null_check = Some(false),
+            Rule::not => null_check = null_check.map(|_| true),
+            Rule::r#in => list = Some(Vec::new()),
+            Rule::in_list => list = Some(
+                inner_pair.into_inner().map(build_filter_operand).collect::<Result<Vec<_>>>()?,
+            ),
+            _ => {},
+        }
+    }
+
+    if let Some(negated) = null_check {
+        let operand = operands.pop().filter(|_| operands.is_empty());
+        return operand
+            .map(|operand| FilterExpr::IsNull { operand, negated })
+            .ok_or_else(|| anyhow!("Malformed IS NULL in filter expression"));
+    }
+
+    if let Some(mut list) = list {
+        // `IN SESSION_CONTEXT('key')` without parentheses
+        if list.is_empty() && operands.len() == 2 {
+            list.extend(operands.pop());
+        }
+        let left = operands.pop().filter(|_| operands.is_empty());
+        return left
+            .map(|left| FilterExpr::In { left, list })
+            .ok_or_else(|| anyhow!("Malformed IN list in filter expression"));
+    }
+
+    match (operands.len(), op) {
+        (2, Some(op)) => {
+            let right = operands.pop().unwrap();
+            let left = operands.pop().unwrap();
+            Ok(FilterExpr::Comparison { left, op, right })
+        },
+        // A bare function call
+        (1, None) => Ok(FilterExpr::Value(operands.pop().unwrap())),
+        _ => Err(anyhow!("Malformed comparison in filter expression")),
+    }
+}
+
+fn build_filter_operand(pair: pest::iterators::Pair<Rule>) -> Result<FilterOperand> {
+    match pair.as_rule() {
+        Rule::value => match pair.into_inner().next() {
+            Some(inner) if inner.as_rule() == Rule::string_literal => {
+                Ok(FilterOperand::Literal(unquote_string_literal(inner.as_str())))
+            },
+            Some(inner) if inner.as_rule() == Rule::number => Ok(FilterOperand::Number(inner.as_str().to_string())),
+            _ => Ok(FilterOperand::Null),
+        },
+        Rule::builtin_value => match pair.into_inner().next().map(|p| p.as_rule()) {
+            Some(Rule::current_user) => Ok(FilterOperand::CurrentUser),
+            Some(Rule::current_timestamp) => Ok(FilterOperand::CurrentTimestamp),
+            _ => Ok(FilterOperand::CurrentDate),
+        },
+        Rule::column_reference => {
+            let parts: Vec<_> = pair.into_inner().map(|p| p.as_str()).collect();
+            Ok(FilterOperand::Column(parts.join(".")))
+        },
+        Rule::function_call => {
+            let mut inner = pair.into_inner();
+            let name = inner.next().map(|p| p.as_str().to_string()).unwrap_or_default();
+            Ok(FilterOperand::Function {
+                name,
+                args: inner.map(build_filter_operand).collect::<Result<Vec<_>>>()?,
+            })
+        },
+        Rule::session_context_ref => {
+            let mut key = None;
+            let mut default = None;
+            for inner_pair in pair.into_inner() {
+                match inner_pair.as_rule() {
+                    Rule::string_literal => key = Some(unquote_string_literal(inner_pair.as_str())),
+                    Rule::value => default = Some(Box::new(build_filter_operand(inner_pair)?)),
+                    _ => {},
+                }
+            }
+            Ok(FilterOperand::SessionContext {
+                key: key.ok_or_else(|| anyhow!("Missing key in SESSION_CONTEXT"))?,
+                default,
+            })
+        },
+        other => Err(anyhow!("Unexpected operand in filter expression: {:?}", other)),
+    }
+}
+
+fn parse_comparison_op(op: &str) -> Result<ComparisonOp> {
+    // `NOT LIKE` may be spread over several whitespace characters
+    let op = op.split_whitespace().collect::<Vec<_>>().join(" ").to_uppercase();
+    match op.as_str() {
+        "=" => Ok(ComparisonOp::Eq),
+        "!=" | "<>" => Ok(ComparisonOp::NotEq),
+        "<" => Ok(ComparisonOp::Lt),
+        "<=" => Ok(ComparisonOp::LtEq),
+        ">" => Ok(ComparisonOp::Gt),
+        ">=" => Ok(ComparisonOp::GtEq),
+        "LIKE" => Ok(ComparisonOp::Like),
+        "NOT LIKE" => Ok(ComparisonOp::NotLike),
+        other => Err(anyhow!("Unknown comparison operator: {}", other)),
+    }
+}
+
+/// Strip the surrounding quotes from a string literal and unescape `''`
+fn unquote_string_literal(literal: &str) -> String {
+    literal
+        .strip_prefix('\'')
+        .and_then(|s| s.strip_suffix('\''))
+        .unwrap_or(literal)
+        .replace("''", "'")
+}
+
+/// `key=value` / `key=(v1, v2)` pairs of a RESOURCES TAGGED expression
+fn parse_tag_conditions(pair: pest::iterators::Pair<Rule>) -> Vec<(String, Vec<String>)> {
+    pair.into_inner()
+        .filter(|p| p.as_rule() == Rule::tag_condition)
+        .filter_map(|condition| {
+            let mut inner = condition.into_inner();
+            let key = inner.next()?.as_str().to_string();
+            let values = inner
+                .next()?
+                .into_inner()
+                .filter(|p| p.as_rule() == Rule::tag_value)
+                .map(parse_tag_value)
+                .collect();
+            Some((key, values))
+        })
+        .collect()
+}
+
+/// A tag value, written either quoted ('finance') or bare (finance)
+fn parse_tag_value(pair: pest::iterators::Pair<Rule>) -> String {
+    match pair.into_inner().next() {
+        Some(value) if value.as_rule() == Rule::string_literal => unquote_string_literal(value.as_str()),
+        Some(value) => value.as_str().to_string(),
+        None => String::new(),
+    }
+}
+
+/// Strip the double quotes from a quoted identifier and unescape `""`;
+/// bare identifiers are returned unchanged
+fn unquote_identifier(identifier: &str) -> String {
+    match identifier.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Some(inner) => inner.replace("\"\"", "\""),
+        None => identifier.to_string(),
+    }
+}
+
 fn parse_string_list(pair: pest::iterators::Pair<Rule>) -> Result<Vec<String>> {
     let mut strings = Vec::new();
     for inner_pair in pair.into_inner() {
         if inner_pair.as_rule() == Rule::string_literal {
-            strings.push(inner_pair.as_str().trim_matches('\'').to_string());
+            strings.push(unquote_string_literal(inner_pair.as_str()));
         }
     }
     Ok(strings)
@@ -422,6 +1659,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_create_roles() {
+        let result = parse_ddl("CREATE ROLES analyst, engineer,auditor").unwrap();
+        assert_eq!(result, DdlStatement::CreateRoles {
+            names: vec!["analyst".to_string(), "engineer".to_string(), "auditor".to_string()],
+        });
+
+        // CREATE ROLE still names a single role
+        assert_eq!(parse_ddl("CREATE ROLE sales").unwrap(), DdlStatement::CreateRole { name: "sales".to_string() });
+    }
+
     #[test]
     fn test_create_tag() {
         let sql = "CREATE TAG department VALUES ('finance', 'marketing', 'engineering')";
@@ -435,4 +1683,1102 @@ mod tests {
             _ => panic!("Expected CreateTag statement"),
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_create_tag_multiline_values() {
+        let sql = "CREATE TAG region VALUES (\n    'us-east-1',\n    'us-west-2'\n  , 'eu-west-1',\r\n\t'eu-central-1',\n\n    'ap-southeast-2'\n)";
+        let result = parse_ddl(sql).unwrap();
+
+        match result {
+            DdlStatement::CreateTag { name, values } => {
+                assert_eq!(name, "region");
+                assert_eq!(values, vec!["us-east-1", "us-west-2", "eu-west-1", "eu-central-1", "ap-southeast-2"]);
+            },
+            _ => panic!("Expected CreateTag statement"),
+        }
+    }
+
+    #[test]
+    fn test_duplicate_actions_are_deduplicated() {
+        let sql = "GRANT SELECT, SELECT ON sales.orders TO ROLE x";
+        let result = parse_ddl(sql).unwrap();
+
+        match result {
+            DdlStatement::Grant { actions, .. } => {
+                assert_eq!(actions, vec![Action::Select]);
+            },
+            _ => panic!("Expected Grant statement"),
+        }
+    }
+
+    #[test]
+    fn test_duplicate_actions_rejected_in_strict_mode() {
+        let options = ParseOptions { strict_actions: true, ..ParseOptions::default() };
+
+        let sql = "GRANT SELECT, SELECT ON sales.orders TO ROLE x";
+        assert!(parse_ddl_with_options(sql, &options).is_err());
+
+        let sql = "GRANT SELECT, INSERT ON sales.orders TO ROLE x";
+        assert!(parse_ddl_with_options(sql, &options).is_ok());
+    }
+
+    #[test]
+    fn test_bind_params_user_arn_and_table() {
+        let params = vec![
+            "sales.orders".to_string(),
+            "arn:aws:iam::123456789012:user/alice".to_string(),
+        ];
+        let sql = bind_params("GRANT SELECT ON $1 TO USER '$2'", &params).unwrap();
+        let result = parse_ddl(&sql).unwrap();
+
+        match result {
+            DdlStatement::Grant { resource, principal, .. } => {
+                assert_eq!(resource, Resource::Table {
+                    database: "sales".to_string(),
+                    table: "orders".to_string(),
+                    columns: None,
+                    catalog_id: None,
+                });
+                assert_eq!(principal, Principal::User("arn:aws:iam::123456789012:user/alice".to_string()));
+            },
+            _ => panic!("Expected Grant statement"),
+        }
+    }
+
+    #[test]
+    fn test_bind_params_escapes_quotes() {
+        let params = vec!["o'brien@company.com' TO ROLE admin --".to_string()];
+        let sql = bind_params("GRANT SELECT ON sales.orders TO USER '$1'", &params).unwrap();
+        let result = parse_ddl(&sql).unwrap();
+
+        match result {
+            DdlStatement::Grant { principal, .. } => {
+                assert_eq!(principal, Principal::User("o'brien@company.com' TO ROLE admin --".to_string()));
+            },
+            _ => panic!("Expected Grant statement"),
+        }
+    }
+
+    #[test]
+    fn test_bind_params_rejects_non_identifier_bare_param() {
+        let params = vec!["orders TO ROLE admin".to_string()];
+        assert!(bind_params("GRANT SELECT ON sales.$1 TO ROLE analyst", &params).is_err());
+        assert!(bind_params("GRANT SELECT ON $2 TO ROLE analyst", &params).is_err());
+    }
+
+    #[test]
+    fn test_bind_params_ignores_quotes_in_comments_and_identifiers() {
+        let params = vec!["sales.orders TO ROLE x; GRANT ALL ON DATABASE prod TO ROLE attacker".to_string()];
+        for sql in [
+            "-- don't\nGRANT SELECT ON $1 TO ROLE analyst -- it's fine",
+            "/* don't */ GRANT SELECT ON $1 TO ROLE analyst /* it's fine */",
+            "GRANT SELECT ON $1 TO ROLE \"o'brien\"",
+        ] {
+            assert!(bind_params(sql, &params).is_err(), "{}", sql);
+        }
+
+        let params = vec!["sales.orders".to_string()];
+        let sql = bind_params("-- don't\nGRANT SELECT ON $1 TO ROLE analyst /* it's $2 */", &params).unwrap();
+        assert_eq!(sql, "-- don't\nGRANT SELECT ON sales.orders TO ROLE analyst /* it's $2 */");
+    }
+
+    #[test]
+    fn test_trailing_comment_captured_on_grant() {
+        let sql = "GRANT SELECT ON sales.orders TO ROLE analyst; -- needed for the weekly dashboard";
+        let result = parse_ddl(sql).unwrap();
+
+        match result {
+            DdlStatement::Grant { comment, .. } => {
+                assert_eq!(comment.as_deref(), Some("needed for the weekly dashboard"));
+            },
+            _ => panic!("Expected Grant statement"),
+        }
+
+        let sql = "GRANT SELECT ON sales.orders TO ROLE analyst -- without semicolon";
+        match parse_ddl(sql).unwrap() {
+            DdlStatement::Grant { comment, .. } => {
+                assert_eq!(comment.as_deref(), Some("without semicolon"));
+            },
+            _ => panic!("Expected Grant statement"),
+        }
+
+        match parse_ddl("GRANT SELECT ON sales.orders TO ROLE analyst").unwrap() {
+            DdlStatement::Grant { comment, .. } => assert_eq!(comment, None),
+            _ => panic!("Expected Grant statement"),
+        }
+    }
+
+    #[test]
+    fn test_register_location() {
+        for sql in [
+            "REGISTER LOCATION 's3://data-lake/sales'",
+            "REGISTER LOCATION s3://data-lake/sales",
+        ] {
+            let result = parse_ddl(sql).unwrap();
+            assert_eq!(result, DdlStatement::RegisterLocation {
+                path: "s3://data-lake/sales".to_string(),
+            });
+        }
+    }
+
+    #[test]
+    fn test_register_table() {
+        for sql in [
+            "REGISTER TABLE sales.orders LOCATION 's3://data-lake/sales/orders'",
+            "register table sales.orders location s3://data-lake/sales/orders",
+        ] {
+            let result = parse_ddl(sql).unwrap();
+            assert_eq!(result, DdlStatement::RegisterTable {
+                database: "sales".to_string(),
+                table: "orders".to_string(),
+                location: "s3://data-lake/sales/orders".to_string(),
+            });
+        }
+
+        let result = parse_ddl(r#"REGISTER TABLE "my-db"."2024_orders" LOCATION 's3://data-lake/orders'"#).unwrap();
+        assert_eq!(result, DdlStatement::RegisterTable {
+            database: "my-db".to_string(),
+            table: "2024_orders".to_string(),
+            location: "s3://data-lake/orders".to_string(),
+        });
+
+        assert!(parse_ddl("REGISTER TABLE orders LOCATION 's3://data-lake/orders'").is_err());
+    }
+
+    #[test]
+    fn test_grant_all() {
+        let sql = "GRANT ALL ON DATABASE analytics TO USER 'alice@company.com'";
+        let result = parse_ddl(sql).unwrap();
+
+        match result {
+            DdlStatement::Grant { actions, .. } => {
+                assert_eq!(actions, vec![Action::All]);
+            },
+            _ => panic!("Expected Grant statement"),
+        }
+    }
+
+    #[test]
+    fn test_multi_word_action_separators() {
+        for action in ["CREATE_TABLE", "CREATE TABLE", "create table", "Create Table", "create_table", "CREATE \t TABLE"] {
+            let sql = format!("GRANT {}, DATA LOCATION ACCESS ON DATABASE sales TO ROLE admin", action);
+            match parse_ddl(&sql).unwrap() {
+                DdlStatement::Grant { actions, .. } => {
+                    assert_eq!(actions, vec![Action::CreateTable, Action::DataLocationAccess], "{}", action);
+                },
+                _ => panic!("Expected Grant statement"),
+            }
+        }
+
+        assert!(parse_ddl("GRANT CREATETABLE ON DATABASE sales TO ROLE admin").is_err());
+    }
+
+    #[test]
+    fn test_per_action_grant_option() {
+        let grantable_of = |sql: &str| match parse_ddl(sql).unwrap() {
+            DdlStatement::Grant { actions, grantable_actions, .. } => (actions, grantable_actions),
+            other => panic!("Expected Grant, got {:?}", other),
+        };
+
+        let (actions, grantable) = grantable_of("GRANT SELECT WITH GRANT OPTION, INSERT ON sales.orders TO ROLE analyst");
+        assert_eq!(actions, vec![Action::Select, Action::Insert]);
+        assert_eq!(grantable, HashSet::from([Action::Select]));
+
+        // A trailing WITH GRANT OPTION covers every action
+        let (_, grantable) = grantable_of("GRANT SELECT, INSERT ON sales.orders TO ROLE analyst WITH GRANT OPTION");
+        assert_eq!(grantable, HashSet::from([Action::Select, Action::Insert]));
+
+        let (_, grantable) = grantable_of("GRANT SELECT, INSERT ON sales.orders TO ROLE analyst");
+        assert!(grantable.is_empty());
+    }
+
+    #[test]
+    fn test_grant_option_positions() {
+        for sql in [
+            "GRANT SELECT ON sales.orders TO ROLE analyst WITH GRANT OPTION",
+            "GRANT SELECT ON sales.orders TO ROLE analyst WITH GRANT OPTION WHERE region = 'us'",
+            "GRANT SELECT ON sales.orders TO ROLE analyst WHERE region = 'us' WITH GRANT OPTION",
+            "GRANT SELECT ON sales.orders TO ROLE analyst WHEN SESSION_CONTEXT('mfa') = 'true' WITH GRANT OPTION",
+            "GRANT SELECT WITH GRANT OPTION ON sales.orders TO ROLE analyst",
+        ] {
+            match parse_ddl(sql) {
+                Ok(DdlStatement::Grant { grantable_actions, .. }) => {
+                    assert_eq!(grantable_actions, HashSet::from([Action::Select]), "{}", sql);
+                },
+                other => panic!("Expected Grant for {}, got {:?}", sql, other),
+            }
+        }
+
+        // Without the clause nothing is grantable
+        let plain = parse_ddl("GRANT SELECT ON sales.orders TO ROLE analyst").unwrap();
+        assert!(!plain.to_permission().unwrap().grant_option());
+
+        for sql in [
+            "GRANT SELECT ON sales.orders WITH GRANT OPTION TO ROLE analyst",
+            "WITH GRANT OPTION GRANT SELECT ON sales.orders TO ROLE analyst",
+            "GRANT SELECT ON sales.orders TO ROLE analyst WITH GRANT OPTION WHERE region = 'us' WITH GRANT OPTION",
+            "GRANT SELECT ON sales.orders TO ROLE analyst WITH GRANT",
+        ] {
+            assert!(parse_ddl(sql).is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_revoke_all() {
+        let sql = "REVOKE ALL ON sales.orders FROM ROLE analyst";
+        let result = parse_ddl(sql).unwrap();
+
+        match result {
+            DdlStatement::Revoke { actions, .. } => {
+                assert_eq!(actions, vec![Action::All]);
+            },
+            _ => panic!("Expected Revoke statement"),
+        }
+    }
+
+    #[test]
+    fn test_grant_role() {
+        let result = parse_ddl("GRANT ROLE analyst TO ROLE senior_analyst").unwrap();
+        assert_eq!(result, DdlStatement::GrantRole {
+            role: "analyst".to_string(),
+            member: "senior_analyst".to_string(),
+        });
+        assert!(result.to_permission().is_err());
+
+        // Still an ordinary grant when the actions come first
+        assert!(matches!(
+            parse_ddl("GRANT SELECT ON sales.orders TO ROLE analyst").unwrap(),
+            DdlStatement::Grant { .. }
+        ));
+        assert!(parse_ddl("GRANT ROLE analyst TO USER 'alice'").is_err());
+    }
+
+    #[test]
+    fn test_deny() {
+        let sql = "DENY SELECT ON sales.orders TO ROLE intern";
+        let result = parse_ddl(sql).unwrap();
+
+        match &result {
+            DdlStatement::Deny { actions, resource, principal } => {
+                assert_eq!(actions, &vec![Action::Select]);
+                assert_eq!(resource, &Resource::Table {
+                    database: "sales".to_string(),
+                    table: "orders".to_string(),
+                    columns: None,
+                    catalog_id: None,
+                });
+                assert_eq!(principal, &Principal::Role("intern".to_string()));
+            },
+            other => panic!("Expected Deny statement, got {:?}", other),
+        }
+
+        let permission = result.to_permission().unwrap();
+        assert_eq!(permission.effect, Effect::Deny);
+        assert!(permission.grantable_actions.is_empty());
+    }
+
+    #[test]
+    fn test_define_and_expand_template() {
+        let sql = "DEFINE TEMPLATE read_access AS (GRANT SELECT, DESCRIBE ON {resource} TO ROLE {principal}; \
+                   GRANT DESCRIBE ON DATABASE sales TO ROLE {principal} WITH GRANT OPTION)";
+        let body = match parse_ddl(sql).unwrap() {
+            DdlStatement::DefineTemplate { name, body } => {
+                assert_eq!(name, "read_access");
+                body
+            },
+            other => panic!("Expected DefineTemplate statement, got {:?}", other),
+        };
+
+        let resource = Resource::View { database: "sales".to_string(), view: "monthly".to_string() };
+        let principal = Principal::SamlGroup("analysts".to_string());
+        let grants = expand_template(&body, &resource, &principal).unwrap();
+        assert_eq!(grants.len(), 2);
+        match &grants[0] {
+            DdlStatement::Grant { resource: r, principal: p, actions, .. } => {
+                assert_eq!(r, &resource);
+                assert_eq!(p, &principal);
+                assert_eq!(actions, &vec![Action::Select, Action::Describe]);
+            },
+            other => panic!("Expected Grant statement, got {:?}", other),
+        }
+        match &grants[1] {
+            DdlStatement::Grant { resource: r, principal: p, grantable_actions, .. } => {
+                assert_eq!(r, &Resource::Database { name: "sales".to_string(), catalog_id: None });
+                assert_eq!(p, &principal);
+                assert!(grantable_actions.contains(&Action::Describe));
+            },
+            other => panic!("Expected Grant statement, got {:?}", other),
+        }
+
+        let applied = parse_ddl("APPLY TEMPLATE read_access ON DATABASE sales TO ROLE analyst").unwrap();
+        assert_eq!(applied, DdlStatement::ApplyTemplate {
+            name: "read_access".to_string(),
+            resource: Resource::Database { name: "sales".to_string(), catalog_id: None },
+            principal: Principal::Role("analyst".to_string()),
+        });
+
+        // Only GRANTs may appear in a template
+        let err = parse_ddl("DEFINE TEMPLATE cleanup AS (DROP ROLE {principal})").unwrap_err();
+        assert!(err.to_string().contains("only contain GRANT"));
+    }
+
+    #[test]
+    fn test_template_resource_with_columns() {
+        let body = match parse_ddl("DEFINE TEMPLATE pii_free AS (GRANT SELECT ON {resource}(id, region) TO ROLE {principal})").unwrap() {
+            DdlStatement::DefineTemplate { body, .. } => body,
+            other => panic!("Expected DefineTemplate statement, got {:?}", other),
+        };
+        let orders = Resource::Table {
+            database: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            catalog_id: Some("123456789012".to_string()),
+        };
+        let principal = Principal::Role("analyst".to_string());
+
+        let statements = expand_template(&body, &orders, &principal).unwrap();
+        match &statements[0] {
+            DdlStatement::Grant { resource, .. } => assert_eq!(resource, &Resource::Table {
+                database: "sales".to_string(),
+                table: "orders".to_string(),
+                columns: Some(vec!["id".to_string(), "region".to_string()]),
+                catalog_id: Some("123456789012".to_string()),
+            }),
+            other => panic!("Expected Grant statement, got {:?}", other),
+        }
+
+        // Columns only make sense on a table
+        let sales = Resource::Database { name: "sales".to_string(), catalog_id: None };
+        let err = expand_template(&body, &sales, &principal).unwrap_err();
+        assert!(err.to_string().contains("needs a table"), "{}", err);
+    }
+
+    #[test]
+    fn test_stream_keeps_template_body_together() {
+        let sql = "DEFINE TEMPLATE t AS (GRANT SELECT ON {resource} TO ROLE {principal}; GRANT DESCRIBE ON {resource} TO ROLE {principal});\n\
+                   APPLY TEMPLATE t ON sales.orders TO ROLE analyst;";
+        let statements: Vec<_> = parse_ddl_stream(sql).collect::<Result<_>>().unwrap();
+        assert_eq!(statements.len(), 2);
+        assert!(matches!(statements[0], DdlStatement::DefineTemplate { .. }));
+        assert_eq!(parse_ddl_script(sql).unwrap(), statements);
+    }
+
+    #[test]
+    fn test_grant_on_view() {
+        let result = parse_ddl("GRANT SELECT ON VIEW sales.monthly TO ROLE analyst").unwrap();
+        match result {
+            DdlStatement::Grant { resource, .. } => {
+                assert_eq!(resource, Resource::View {
+                    database: "sales".to_string(),
+                    view: "monthly".to_string(),
+                });
+            },
+            _ => panic!("Expected Grant statement"),
+        }
+
+        // A database whose name starts with "view" is still a table grant
+        match parse_ddl("GRANT SELECT ON viewsdb.orders TO ROLE analyst").unwrap() {
+            DdlStatement::Grant { resource, .. } => {
+                assert!(matches!(resource, Resource::Table { ref database, .. } if database == "viewsdb"));
+            },
+            _ => panic!("Expected Grant statement"),
+        }
+    }
+
+    #[test]
+    fn test_grant_on_lf_tag() {
+        let sql = "GRANT ASSOCIATE, DESCRIBE ON LF-TAG classification TO ROLE data_steward";
+        let result = parse_ddl(sql).unwrap();
+
+        match result {
+            DdlStatement::Grant { actions, resource, principal, .. } => {
+                assert_eq!(actions, vec![Action::Associate, Action::Describe]);
+                assert_eq!(resource, Resource::LfTag { key: "classification".to_string() });
+                assert_eq!(principal, Principal::Role("data_steward".to_string()));
+            },
+            _ => panic!("Expected Grant statement"),
+        }
+    }
+
+    #[test]
+    fn test_set_access_mode() {
+        let result = parse_ddl("SET ACCESS MODE IAM_ONLY ON sales.orders").unwrap();
+        assert_eq!(result, DdlStatement::SetAccessMode {
+            resource: Resource::Table {
+                database: "sales".to_string(),
+                table: "orders".to_string(),
+                columns: None,
+                catalog_id: None,
+            },
+            mode: AccessMode::IamOnly,
+        });
+
+        let result = parse_ddl("set access mode lake_formation on DATABASE sales").unwrap();
+        assert_eq!(result, DdlStatement::SetAccessMode {
+            resource: Resource::Database { name: "sales".to_string(), catalog_id: None },
+            mode: AccessMode::LakeFormation,
+        });
+    }
+
+    fn data_location_of(sql: &str) -> String {
+        match parse_ddl(sql).unwrap() {
+            DdlStatement::Grant { resource: Resource::DataLocation { path }, .. } => path,
+            other => panic!("Expected data location grant, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_data_location_paths() {
+        assert_eq!(
+            data_location_of("GRANT DATA_LOCATION_ACCESS ON 's3://data-lake/sales/' TO ROLE etl"),
+            "s3://data-lake/sales"
+        );
+        assert_eq!(
+            data_location_of("GRANT DATA_LOCATION_ACCESS ON s3://data-lake/sales/ TO ROLE etl"),
+            "s3://data-lake/sales"
+        );
+        assert_eq!(
+            data_location_of("GRANT DATA_LOCATION_ACCESS ON 's3://data-lake/sales reports/' TO ROLE etl"),
+            "s3://data-lake/sales reports"
+        );
+        assert_eq!(
+            data_location_of("GRANT DATA_LOCATION_ACCESS ON 's3://data-lake/o''brien/' TO ROLE etl"),
+            "s3://data-lake/o'brien"
+        );
+
+        // Scheme case and doubled slashes are normalized too
+        assert_eq!(
+            data_location_of("GRANT DATA_LOCATION_ACCESS ON S3://data-lake//sales/ TO ROLE etl"),
+            "s3://data-lake/sales"
+        );
+        assert_eq!(
+            parse_ddl("REGISTER LOCATION 'S3://data-lake/sales/'").unwrap(),
+            DdlStatement::RegisterLocation { path: "s3://data-lake/sales".to_string() }
+        );
+    }
+
+    #[test]
+    fn test_parse_script() {
+        let sql = "CREATE ROLE analyst;\n\
+                   GRANT SELECT ON sales.orders TO ROLE analyst; -- reporting\n\
+                   REVOKE INSERT ON sales.orders FROM ROLE analyst;\n";
+        let statements = parse_ddl_script(sql).unwrap();
+
+        assert_eq!(statements.len(), 3);
+        assert!(matches!(statements[0], DdlStatement::CreateRole { .. }));
+        match &statements[1] {
+            DdlStatement::Grant { comment, .. } => assert_eq!(comment.as_deref(), Some("reporting")),
+            other => panic!("Expected Grant statement, got {:?}", other),
+        }
+        assert!(matches!(statements[2], DdlStatement::Revoke { .. }));
+
+        assert!(parse_ddl_script("").unwrap().is_empty());
+        assert!(parse_ddl_script("CREATE ROLE a CREATE ROLE b").is_err());
+    }
+
+    #[test]
+    fn test_parse_stream() {
+        let sql = "-- roles first\n\
+                   CREATE ROLE analyst;\n\
+                   GRANT SELECT ON sales.orders TO ROLE analyst; -- reporting\n\
+                   GRANT BOGUS ON sales.orders TO ROLE analyst;\n\
+                   /* tags; later */ CREATE TAG region VALUES ('us;east', 'eu')\n";
+        let mut stream = parse_ddl_stream(sql);
+
+        assert!(matches!(stream.next(), Some(Ok(DdlStatement::CreateRole { .. }))));
+        match stream.next() {
+            Some(Ok(DdlStatement::Grant { comment, .. })) => assert_eq!(comment.as_deref(), Some("reporting")),
+            other => panic!("Expected Grant statement, got {:?}", other),
+        }
+
+        // A bad statement surfaces as an error without ending the stream
+        let error = stream.next().unwrap().unwrap_err();
+        assert!(error.to_string().starts_with("Statement 3:"));
+        match stream.next() {
+            Some(Ok(DdlStatement::CreateTag { values, .. })) => assert_eq!(values, vec!["us;east", "eu"]),
+            other => panic!("Expected CreateTag statement, got {:?}", other),
+        }
+        assert!(stream.next().is_none());
+
+        assert!(parse_ddl_stream("  -- nothing here\n").next().is_none());
+    }
+
+    #[test]
+    fn test_parse_script_collects_every_error() {
+        let sql = "CREATE ROLE analyst;\n\
+                   GRANT SELEKT ON sales.orders TO ROLE analyst;\n\
+                   GRANT SELECT ON sales.orders TO ROLE analyst;\n\
+                   CREATE TAG region VALUES;\n\
+                   REVOKE SELECT ON sales.orders FROM ROLE analyst";
+        let (statements, errors) = parse_ddl_script_collect(sql);
+
+        assert_eq!(statements.len(), 3);
+        assert!(matches!(statements[0], DdlStatement::CreateRole { .. }));
+        assert!(matches!(statements[1], DdlStatement::Grant { .. }));
+        assert!(matches!(statements[2], DdlStatement::Revoke { .. }));
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!((errors[0].statement_index, errors[0].line, errors[0].column), (1, 2, 7));
+        assert_eq!((errors[1].statement_index, errors[1].line), (3, 4));
+        assert!(errors[1].to_string().starts_with("unexpected token at line 4 col"));
+
+        let (statements, errors) = parse_ddl_script_collect("CREATE ROLE a; -- done\n");
+        assert_eq!(statements.len(), 1);
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_bare_quoted_principal_as_user() {
+        let options = ParseOptions {
+            bare_principal_as_user: true,
+            ..ParseOptions::default()
+        };
+        let principal_of = |sql: &str| match parse_ddl_with_options(sql, &options).unwrap() {
+            DdlStatement::Grant { principal, .. } | DdlStatement::Revoke { principal, .. } => principal,
+            other => panic!("Expected Grant or Revoke, got {:?}", other),
+        };
+
+        assert_eq!(
+            principal_of("GRANT SELECT ON sales.orders TO \"alice@co.com\""),
+            Principal::User("alice@co.com".to_string())
+        );
+        assert_eq!(
+            principal_of("REVOKE SELECT ON sales.orders FROM \"alice@co.com\""),
+            Principal::User("alice@co.com".to_string())
+        );
+
+        // Explicit forms are unaffected
+        assert_eq!(
+            principal_of("GRANT SELECT ON sales.orders TO USER 'alice@co.com'"),
+            Principal::User("alice@co.com".to_string())
+        );
+        assert_eq!(
+            principal_of("GRANT SELECT ON sales.orders TO ROLE analyst"),
+            Principal::Role("analyst".to_string())
+        );
+
+        // Off by default
+        assert!(parse_ddl("GRANT SELECT ON sales.orders TO \"alice@co.com\"").is_err());
+    }
+
+    #[test]
+    fn test_grant_with_session_condition() {
+        let sql = "GRANT SELECT ON sales.orders TO ROLE analyst WHEN SESSION_CONTEXT('mfa')='true'";
+        match parse_ddl(sql).unwrap() {
+            DdlStatement::Grant { principal_condition, row_filter, .. } => {
+                assert!(row_filter.is_none());
+                assert_eq!(principal_condition.unwrap().expression, "SESSION_CONTEXT('mfa')='true'");
+            },
+            other => panic!("Expected Grant, got {:?}", other),
+        }
+
+        // Conditions compare session context, not columns
+        assert!(parse_ddl("GRANT SELECT ON sales.orders TO ROLE analyst WHEN region = 'us'").is_err());
+    }
+
+    #[test]
+    fn test_session_context_with_default() {
+        let sql = "GRANT SELECT ON sales.orders TO ROLE analyst WHERE region = SESSION_CONTEXT('user_region', 'west')";
+        match parse_ddl(sql).unwrap() {
+            DdlStatement::Grant { row_filter, .. } => {
+                assert!(row_filter.unwrap().expression.ends_with("SESSION_CONTEXT('user_region', 'west')"));
+            },
+            other => panic!("Expected Grant, got {:?}", other),
+        }
+
+        assert!(parse_ddl("GRANT SELECT ON sales.orders TO ROLE analyst WHEN SESSION_CONTEXT('mfa', 'false') = 'true'").is_ok());
+    }
+
+    #[test]
+    fn test_parse_ddl_many() {
+        let sql = "CREATE ROLE analyst; GRANT SELECT ON sales.orders TO ROLE analyst;";
+        let statements = parse_ddl_many(sql).unwrap();
+        assert_eq!(statements.len(), 2);
+        assert!(matches!(statements[0], DdlStatement::CreateRole { .. }));
+        assert!(matches!(statements[1], DdlStatement::Grant { .. }));
+
+        // Blank statements and a missing final semicolon are fine
+        let statements = parse_ddl_many(";CREATE ROLE a;; ;CREATE ROLE b").unwrap();
+        assert_eq!(statements, vec![
+            DdlStatement::CreateRole { name: "a".to_string() },
+            DdlStatement::CreateRole { name: "b".to_string() },
+        ]);
+        assert!(parse_ddl_many("  ").unwrap().is_empty());
+
+        // parse_ddl still wants exactly one statement
+        assert!(parse_ddl("CREATE ROLE a;").is_ok());
+        let err = parse_ddl(sql).unwrap_err();
+        assert!(err.to_string().contains("found 2"));
+        assert!(parse_ddl(";").is_err());
+    }
+
+    #[test]
+    fn test_comments_are_ignored() {
+        let expected = parse_ddl("GRANT SELECT ON sales.orders TO ROLE analyst").unwrap();
+
+        for sql in [
+            "-- grant read\nGRANT SELECT ON sales.orders TO ROLE analyst",
+            "/* grant read */ GRANT SELECT ON sales.orders TO ROLE analyst",
+            "GRANT SELECT /* reporting only */ ON sales.orders\n-- the analyst role\nTO ROLE analyst",
+            "GRANT SELECT ON sales.orders TO ROLE analyst /* end of file */",
+        ] {
+            assert_eq!(parse_ddl(sql).unwrap(), expected, "{}", sql);
+        }
+
+        let statements = parse_ddl_many("-- roles\nCREATE ROLE a; /* next */ CREATE ROLE b;\n-- done\n").unwrap();
+        assert_eq!(statements.len(), 2);
+    }
+
+    #[test]
+    fn test_define_group() {
+        let sql = "DEFINE GROUP analysts AS ('alice', 'arn:aws:iam::123456789012:user/bob')";
+        assert_eq!(parse_ddl(sql).unwrap(), DdlStatement::DefineGroup {
+            name: "analysts".to_string(),
+            members: vec!["alice".to_string(), "arn:aws:iam::123456789012:user/bob".to_string()],
+        });
+
+        assert!(parse_ddl("DEFINE GROUP analysts AS ()").is_err());
+    }
+
+    #[test]
+    fn test_show_permissions_for_principal() {
+        assert_eq!(
+            parse_ddl("SHOW PERMISSIONS FOR ROLE analyst").unwrap(),
+            DdlStatement::ShowPermissions { principal: Some(Principal::Role("analyst".to_string())) },
+        );
+        assert_eq!(
+            parse_ddl("show permissions for USER 'alice@company.com'").unwrap(),
+            DdlStatement::ShowPermissions { principal: Some(Principal::User("alice@company.com".to_string())) },
+        );
+        assert_eq!(parse_ddl("SHOW PERMISSIONS").unwrap(), DdlStatement::ShowPermissions { principal: None });
+        assert!(parse_ddl("SHOW PERMISSIONS FOR").is_err());
+    }
+
+    #[test]
+    fn test_alter_role() {
+        assert_eq!(
+            parse_ddl("ALTER ROLE analyst ADD USER 'alice@co', 'bob@co'").unwrap(),
+            DdlStatement::AlterRole {
+                name: "analyst".to_string(),
+                add_users: vec!["alice@co".to_string(), "bob@co".to_string()],
+                drop_users: vec![],
+            },
+        );
+        assert_eq!(
+            parse_ddl("alter role analyst drop user 'alice@co' add user 'carol@co'").unwrap(),
+            DdlStatement::AlterRole {
+                name: "analyst".to_string(),
+                add_users: vec!["carol@co".to_string()],
+                drop_users: vec!["alice@co".to_string()],
+            },
+        );
+        assert!(parse_ddl("ALTER ROLE analyst").is_err());
+    }
+
+    #[test]
+    fn test_principal_kinds() {
+        let principal = |sql: &str| parse_ddl(sql).unwrap().to_permission().unwrap().principal;
+        assert_eq!(principal("GRANT SELECT ON DATABASE sales TO ROLE analyst"), Principal::Role("analyst".to_string()));
+        assert_eq!(principal("GRANT SELECT ON DATABASE sales TO USER 'alice@co'"), Principal::User("alice@co".to_string()));
+        assert_eq!(principal("GRANT SELECT ON DATABASE sales TO GROUP 'org:finance'"), Principal::SamlGroup("org:finance".to_string()));
+        assert_eq!(
+            principal("GRANT SELECT ON DATABASE sales TO EXTERNAL_ACCOUNT '123456789012'"),
+            Principal::ExternalAccount("123456789012".to_string()),
+        );
+        assert_eq!(
+            principal("grant select on database sales to external_account '123456789012'"),
+            Principal::ExternalAccount("123456789012".to_string()),
+        );
+    }
+
+    #[test]
+    fn test_action_keywords_round_trip() {
+        for action in Action::CONCRETE.iter().chain([&Action::All]) {
+            let sql = format!("GRANT {} ON DATABASE sales TO ROLE analyst", action.to_ddl_keyword());
+            match parse_ddl(&sql).unwrap() {
+                DdlStatement::Grant { actions, .. } => assert_eq!(actions, vec![action.clone()], "{}", sql),
+                other => panic!("Expected a GRANT, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_tagged_principal() {
+        let principal = |sql: &str| parse_ddl(sql).unwrap().to_permission().unwrap().principal;
+        assert_eq!(
+            principal("GRANT SELECT ON sales.orders TO TAGGED department='finance','hr'"),
+            Principal::TaggedPrincipal {
+                tag_key: "department".to_string(),
+                tag_values: vec!["finance".to_string(), "hr".to_string()],
+            },
+        );
+        assert_eq!(
+            principal("GRANT SELECT ON sales.orders TO TAGGED classification = 'confidential' WITH GRANT OPTION"),
+            Principal::TaggedPrincipal {
+                tag_key: "classification".to_string(),
+                tag_values: vec!["confidential".to_string()],
+            },
+        );
+        assert!(parse_ddl("GRANT SELECT ON sales.orders TO TAGGED department").is_err());
+    }
+
+    #[test]
+    fn test_tagged_resource() {
+        let resource = |sql: &str| parse_ddl(sql).unwrap().to_permission().unwrap().resource;
+        assert_eq!(
+            resource("GRANT SELECT ON RESOURCES TAGGED department='finance' AND classification='confidential' TO ROLE analyst"),
+            Resource::TaggedResource {
+                tag_conditions: vec![
+                    ("department".to_string(), vec!["finance".to_string()]),
+                    ("classification".to_string(), vec!["confidential".to_string()]),
+                ],
+            },
+        );
+        assert_eq!(
+            resource("GRANT SELECT ON resources tagged department=('finance', 'hr') TO ROLE analyst"),
+            Resource::TaggedResource {
+                tag_conditions: vec![("department".to_string(), vec!["finance".to_string(), "hr".to_string()])],
+            },
+        );
+    }
+
+    #[test]
+    fn test_column_level_grant() {
+        let columns = |sql: &str| match parse_ddl(sql).unwrap().to_permission().unwrap().resource {
+            Resource::Table { columns, .. } => columns,
+            other => panic!("Expected a table, got {:?}", other),
+        };
+        assert_eq!(
+            columns("GRANT SELECT ON sales.orders(order_id, region) TO ROLE analyst"),
+            Some(vec!["order_id".to_string(), "region".to_string()]),
+        );
+        assert_eq!(
+            columns(r#"GRANT SELECT ON sales.orders ( "order_id" , region ) TO ROLE analyst"#),
+            Some(vec!["order_id".to_string(), "region".to_string()]),
+        );
+        assert_eq!(columns("GRANT SELECT ON sales.orders TO ROLE analyst"), None);
+
+        assert!(parse_ddl("GRANT SELECT ON sales.orders() TO ROLE analyst").is_err());
+        assert!(parse_ddl("GRANT SELECT ON sales.orders(order_id,) TO ROLE analyst").is_err());
+    }
+
+    #[test]
+    fn test_all_tables_in_database() {
+        let permission = parse_ddl("GRANT SELECT ON ALL TABLES IN DATABASE sales TO ROLE analyst")
+            .unwrap()
+            .to_permission()
+            .unwrap();
+        assert_eq!(permission.resource, Resource::AllTables { database: "sales".to_string(), catalog_id: None });
+
+        let revoke = parse_ddl(r#"revoke select on all tables in database "raw-events" from role analyst"#).unwrap();
+        assert!(matches!(revoke, DdlStatement::Revoke { resource: Resource::AllTables { database, .. }, .. } if database == "raw-events"));
+        assert!(parse_ddl("GRANT SELECT ON ALL TABLES IN sales TO ROLE analyst").is_err());
+    }
+
+    #[test]
+    fn test_quoted_identifiers() {
+        let table = |sql: &str| parse_ddl(sql).unwrap().to_permission().unwrap().resource;
+        assert_eq!(
+            table(r#"GRANT SELECT ON "raw-events"."2024_orders" TO ROLE analyst"#),
+            Resource::Table {
+                database: "raw-events".to_string(),
+                table: "2024_orders".to_string(),
+                columns: None,
+                catalog_id: None,
+            },
+        );
+        assert_eq!(
+            table(r#"GRANT SELECT ON sales."order ""items""" ("unit price") TO ROLE analyst"#),
+            Resource::Table {
+                database: "sales".to_string(),
+                table: "order \"items\"".to_string(),
+                columns: Some(vec!["unit price".to_string()]),
+                catalog_id: None,
+            },
+        );
+        assert_eq!(
+            table(r#"GRANT DESCRIBE ON DATABASE "raw-events" TO ROLE analyst"#),
+            Resource::Database { name: "raw-events".to_string(), catalog_id: None },
+        );
+        assert!(parse_ddl("GRANT SELECT ON raw-events.orders TO ROLE analyst").is_err());
+    }
+
+    #[test]
+    fn test_row_filter_ast() {
+        let column = |name: &str| FilterOperand::Column(name.to_string());
+        let region_check = FilterExpr::Comparison {
+            left: column("region"),
+            op: ComparisonOp::Eq,
+            right: FilterOperand::SessionContext { key: "user_region".to_string(), default: None },
+        };
+        let amount_check = |op, amount: &str| FilterExpr::Comparison {
+            left: column("amount"),
+            op,
+            right: FilterOperand::Number(amount.to_string()),
+        };
+
+        let sql = "GRANT SELECT ON sales.orders TO ROLE analyst WHERE region = SESSION_CONTEXT('user_region') AND amount > 100";
+        match parse_ddl(sql).unwrap() {
+            DdlStatement::Grant { row_filter: Some(filter), .. } => {
+                assert_eq!(filter.expression, "WHERE region = SESSION_CONTEXT('user_region') AND amount > 100");
+                assert_eq!(filter.ast, Some(FilterExpr::And(
+                    Box::new(region_check.clone()),
+                    Box::new(amount_check(ComparisonOp::Gt, "100")),
+                )));
+            },
+            other => panic!("Expected Grant with a row filter, got {:?}", other),
+        }
+
+        // AND binds tighter than OR; parentheses override it
+        let status = FilterExpr::Comparison {
+            left: column("status"),
+            op: ComparisonOp::NotEq,
+            right: FilterOperand::Literal("closed".to_string()),
+        };
+        assert_eq!(
+            parse_filter_expression("status <> 'closed' OR region = SESSION_CONTEXT('user_region') AND amount >= 5").unwrap(),
+            FilterExpr::Or(
+                Box::new(status.clone()),
+                Box::new(FilterExpr::And(Box::new(region_check.clone()), Box::new(amount_check(ComparisonOp::GtEq, "5")))),
+            ),
+        );
+        assert_eq!(
+            parse_filter_expression("WHERE (status != 'closed' OR region = SESSION_CONTEXT('user_region')) AND amount <= 5").unwrap(),
+            FilterExpr::And(
+                Box::new(FilterExpr::Or(Box::new(status), Box::new(region_check))),
+                Box::new(amount_check(ComparisonOp::LtEq, "5")),
+            ),
+        );
+
+        // WHEN conditions are parsed too
+        match parse_ddl("GRANT SELECT ON sales.orders TO ROLE analyst WHEN SESSION_CONTEXT('mfa', 'false') = 'true'").unwrap() {
+            DdlStatement::Grant { principal_condition: Some(condition), .. } => {
+                assert_eq!(condition.ast, Some(FilterExpr::Comparison {
+                    left: FilterOperand::SessionContext {
+                        key: "mfa".to_string(),
+                        default: Some(Box::new(FilterOperand::Literal("false".to_string()))),
+                    },
+                    op: ComparisonOp::Eq,
+                    right: FilterOperand::Literal("true".to_string()),
+                }));
+            },
+            other => panic!("Expected Grant with a condition, got {:?}", other),
+        }
+
+        // Custom function calls, on either side or as a term of their own
+        assert_eq!(
+            parse_filter_expression("region = upper('west') OR NOT in_region_group(region, 'emea')").unwrap(),
+            FilterExpr::Or(
+                Box::new(FilterExpr::Comparison {
+                    left: column("region"),
+                    op: ComparisonOp::Eq,
+                    right: FilterOperand::Function {
+                        name: "upper".to_string(),
+                        args: vec![FilterOperand::Literal("west".to_string())],
+                    },
+                }),
+                Box::new(FilterExpr::Not(Box::new(FilterExpr::Value(FilterOperand::Function {
+                    name: "in_region_group".to_string(),
+                    args: vec![column("region"), FilterOperand::Literal("emea".to_string())],
+                })))),
+            ),
+        );
+        assert!(parse_filter_expression("region = upper('west'").is_err());
+        assert!(parse_filter_expression("region").is_err());
+    }
+
+    #[test]
+    fn test_is_null_filter() {
+        let manager = FilterOperand::Column("manager".to_string());
+        assert_eq!(
+            parse_filter_expression("manager IS NULL").unwrap(),
+            FilterExpr::IsNull { operand: manager.clone(), negated: false },
+        );
+        assert_eq!(
+            parse_filter_expression("NOT manager is not null").unwrap(),
+            FilterExpr::Not(Box::new(FilterExpr::IsNull { operand: manager, negated: true })),
+        );
+        assert_eq!(
+            parse_filter_expression("SESSION_CONTEXT('mfa') IS NOT NULL").unwrap(),
+            FilterExpr::IsNull {
+                operand: FilterOperand::SessionContext { key: "mfa".to_string(), default: None },
+                negated: true,
+            },
+        );
+        assert!(parse_filter_expression("manager IS 'x'").is_err());
+        assert!(parse_filter_expression("manager IS NOT").is_err());
+    }
+
+    #[test]
+    fn test_current_user_and_time_filter() {
+        assert_eq!(
+            parse_filter_expression("owner = current_user AND created_at <= CURRENT_TIMESTAMP").unwrap(),
+            FilterExpr::And(
+                Box::new(FilterExpr::Comparison {
+                    left: FilterOperand::Column("owner".to_string()),
+                    op: ComparisonOp::Eq,
+                    right: FilterOperand::CurrentUser,
+                }),
+                Box::new(FilterExpr::Comparison {
+                    left: FilterOperand::Column("created_at".to_string()),
+                    op: ComparisonOp::LtEq,
+                    right: FilterOperand::CurrentTimestamp,
+                }),
+            ),
+        );
+        assert_eq!(
+            parse_filter_expression("CURRENT_DATE > day").unwrap(),
+            FilterExpr::Comparison {
+                left: FilterOperand::CurrentDate,
+                op: ComparisonOp::Gt,
+                right: FilterOperand::Column("day".to_string()),
+            },
+        );
+        // Only the whole word is the keyword
+        assert_eq!(
+            parse_filter_expression("current_user_id = 'ana'").unwrap(),
+            FilterExpr::Comparison {
+                left: FilterOperand::Column("current_user_id".to_string()),
+                op: ComparisonOp::Eq,
+                right: FilterOperand::Literal("ana".to_string()),
+            },
+        );
+    }
+
+    #[test]
+    fn test_filter_precedence() {
+        let eq = |name: &str, value: &str| FilterExpr::Comparison {
+            left: FilterOperand::Column(name.to_string()),
+            op: ComparisonOp::Eq,
+            right: FilterOperand::Literal(value.to_string()),
+        };
+        let and = |l, r| FilterExpr::And(Box::new(l), Box::new(r));
+        let or = |l, r| FilterExpr::Or(Box::new(l), Box::new(r));
+        let not = |e| FilterExpr::Not(Box::new(e));
+
+        assert_eq!(
+            parse_filter_expression("(a = '1' OR b = '2') AND c = '3'").unwrap(),
+            and(or(eq("a", "1"), eq("b", "2")), eq("c", "3")),
+        );
+        assert_eq!(
+            parse_filter_expression("a = '1' OR b = '2' AND c = '3'").unwrap(),
+            or(eq("a", "1"), and(eq("b", "2"), eq("c", "3"))),
+        );
+        // NOT binds tighter than AND
+        assert_eq!(
+            parse_filter_expression("NOT a = '1' AND b = '2'").unwrap(),
+            and(not(eq("a", "1")), eq("b", "2")),
+        );
+        assert_eq!(
+            parse_filter_expression("NOT (a = '1' AND b = '2')").unwrap(),
+            not(and(eq("a", "1"), eq("b", "2"))),
+        );
+        // Delimiters inside literals stay part of the literal
+        assert_eq!(parse_filter_expression("name = 'A AND B'").unwrap(), eq("name", "A AND B"));
+        // Keywords only match whole words
+        assert_eq!(parse_filter_expression("notes = 'x'").unwrap(), eq("notes", "x"));
+        assert_eq!(
+            parse_filter_expression("nullable = NULL").unwrap(),
+            FilterExpr::Comparison {
+                left: FilterOperand::Column("nullable".to_string()),
+                op: ComparisonOp::Eq,
+                right: FilterOperand::Null,
+            },
+        );
+        // Columns may be compared with each other
+        assert_eq!(
+            parse_filter_expression("owner = t.manager").unwrap(),
+            FilterExpr::Comparison {
+                left: FilterOperand::Column("owner".to_string()),
+                op: ComparisonOp::Eq,
+                right: FilterOperand::Column("t.manager".to_string()),
+            },
+        );
+    }
+
+    #[test]
+    fn test_like_filter() {
+        let like = |op, pattern: &str| FilterExpr::Comparison {
+            left: FilterOperand::Column("email".to_string()),
+            op,
+            right: FilterOperand::Literal(pattern.to_string()),
+        };
+
+        assert_eq!(
+            parse_filter_expression("email LIKE '%@company.com'").unwrap(),
+            like(ComparisonOp::Like, "%@company.com"),
+        );
+        assert_eq!(
+            parse_filter_expression("email not  like '%@contractor.com'").unwrap(),
+            like(ComparisonOp::NotLike, "%@contractor.com"),
+        );
+        assert!(parse_filter_expression("email NOT '%@company.com'").is_err());
+    }
+
+    #[test]
+    fn test_in_list_filter() {
+        let literal = |value: &str| FilterOperand::Literal(value.to_string());
+
+        assert_eq!(
+            parse_filter_expression("region IN ('west','central')").unwrap(),
+            FilterExpr::In {
+                left: FilterOperand::Column("region".to_string()),
+                list: vec![literal("west"), literal("central")],
+            },
+        );
+        assert_eq!(
+            parse_filter_expression("WHERE department in ( SESSION_CONTEXT('dept') , 'shared' ) AND level IN (1, 2)").unwrap(),
+            FilterExpr::And(
+                Box::new(FilterExpr::In {
+                    left: FilterOperand::Column("department".to_string()),
+                    list: vec![
+                        FilterOperand::SessionContext { key: "dept".to_string(), default: None },
+                        literal("shared"),
+                    ],
+                }),
+                Box::new(FilterExpr::In {
+                    left: FilterOperand::Column("level".to_string()),
+                    list: vec![FilterOperand::Number("1".to_string()), FilterOperand::Number("2".to_string())],
+                }),
+            ),
+        );
+
+        // Conditions can test a session value against a list
+        match parse_ddl("GRANT SELECT ON sales.orders TO ROLE analyst WHERE region IN ('west') WHEN SESSION_CONTEXT('team') IN ('a', 'b')").unwrap() {
+            DdlStatement::Grant { row_filter: Some(filter), principal_condition: Some(condition), .. } => {
+                assert_eq!(filter.expression, "WHERE region IN ('west')");
+                assert!(matches!(filter.ast, Some(FilterExpr::In { ref list, .. }) if list.len() == 1));
+                assert!(matches!(condition.ast, Some(FilterExpr::In { ref list, .. }) if list.len() == 2));
+            },
+            other => panic!("Expected Grant with a filter and condition, got {:?}", other),
+        }
+
+        assert_eq!(
+            parse_filter_expression("region IN SESSION_CONTEXT('allowed_regions')").unwrap(),
+            FilterExpr::In {
+                left: FilterOperand::Column("region".to_string()),
+                list: vec![FilterOperand::SessionContext { key: "allowed_regions".to_string(), default: None }],
+            },
+        );
+
+        assert!(parse_filter_expression("region IN ()").is_err());
+        assert!(parse_filter_expression("region IN 'west'").is_err());
+        assert!(parse_filter_expression("region IN ('west',)").is_err());
+    }
+
+    #[test]
+    fn test_parse_error_points_at_token() {
+        let err = parse_ddl("GRANT SELECT sales.orders TO ROLE analyst").unwrap_err();
+        let parse_error = err.downcast_ref::<ParseError>().expect("ParseError");
+        assert_eq!((parse_error.line, parse_error.column), (1, 14));
+        assert_eq!(
+            parse_error.to_string(),
+            "unexpected token at line 1 col 14: expected ON or WITH\n  GRANT SELECT sales.orders TO ROLE analyst\n               ^",
+        );
+
+        let err = parse_ddl("CREATE ROLE analyst;\nGRANT SELECT ON sales.orders TO").unwrap_err();
+        let parse_error = err.downcast_ref::<ParseError>().expect("ParseError");
+        assert_eq!((parse_error.statement_index, parse_error.line), (1, 2));
+        assert!(parse_error.to_string().starts_with("unexpected end of input at line 2 col 32"));
+    }
+}
@@ -86,6 +86,7 @@ async fn test_backend(config: BackendConfig, statements: &[&str]) -> Result<()>
         database: "sales".to_string(),
         table: "orders".to_string(),
         columns: None,
+        catalog_id: None,
     };
 
     let has_select = backend.check_permissions(&user_alice, &sales_orders, &Action::Select).await?;